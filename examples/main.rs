@@ -2,7 +2,7 @@ use starling::constants::KEY_LEN;
 use starling::merkle_bit::BinaryMerkleTreeResult;
 
 fn main() -> BinaryMerkleTreeResult<()> {
-    let mut tree = starling::hash_tree::HashTree::new(16)?;
+    let mut tree = starling::hash_tree::HashTree::<Vec<u8>>::new(16)?;
 
     let key = [0x00; KEY_LEN];
     let value = vec![0x00; KEY_LEN];
@@ -1,8 +1,12 @@
-/// The number of bytes in the key.
+/// The default number of bytes in a key.
 pub const KEY_LEN: usize = 32;
-/// The number of bytes in the key as a u8.
-pub const KEY_LEN_BYTES: u8 = KEY_LEN as u8;
-/// The number of bits in the key.
-pub const KEY_LEN_BITS: u8 = (KEY_LEN_BYTES as u16 * 8 - 1) as u8;
+/// The default number of bytes in a hash, which locates each node in the database.
+pub const HASH_LEN: usize = 32;
+/// The largest number of bytes in a key, as split indexes are held in a `u8`.
+pub const MAX_KEY_LEN: usize = 32;
 /// These constants are used to quickly calculate the values of log2.
-pub const MULTIPLY_DE_BRUIJN_BIT_POSITION: [u8; 8] = [0, 5, 1, 6, 4, 3, 2, 7];
\ No newline at end of file
+pub const MULTIPLY_DE_BRUIJN_BIT_POSITION: [u8; 8] = [0, 5, 1, 6, 4, 3, 2, 7];
+/// The version of the byte encoding used for proofs.
+pub const PROOF_ENCODING_VERSION: u8 = 2;
+/// The first version of the byte encoding used for proofs, which only held 32 byte keys and hashes.
+pub const LEGACY_PROOF_ENCODING_VERSION: u8 = 1;
@@ -1,33 +1,61 @@
 #[cfg(not(any(feature = "use_hashbrown")))]
 use std::collections::HashMap;
+use std::ops::RangeBounds;
 use std::path::PathBuf;
 
 #[cfg(feature = "use_hashbrown")]
 use hashbrown::HashMap;
 
-use crate::constants::KEY_LEN;
 use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT};
-use crate::traits::{Decode, Encode};
+use crate::proof::absence_proof::AbsenceProof;
+use crate::proof::inclusion_proof::InclusionProof;
+use crate::proof::multi_proof::MultiProof;
+use crate::proof::range_proof::RangeProof;
+use crate::traits::{Database, Decode, Encode, Hasher};
 use crate::tree::tree_branch::TreeBranch;
 use crate::tree::tree_data::TreeData;
 use crate::tree::tree_leaf::TreeLeaf;
 use crate::tree::tree_node::TreeNode;
 use crate::tree_db::HashTreeDB;
+use crate::tree_hasher::tagged_scheme::TaggedScheme;
 use crate::tree_hasher::TreeHasher;
+use crate::utils::changeset::Changeset;
+use crate::utils::check_report::CheckReport;
+use crate::utils::tree_diff::TreeDiff;
+use crate::utils::tree_iter::TreeIter;
+use crate::utils::write_batch::WriteBatch;
 
 /// A `MerkleBIT` implemented with a `HashMap`.  Can be used for quickly storing items in memory, though
-/// larger sets of items should be stored on disk or over the network in a real database.
-pub struct HashTree<ValueType>
-where
+/// larger sets of items should be stored on disk or over the network in a real database.  The hash
+/// function is chosen with `HasherType`, which defaults to `TreeHasher`.
+pub struct HashTree<
+    ValueType,
+    const KEY_LEN: usize = 32,
+    const HASH_LEN: usize = 32,
+    HasherType = TreeHasher,
+> where
     ValueType: Encode + Decode + Sync + Send,
+    HasherType: Hasher<HASH_LEN>,
 {
     /// The underlying tree.  The type requirements have already been implemented for easy use.
-    tree: MerkleBIT<HashTreeDB, TreeBranch, TreeLeaf, TreeData, TreeNode, TreeHasher, ValueType>,
+    tree: MerkleBIT<
+        HashTreeDB<KEY_LEN, HASH_LEN>,
+        TreeBranch<KEY_LEN, HASH_LEN>,
+        TreeLeaf<KEY_LEN, HASH_LEN>,
+        TreeData,
+        TreeNode<KEY_LEN, HASH_LEN>,
+        HasherType,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    >,
 }
 
-impl<ValueType> HashTree<ValueType>
+impl<ValueType, const KEY_LEN: usize, const HASH_LEN: usize, HasherType>
+    HashTree<ValueType, KEY_LEN, HASH_LEN, HasherType>
 where
     ValueType: Encode + Decode + Sync + Send,
+    HasherType: Hasher<HASH_LEN, HashType = HasherType>,
 {
     /// Creates a new `HashTree`.  `depth` indicates the maximum depth of the tree.
     #[inline]
@@ -37,6 +65,18 @@ where
         Ok(Self { tree })
     }
 
+    /// Creates a new `HashTree` that calculates the locations of nodes with `scheme`.  `depth`
+    /// indicates the maximum depth of the tree.
+    #[inline]
+    pub fn with_scheme(
+        depth: usize,
+        scheme: TaggedScheme<HasherType>,
+    ) -> BinaryMerkleTreeResult<Self> {
+        let db = HashTreeDB::open(&PathBuf::new())?;
+        let tree = MerkleBIT::from_db_with_scheme(db, depth, scheme)?;
+        Ok(Self { tree })
+    }
+
     /// Creates a new `HashTree`.  This method exists for conforming with the general API for the `MerkleBIT`
     /// and does not need to be used (except for compatibility).  Prefer `new` when possible.
     #[inline]
@@ -49,28 +89,255 @@ where
     #[inline]
     pub fn get<'a>(
         &self,
-        root_hash: &[u8; KEY_LEN],
+        root_hash: &[u8; HASH_LEN],
         keys: &mut [&'a [u8; KEY_LEN]],
     ) -> BinaryMerkleTreeResult<HashMap<&'a [u8; KEY_LEN], Option<ValueType>>> {
         self.tree.get(root_hash, keys)
     }
 
+    /// Iterates over the keys and values held under `root_hash` in ascending order of keys.
+    #[inline]
+    pub fn iter(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+    ) -> TreeIter<
+        '_,
+        HashTreeDB<KEY_LEN, HASH_LEN>,
+        TreeBranch<KEY_LEN, HASH_LEN>,
+        TreeLeaf<KEY_LEN, HASH_LEN>,
+        TreeData,
+        TreeNode<KEY_LEN, HASH_LEN>,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    > {
+        self.tree.iter(root_hash)
+    }
+
+    /// Iterates over the keys and values held under `root_hash` with keys in `range`, in ascending
+    /// order of keys.
+    #[inline]
+    pub fn range<RangeType>(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        range: RangeType,
+    ) -> TreeIter<
+        '_,
+        HashTreeDB<KEY_LEN, HASH_LEN>,
+        TreeBranch<KEY_LEN, HASH_LEN>,
+        TreeLeaf<KEY_LEN, HASH_LEN>,
+        TreeData,
+        TreeNode<KEY_LEN, HASH_LEN>,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    >
+    where
+        RangeType: RangeBounds<[u8; KEY_LEN]>,
+    {
+        self.tree.range(root_hash, range)
+    }
+
+    /// Iterates over the keys and values held under `root_hash` whose keys share the first
+    /// `prefix_bits` bits of `prefix`, in ascending order of keys.
+    #[inline]
+    pub fn prefix(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        prefix: &[u8; KEY_LEN],
+        prefix_bits: usize,
+    ) -> TreeIter<
+        '_,
+        HashTreeDB<KEY_LEN, HASH_LEN>,
+        TreeBranch<KEY_LEN, HASH_LEN>,
+        TreeLeaf<KEY_LEN, HASH_LEN>,
+        TreeData,
+        TreeNode<KEY_LEN, HASH_LEN>,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    > {
+        self.tree.prefix(root_hash, prefix, prefix_bits)
+    }
+
+    /// Gets the number of keys held under `root_hash`.  Returns 0 if the root could not be found.
+    #[inline]
+    pub fn len(&self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<u64> {
+        self.tree.len(root_hash)
+    }
+
+    /// Gets the key and value at position `index` in ascending order of keys under `root_hash`.
+    /// Returns `None` if there are not more than `index` keys in the tree.
+    #[inline]
+    pub fn nth(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        index: u64,
+    ) -> BinaryMerkleTreeResult<Option<([u8; KEY_LEN], ValueType)>> {
+        self.tree.nth(root_hash, index)
+    }
+
+    /// Gets the number of keys under `root_hash` that are less than `key`.
+    #[inline]
+    pub fn rank(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        key: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<u64> {
+        self.tree.rank(root_hash, key)
+    }
+
+    /// Finds the keys that were added, removed, or changed going from `old_root` to `new_root`.
+    #[inline]
+    pub fn diff(
+        &self,
+        old_root: &[u8; HASH_LEN],
+        new_root: &[u8; HASH_LEN],
+    ) -> BinaryMerkleTreeResult<TreeDiff<ValueType, [u8; KEY_LEN]>> {
+        self.tree.diff(old_root, new_root)
+    }
+
+    /// Generates a proof that `key` is held under `root_hash`.  Returns `None` if the key is not
+    /// in the tree.
+    #[inline]
+    pub fn get_proof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        key: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<Option<InclusionProof<KEY_LEN, HASH_LEN>>> {
+        self.tree.get_proof(root_hash, key)
+    }
+
+    /// Generates a proof that `key` is not held under `root_hash`.  Returns `None` if the key is
+    /// in the tree, or if the root could not be found.  Returns an error unless the tree was
+    /// created with a scheme that commits to the position of branches.
+    #[inline]
+    pub fn get_absence_proof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        key: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<Option<AbsenceProof<KEY_LEN, HASH_LEN>>> {
+        self.tree.get_absence_proof(root_hash, key)
+    }
+
+    /// Generates a single proof for the presence or absence of each of `keys` under `root_hash`.
+    /// Returns `None` if the root could not be found.  Returns an error if any of `keys` is absent,
+    /// unless the tree was created with a scheme that commits to the position of branches.
+    #[inline]
+    pub fn get_multiproof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        keys: &mut [&[u8; KEY_LEN]],
+    ) -> BinaryMerkleTreeResult<Option<MultiProof<KEY_LEN, HASH_LEN>>> {
+        self.tree.get_multiproof(root_hash, keys)
+    }
+
+    /// Generates a proof that the returned keys and values are every entry under `root_hash` with
+    /// a key between `start` and `end` inclusive.  Returns `None` if the root could not be found.
+    #[inline]
+    pub fn get_range_proof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        start: &[u8; KEY_LEN],
+        end: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<
+        Option<(
+            Vec<([u8; KEY_LEN], ValueType)>,
+            RangeProof<KEY_LEN, HASH_LEN>,
+        )>,
+    > {
+        self.tree.get_range_proof(root_hash, start, end)
+    }
+
     /// Inserts elements into the tree.  Using `previous_root` specifies that the insert depends on
     /// the state from the previous root, and will update references accordingly.
     #[inline]
     pub fn insert(
         &mut self,
-        previous_root: Option<&[u8; KEY_LEN]>,
+        previous_root: Option<&[u8; HASH_LEN]>,
         keys: &mut [&[u8; KEY_LEN]],
         values: &mut [&ValueType],
-    ) -> BinaryMerkleTreeResult<[u8; KEY_LEN]> {
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
         self.tree.insert(previous_root, keys, values)
     }
 
+    /// Deletes elements from the tree at `previous_root`.  Returns the new root, or `None` if no
+    /// elements remain.
+    #[inline]
+    pub fn delete(
+        &mut self,
+        previous_root: &[u8; HASH_LEN],
+        keys: &mut [&[u8; KEY_LEN]],
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        self.tree.delete(previous_root, keys)
+    }
+
+    /// Applies the changes in `batch` to the tree at `previous_root` in a single pass.  Returns the
+    /// new root, or `None` if no elements remain.
+    #[inline]
+    pub fn apply(
+        &mut self,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        batch: &WriteBatch<ValueType, KEY_LEN>,
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        self.tree.apply(previous_root, batch)
+    }
+
+    /// Exports the changes going from `previous_root` to `new_root` as a `Changeset`.
+    #[inline]
+    pub fn export_changeset(
+        &self,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        new_root: &[u8; HASH_LEN],
+    ) -> BinaryMerkleTreeResult<Changeset<ValueType, KEY_LEN, HASH_LEN>> {
+        self.tree.export_changeset(previous_root, new_root)
+    }
+
+    /// Checks the references of every node held under `live_roots`, and finds nodes that are
+    /// unreachable, missing, or corrupt.  With `repair`, mismatched references are rewritten.
+    #[inline]
+    pub fn check(
+        &mut self,
+        live_roots: &[[u8; HASH_LEN]],
+        repair: bool,
+    ) -> BinaryMerkleTreeResult<CheckReport<HASH_LEN>> {
+        self.tree.check(live_roots, repair)
+    }
+
+    /// Applies `changeset` on top of its previous root, checking that it produces its new root.
+    #[inline]
+    pub fn apply_changeset(
+        &mut self,
+        changeset: Changeset<ValueType, KEY_LEN, HASH_LEN>,
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
+        self.tree.apply_changeset(changeset)
+    }
+
+    /// Merges the changes made from `base` to `theirs` into `ours`, passing keys changed
+    /// differently on both sides to `resolver`.  Returns the merged root.
+    #[inline]
+    pub fn merge<ResolverType>(
+        &mut self,
+        base: &[u8; HASH_LEN],
+        ours: &[u8; HASH_LEN],
+        theirs: &[u8; HASH_LEN],
+        resolver: ResolverType,
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>>
+    where
+        ResolverType: FnMut(
+            &[u8; KEY_LEN],
+            Option<&ValueType>,
+            Option<&ValueType>,
+            Option<&ValueType>,
+        ) -> BinaryMerkleTreeResult<Option<ValueType>>,
+    {
+        self.tree.merge(base, ours, theirs, resolver)
+    }
+
     /// Removes a root from the tree.  This will remove all elements with less than two references
     /// under the given root.
     #[inline]
-    pub fn remove(&mut self, root_hash: &[u8; KEY_LEN]) -> BinaryMerkleTreeResult<()> {
+    pub fn remove(&mut self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<()> {
         self.tree.remove(root_hash)
     }
 }
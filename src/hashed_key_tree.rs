@@ -0,0 +1,551 @@
+use std::collections::BTreeMap;
+#[cfg(not(any(feature = "use_hashbrown")))]
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+#[cfg(feature = "use_hashbrown")]
+use hashbrown::HashMap;
+
+use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT};
+use crate::traits::{Database, Decode, Encode, Exception, Hasher};
+use crate::tree::tree_branch::TreeBranch;
+use crate::tree::tree_data::TreeData;
+use crate::tree::tree_leaf::TreeLeaf;
+use crate::tree::tree_node::TreeNode;
+use crate::tree_hasher::TreeHasher;
+use crate::utils::check_report::CheckReport;
+use crate::utils::keyed_value::KeyedValue;
+use crate::utils::tree_diff::TreeDiff;
+
+/// A `MerkleBIT` that accepts keys of any length.  Each key is hashed into the fixed key space of
+/// the tree, and the original key is stored next to its value so that it can be given back when
+/// iterating or comparing roots.
+pub struct HashedKeyTree<
+    DatabaseType,
+    ValueType,
+    const KEY_LEN: usize = 32,
+    const HASH_LEN: usize = 32,
+    HasherType = TreeHasher,
+> where
+    DatabaseType: Database<HASH_LEN, NodeType = TreeNode<KEY_LEN, HASH_LEN>>,
+    ValueType: Encode + Decode,
+    HasherType: Hasher<HASH_LEN>,
+{
+    /// The underlying tree, holding each value along with its original key.
+    tree: MerkleBIT<
+        DatabaseType,
+        TreeBranch<KEY_LEN, HASH_LEN>,
+        TreeLeaf<KEY_LEN, HASH_LEN>,
+        TreeData,
+        TreeNode<KEY_LEN, HASH_LEN>,
+        HasherType,
+        KeyedValue,
+        KEY_LEN,
+        HASH_LEN,
+    >,
+    /// Marker for dealing with `ValueType`.
+    value: PhantomData<*const ValueType>,
+}
+
+#[cfg(not(feature = "use_rayon"))]
+impl<DatabaseType, ValueType, const KEY_LEN: usize, const HASH_LEN: usize, HasherType>
+    HashedKeyTree<DatabaseType, ValueType, KEY_LEN, HASH_LEN, HasherType>
+where
+    DatabaseType: Database<HASH_LEN, NodeType = TreeNode<KEY_LEN, HASH_LEN>>,
+    ValueType: Encode + Decode,
+    HasherType: Hasher<HASH_LEN, HashType = HasherType>,
+{
+    /// Opens the database at `path` and creates a new `HashedKeyTree` from it.  `depth` indicates
+    /// the maximum depth of the tree.
+    #[inline]
+    pub fn open(path: &PathBuf, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let db = DatabaseType::open(path)?;
+        Self::from_db(db, depth)
+    }
+
+    /// Creates a new `HashedKeyTree` from an already opened database.
+    #[inline]
+    pub fn from_db(db: DatabaseType, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        if KEY_LEN > HASH_LEN {
+            return Err(Exception::new(
+                "Key length must not exceed hash length for hashed keys",
+            ));
+        }
+        let tree = MerkleBIT::from_db(db, depth)?;
+        Ok(Self {
+            tree,
+            value: PhantomData,
+        })
+    }
+
+    /// Hashes `key` into the key space of the tree.  The hash is taken with the tree's hasher and
+    /// cut down to `KEY_LEN` bytes.
+    #[inline]
+    pub fn hash_key(key: &[u8]) -> [u8; KEY_LEN] {
+        let mut key_hasher = <HasherType as Hasher<HASH_LEN>>::new(HASH_LEN);
+        key_hasher.update(b"k");
+        key_hasher.update(key);
+        let hash = key_hasher.finalize();
+
+        let mut hashed_key = [0; KEY_LEN];
+        hashed_key.copy_from_slice(&hash[..KEY_LEN]);
+        hashed_key
+    }
+
+    /// Gets the values associated with `keys` from the tree.
+    #[inline]
+    pub fn get<'a, KeyType>(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        keys: &'a [KeyType],
+    ) -> BinaryMerkleTreeResult<HashMap<&'a [u8], Option<ValueType>>>
+    where
+        KeyType: AsRef<[u8]>,
+    {
+        let hashed_keys = keys
+            .iter()
+            .map(|key| Self::hash_key(key.as_ref()))
+            .collect::<Vec<_>>();
+        let mut hashed_key_refs = hashed_keys.iter().collect::<Vec<_>>();
+        let stored = self.tree.get(root_hash, &mut hashed_key_refs)?;
+
+        let mut items = HashMap::new();
+        for (key, hashed_key) in keys.iter().zip(hashed_keys.iter()) {
+            let value = match stored.get(hashed_key) {
+                // A different key stored under the same hash does not count as a match
+                Some(Some(keyed_value)) if keyed_value.key == key.as_ref() => {
+                    Some(ValueType::decode(&keyed_value.value)?)
+                }
+                _ => None,
+            };
+            items.insert(key.as_ref(), value);
+        }
+        Ok(items)
+    }
+
+    /// Iterates over the keys and values held under `root_hash`, in ascending order of the hashes
+    /// of the keys.
+    #[inline]
+    pub fn iter(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+    ) -> impl Iterator<Item = BinaryMerkleTreeResult<(Vec<u8>, ValueType)>> + '_ {
+        self.tree
+            .iter(root_hash)
+            .map(|item| Self::decode_entry(item?.1))
+    }
+
+    /// Gets the number of keys held under `root_hash`.  Returns 0 if the root could not be found.
+    #[inline]
+    pub fn len(&self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<u64> {
+        self.tree.len(root_hash)
+    }
+
+    /// Finds the keys that were added, removed, or changed going from `old_root` to `new_root`.
+    /// Each list is in ascending order of the hashes of the keys.
+    #[inline]
+    pub fn diff(
+        &self,
+        old_root: &[u8; HASH_LEN],
+        new_root: &[u8; HASH_LEN],
+    ) -> BinaryMerkleTreeResult<TreeDiff<ValueType, Vec<u8>>> {
+        let hashed_diff = self.tree.diff(old_root, new_root)?;
+
+        let mut diff = TreeDiff::new();
+        for (_, keyed_value) in hashed_diff.added {
+            diff.added.push(Self::decode_entry(keyed_value)?);
+        }
+        for (_, keyed_value) in hashed_diff.removed {
+            diff.removed.push(Self::decode_entry(keyed_value)?);
+        }
+        for (_, old_keyed_value, new_keyed_value) in hashed_diff.changed {
+            let (key, old_value) = Self::decode_entry(old_keyed_value)?;
+            let new_value = ValueType::decode(&new_keyed_value.value)?;
+            diff.changed.push((key, old_value, new_value));
+        }
+        Ok(diff)
+    }
+
+    /// Inserts elements into the tree.  Using `previous_root` specifies that the insert depends on
+    /// the state from the previous root.  Fails if a key hashes to the same location as a
+    /// different key.
+    #[inline]
+    pub fn insert<KeyType>(
+        &mut self,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        keys: &[KeyType],
+        values: &[&ValueType],
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]>
+    where
+        KeyType: AsRef<[u8]>,
+    {
+        if keys.len() != values.len() {
+            return Err(Exception::new("Keys and values have different lengths"));
+        }
+
+        let mut entries = BTreeMap::new();
+        for (key, value) in keys.iter().zip(values.iter()) {
+            let hashed_key = Self::hash_key(key.as_ref());
+            if entries
+                .get(&hashed_key)
+                .map_or(false, |entry: &KeyedValue| entry.key != key.as_ref())
+            {
+                return Err(Exception::new("Keys collide when hashed"));
+            }
+            entries.insert(
+                hashed_key,
+                KeyedValue::new(key.as_ref().to_vec(), value.encode()?),
+            );
+        }
+
+        if let Some(root) = previous_root {
+            let hashed_keys = entries.keys().copied().collect::<Vec<_>>();
+            let stored_keys = self.stored_keys(root, &hashed_keys)?;
+            for (hashed_key, entry) in &entries {
+                if stored_keys
+                    .get(hashed_key)
+                    .map_or(false, |stored_key| *stored_key != entry.key)
+                {
+                    return Err(Exception::new("Keys collide when hashed"));
+                }
+            }
+        }
+
+        let mut hashed_keys = entries.keys().collect::<Vec<_>>();
+        let mut keyed_values = entries.values().collect::<Vec<_>>();
+        self.tree
+            .insert(previous_root, &mut hashed_keys, &mut keyed_values)
+    }
+
+    /// Deletes elements from the tree at `previous_root`.  Returns the new root, or `None` if no
+    /// elements remain.
+    #[inline]
+    pub fn delete<KeyType>(
+        &mut self,
+        previous_root: &[u8; HASH_LEN],
+        keys: &[KeyType],
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>>
+    where
+        KeyType: AsRef<[u8]>,
+    {
+        if keys.is_empty() {
+            return Err(Exception::new("Keys are empty"));
+        }
+
+        let hashed_keys = keys
+            .iter()
+            .map(|key| Self::hash_key(key.as_ref()))
+            .collect::<Vec<_>>();
+        let stored_keys = self.stored_keys(previous_root, &hashed_keys)?;
+
+        // Only remove hashes that are held for the given key, and not for a colliding key
+        let mut held_keys = keys
+            .iter()
+            .zip(hashed_keys.iter())
+            .filter(|&(key, hashed_key)| {
+                stored_keys
+                    .get(hashed_key)
+                    .map_or(false, |stored_key| stored_key.as_slice() == key.as_ref())
+            })
+            .map(|(_, hashed_key)| hashed_key)
+            .collect::<Vec<_>>();
+        if held_keys.is_empty() {
+            // Nothing is removed, but the returned root still gains a reference as with `MerkleBIT`
+            return self.tree.retain_root(previous_root).map(Some);
+        }
+        self.tree.delete(previous_root, &mut held_keys)
+    }
+
+    /// Removes a root from the tree.  This will remove all elements with less than two references
+    /// under the given root.
+    #[inline]
+    pub fn remove(&mut self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<()> {
+        self.tree.remove(root_hash)
+    }
+
+    /// Checks the references of every node held under `live_roots`, and finds nodes that are
+    /// unreachable, missing, or corrupt.  With `repair`, mismatched references are rewritten.
+    #[inline]
+    pub fn check(
+        &mut self,
+        live_roots: &[[u8; HASH_LEN]],
+        repair: bool,
+    ) -> BinaryMerkleTreeResult<CheckReport<HASH_LEN>> {
+        self.tree.check(live_roots, repair)
+    }
+
+    /// Gets the original keys held under `root_hash` at each of `hashed_keys`, skipping hashes
+    /// that are not in the tree.
+    fn stored_keys(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        hashed_keys: &[[u8; KEY_LEN]],
+    ) -> BinaryMerkleTreeResult<HashMap<[u8; KEY_LEN], Vec<u8>>> {
+        let mut hashed_key_refs = hashed_keys.iter().collect::<Vec<_>>();
+        let stored = self.tree.get(root_hash, &mut hashed_key_refs)?;
+        Ok(stored
+            .into_iter()
+            .filter_map(|(hashed_key, keyed_value)| {
+                keyed_value.map(|stored_value| (*hashed_key, stored_value.key))
+            })
+            .collect())
+    }
+
+    /// Splits a stored `KeyedValue` into its original key and decoded value.
+    fn decode_entry(keyed_value: KeyedValue) -> BinaryMerkleTreeResult<(Vec<u8>, ValueType)> {
+        let value = ValueType::decode(&keyed_value.value)?;
+        Ok((keyed_value.key, value))
+    }
+}
+
+#[cfg(feature = "use_rayon")]
+impl<DatabaseType, ValueType, const KEY_LEN: usize, const HASH_LEN: usize, HasherType>
+    HashedKeyTree<DatabaseType, ValueType, KEY_LEN, HASH_LEN, HasherType>
+where
+    DatabaseType: Database<HASH_LEN, NodeType = TreeNode<KEY_LEN, HASH_LEN>> + Send + Sync,
+    ValueType: Encode + Decode,
+    HasherType: Hasher<HASH_LEN, HashType = HasherType>,
+{
+    /// Opens the database at `path` and creates a new `HashedKeyTree` from it.  `depth` indicates
+    /// the maximum depth of the tree.
+    #[inline]
+    pub fn open(path: &PathBuf, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let db = DatabaseType::open(path)?;
+        Self::from_db(db, depth)
+    }
+
+    /// Creates a new `HashedKeyTree` from an already opened database.
+    #[inline]
+    pub fn from_db(db: DatabaseType, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        if KEY_LEN > HASH_LEN {
+            return Err(Exception::new(
+                "Key length must not exceed hash length for hashed keys",
+            ));
+        }
+        let tree = MerkleBIT::from_db(db, depth)?;
+        Ok(Self {
+            tree,
+            value: PhantomData,
+        })
+    }
+
+    /// Hashes `key` into the key space of the tree.  The hash is taken with the tree's hasher and
+    /// cut down to `KEY_LEN` bytes.
+    #[inline]
+    pub fn hash_key(key: &[u8]) -> [u8; KEY_LEN] {
+        let mut key_hasher = <HasherType as Hasher<HASH_LEN>>::new(HASH_LEN);
+        key_hasher.update(b"k");
+        key_hasher.update(key);
+        let hash = key_hasher.finalize();
+
+        let mut hashed_key = [0; KEY_LEN];
+        hashed_key.copy_from_slice(&hash[..KEY_LEN]);
+        hashed_key
+    }
+
+    /// Gets the values associated with `keys` from the tree.
+    #[inline]
+    pub fn get<'a, KeyType>(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        keys: &'a [KeyType],
+    ) -> BinaryMerkleTreeResult<HashMap<&'a [u8], Option<ValueType>>>
+    where
+        KeyType: AsRef<[u8]>,
+    {
+        let hashed_keys = keys
+            .iter()
+            .map(|key| Self::hash_key(key.as_ref()))
+            .collect::<Vec<_>>();
+        let mut hashed_key_refs = hashed_keys.iter().collect::<Vec<_>>();
+        let stored = self.tree.get(root_hash, &mut hashed_key_refs)?;
+
+        let mut items = HashMap::new();
+        for (key, hashed_key) in keys.iter().zip(hashed_keys.iter()) {
+            let value = match stored.get(hashed_key) {
+                // A different key stored under the same hash does not count as a match
+                Some(Some(keyed_value)) if keyed_value.key == key.as_ref() => {
+                    Some(ValueType::decode(&keyed_value.value)?)
+                }
+                _ => None,
+            };
+            items.insert(key.as_ref(), value);
+        }
+        Ok(items)
+    }
+
+    /// Iterates over the keys and values held under `root_hash`, in ascending order of the hashes
+    /// of the keys.
+    #[inline]
+    pub fn iter(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+    ) -> impl Iterator<Item = BinaryMerkleTreeResult<(Vec<u8>, ValueType)>> + '_ {
+        self.tree
+            .iter(root_hash)
+            .map(|item| Self::decode_entry(item?.1))
+    }
+
+    /// Gets the number of keys held under `root_hash`.  Returns 0 if the root could not be found.
+    #[inline]
+    pub fn len(&self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<u64> {
+        self.tree.len(root_hash)
+    }
+
+    /// Finds the keys that were added, removed, or changed going from `old_root` to `new_root`.
+    /// Each list is in ascending order of the hashes of the keys.
+    #[inline]
+    pub fn diff(
+        &self,
+        old_root: &[u8; HASH_LEN],
+        new_root: &[u8; HASH_LEN],
+    ) -> BinaryMerkleTreeResult<TreeDiff<ValueType, Vec<u8>>> {
+        let hashed_diff = self.tree.diff(old_root, new_root)?;
+
+        let mut diff = TreeDiff::new();
+        for (_, keyed_value) in hashed_diff.added {
+            diff.added.push(Self::decode_entry(keyed_value)?);
+        }
+        for (_, keyed_value) in hashed_diff.removed {
+            diff.removed.push(Self::decode_entry(keyed_value)?);
+        }
+        for (_, old_keyed_value, new_keyed_value) in hashed_diff.changed {
+            let (key, old_value) = Self::decode_entry(old_keyed_value)?;
+            let new_value = ValueType::decode(&new_keyed_value.value)?;
+            diff.changed.push((key, old_value, new_value));
+        }
+        Ok(diff)
+    }
+
+    /// Inserts elements into the tree.  Using `previous_root` specifies that the insert depends on
+    /// the state from the previous root.  Fails if a key hashes to the same location as a
+    /// different key.
+    #[inline]
+    pub fn insert<KeyType>(
+        &mut self,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        keys: &[KeyType],
+        values: &[&ValueType],
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]>
+    where
+        KeyType: AsRef<[u8]>,
+    {
+        if keys.len() != values.len() {
+            return Err(Exception::new("Keys and values have different lengths"));
+        }
+
+        let mut entries = BTreeMap::new();
+        for (key, value) in keys.iter().zip(values.iter()) {
+            let hashed_key = Self::hash_key(key.as_ref());
+            if entries
+                .get(&hashed_key)
+                .map_or(false, |entry: &KeyedValue| entry.key != key.as_ref())
+            {
+                return Err(Exception::new("Keys collide when hashed"));
+            }
+            entries.insert(
+                hashed_key,
+                KeyedValue::new(key.as_ref().to_vec(), value.encode()?),
+            );
+        }
+
+        if let Some(root) = previous_root {
+            let hashed_keys = entries.keys().copied().collect::<Vec<_>>();
+            let stored_keys = self.stored_keys(root, &hashed_keys)?;
+            for (hashed_key, entry) in &entries {
+                if stored_keys
+                    .get(hashed_key)
+                    .map_or(false, |stored_key| *stored_key != entry.key)
+                {
+                    return Err(Exception::new("Keys collide when hashed"));
+                }
+            }
+        }
+
+        let mut hashed_keys = entries.keys().collect::<Vec<_>>();
+        let mut keyed_values = entries.values().collect::<Vec<_>>();
+        self.tree
+            .insert(previous_root, &mut hashed_keys, &mut keyed_values)
+    }
+
+    /// Deletes elements from the tree at `previous_root`.  Returns the new root, or `None` if no
+    /// elements remain.
+    #[inline]
+    pub fn delete<KeyType>(
+        &mut self,
+        previous_root: &[u8; HASH_LEN],
+        keys: &[KeyType],
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>>
+    where
+        KeyType: AsRef<[u8]>,
+    {
+        if keys.is_empty() {
+            return Err(Exception::new("Keys are empty"));
+        }
+
+        let hashed_keys = keys
+            .iter()
+            .map(|key| Self::hash_key(key.as_ref()))
+            .collect::<Vec<_>>();
+        let stored_keys = self.stored_keys(previous_root, &hashed_keys)?;
+
+        // Only remove hashes that are held for the given key, and not for a colliding key
+        let mut held_keys = keys
+            .iter()
+            .zip(hashed_keys.iter())
+            .filter(|&(key, hashed_key)| {
+                stored_keys
+                    .get(hashed_key)
+                    .map_or(false, |stored_key| stored_key.as_slice() == key.as_ref())
+            })
+            .map(|(_, hashed_key)| hashed_key)
+            .collect::<Vec<_>>();
+        if held_keys.is_empty() {
+            // Nothing is removed, but the returned root still gains a reference as with `MerkleBIT`
+            return self.tree.retain_root(previous_root).map(Some);
+        }
+        self.tree.delete(previous_root, &mut held_keys)
+    }
+
+    /// Removes a root from the tree.  This will remove all elements with less than two references
+    /// under the given root.
+    #[inline]
+    pub fn remove(&mut self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<()> {
+        self.tree.remove(root_hash)
+    }
+
+    /// Checks the references of every node held under `live_roots`, and finds nodes that are
+    /// unreachable, missing, or corrupt.  With `repair`, mismatched references are rewritten.
+    #[inline]
+    pub fn check(
+        &mut self,
+        live_roots: &[[u8; HASH_LEN]],
+        repair: bool,
+    ) -> BinaryMerkleTreeResult<CheckReport<HASH_LEN>> {
+        self.tree.check(live_roots, repair)
+    }
+
+    /// Gets the original keys held under `root_hash` at each of `hashed_keys`, skipping hashes
+    /// that are not in the tree.
+    fn stored_keys(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        hashed_keys: &[[u8; KEY_LEN]],
+    ) -> BinaryMerkleTreeResult<HashMap<[u8; KEY_LEN], Vec<u8>>> {
+        let mut hashed_key_refs = hashed_keys.iter().collect::<Vec<_>>();
+        let stored = self.tree.get(root_hash, &mut hashed_key_refs)?;
+        Ok(stored
+            .into_iter()
+            .filter_map(|(hashed_key, keyed_value)| {
+                keyed_value.map(|stored_value| (*hashed_key, stored_value.key))
+            })
+            .collect())
+    }
+
+    /// Splits a stored `KeyedValue` into its original key and decoded value.
+    fn decode_entry(keyed_value: KeyedValue) -> BinaryMerkleTreeResult<(Vec<u8>, ValueType)> {
+        let value = ValueType::decode(&keyed_value.value)?;
+        Ok((keyed_value.key, value))
+    }
+}
@@ -17,8 +17,12 @@
 pub mod constants;
 /// An implementation of the `MerkleBIT` with a `HashMap` backend database.
 pub mod hash_tree;
+/// A tree over the `MerkleBIT` that accepts keys of any length by hashing them.
+pub mod hashed_key_tree;
 /// Contains the actual operations of inserting, getting, and removing items from a tree.
 pub mod merkle_bit;
+/// Contains a collection of structs for proving the contents of a tree.
+pub mod proof;
 /// Contains the traits necessary for tree operations
 pub mod traits;
 /// Contains a collection of structs for representing locations within the tree.
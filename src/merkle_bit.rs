@@ -1,7 +1,8 @@
 #[cfg(not(any(feature = "use_hashbrown")))]
 use std::collections::HashMap;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
 use std::path::PathBuf;
 
 #[cfg(feature = "use_hashbrown")]
@@ -9,19 +10,32 @@ use hashbrown::HashMap;
 #[cfg(feature = "use_rayon")]
 use rayon::prelude::*;
 
-use crate::constants::KEY_LEN;
+use crate::constants::MAX_KEY_LEN;
+use crate::proof::absence_proof::{AbsenceProof, PathEnd};
+use crate::proof::inclusion_proof::InclusionProof;
+use crate::proof::multi_proof::{MultiProof, MultiProofNode};
+use crate::proof::proof_branch::ProofBranch;
+use crate::proof::range_proof::RangeProof;
 use crate::traits::{
-    Branch, Data, Database, Decode, Encode, Exception, Hasher, Leaf, Node, NodeVariant,
+    Branch, Data, Database, Decode, Encode, Exception, Hasher, HashingScheme, Leaf, Node,
+    NodeVariant,
 };
+use crate::tree_hasher::tagged_scheme::TaggedScheme;
+use crate::utils::changeset::Changeset;
+use crate::utils::check_report::CheckReport;
 #[cfg(feature = "use_rayon")]
 use crate::utils::merge_cell::MergeCell;
 use crate::utils::tree_cell::TreeCell;
+use crate::utils::tree_diff::TreeDiff;
+use crate::utils::tree_iter::TreeIter;
 use crate::utils::tree_ref::TreeRef;
 #[cfg(feature = "use_rayon")]
 use crate::utils::tree_ref_raw::TreeRefRaw;
 use crate::utils::tree_utils::{
-    calc_min_split_index, check_descendants, fast_log_2, generate_leaf_map, split_pairs,
+    calc_child_bounds, calc_min_split_index, calc_prefix_bounds, check_descendants, choose_zero,
+    fast_log_2, generate_leaf_map, is_descendant, split_pairs,
 };
+use crate::utils::write_batch::WriteBatch;
 
 /// A generic `Result` from an operation involving a `MerkleBIT`
 pub type BinaryMerkleTreeResult<T> = Result<T, Exception>;
@@ -35,24 +49,42 @@ pub type BinaryMerkleTreeResult<T> = Result<T, Exception>;
 /// * **`NodeType`**: The type used for the outer node that can be either a branch, leaf, or data.  `NodeType` must implement the `Node` trait.
 /// * **`HasherType`**: The type of hasher to use for hashing locations on the tree.  `HasherType` must implement the `Hasher` trait.
 /// * **`ValueType`**: The type to return from a get.  `ValueType` must implement the `Encode` and `Decode` traits.
+/// * **`KEY_LEN`**: The number of bytes in a key, from 1 to 32.  Defaults to 32.
+/// * **`HASH_LEN`**: The number of bytes in a hash, which is the location of a node.  Defaults to 32.
+/// * **`SchemeType`**: The scheme for calculating the locations of nodes.  `SchemeType` must implement the `HashingScheme` trait.  Defaults to `TaggedScheme<HasherType>`.
 /// # Properties
 /// * **db**: The database to store and retrieve values
 /// * **depth**: The maximum permitted depth of the tree.
-#[cfg(not(feature = "use_rayon"))]
-pub struct MerkleBIT<DatabaseType, BranchType, LeafType, DataType, NodeType, HasherType, ValueType>
-where
-    DatabaseType: Database<NodeType = NodeType>,
-    BranchType: Branch,
-    LeafType: Leaf,
+/// * **scheme**: The scheme for calculating the locations of nodes.
+pub struct MerkleBIT<
+    DatabaseType,
+    BranchType,
+    LeafType,
+    DataType,
+    NodeType,
+    HasherType,
+    ValueType,
+    const KEY_LEN: usize = 32,
+    const HASH_LEN: usize = 32,
+    SchemeType = TaggedScheme<HasherType>,
+> where
+    DatabaseType: Database<HASH_LEN, NodeType = NodeType>,
+    BranchType: Branch<KEY_LEN, HASH_LEN>,
+    LeafType: Leaf<KEY_LEN, HASH_LEN>,
     DataType: Data,
     NodeType: Node<BranchType, LeafType, DataType>,
-    HasherType: Hasher,
+    HasherType: Hasher<HASH_LEN>,
     ValueType: Decode + Encode,
 {
     /// The database to store tree nodes.
     db: DatabaseType,
     /// The maximum depth of the tree.
     depth: usize,
+    /// The scheme for calculating the locations of nodes.
+    scheme: SchemeType,
+    /// Whether the database still holds changes from a failed operation that it could not roll
+    /// back.  No changes are written while it does, as they would be written along with them.
+    unreverted: bool,
     /// Marker for dealing with `BranchType`.
     branch: PhantomData<*const BranchType>,
     /// Marker for dealing with `LeafType`.
@@ -67,61 +99,75 @@ where
     value: PhantomData<*const ValueType>,
 }
 
-#[cfg(feature = "use_rayon")]
-pub struct MerkleBIT<DatabaseType, BranchType, LeafType, DataType, NodeType, HasherType, ValueType>
-where
-    DatabaseType: Database<NodeType = NodeType> + Send + Sync,
-    BranchType: Branch,
-    LeafType: Leaf,
-    DataType: Data,
-    NodeType: Node<BranchType, LeafType, DataType> + Send + Sync,
-    HasherType: Hasher,
-    ValueType: Decode + Encode + Sync + Send,
-{
-    db: DatabaseType,
-    depth: usize,
-    branch: PhantomData<*const BranchType>,
-    leaf: PhantomData<*const LeafType>,
-    data: PhantomData<*const DataType>,
-    node: PhantomData<*const NodeType>,
-    hasher: PhantomData<*const HasherType>,
-    value: PhantomData<*const ValueType>,
-}
-
 #[cfg(not(feature = "use_rayon"))]
-impl<DatabaseType, BranchType, LeafType, DataType, NodeType, HasherType, ValueType>
-    MerkleBIT<DatabaseType, BranchType, LeafType, DataType, NodeType, HasherType, ValueType>
+impl<
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        HasherType,
+        ValueType,
+        const KEY_LEN: usize,
+        const HASH_LEN: usize,
+        SchemeType,
+    >
+    MerkleBIT<
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        HasherType,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+        SchemeType,
+    >
 where
-    DatabaseType: Database<NodeType = NodeType>,
-    BranchType: Branch,
-    LeafType: Leaf,
+    DatabaseType: Database<HASH_LEN, NodeType = NodeType>,
+    BranchType: Branch<KEY_LEN, HASH_LEN>,
+    LeafType: Leaf<KEY_LEN, HASH_LEN>,
     DataType: Data,
     NodeType: Node<BranchType, LeafType, DataType>,
-    HasherType: Hasher<HashType = HasherType>,
+    HasherType: Hasher<HASH_LEN, HashType = HasherType>,
+    SchemeType: HashingScheme<KEY_LEN, HASH_LEN>,
     ValueType: Decode + Encode,
 {
     /// Create a new `MerkleBIT` from a saved database
     #[inline]
-    pub fn new(path: &PathBuf, depth: usize) -> BinaryMerkleTreeResult<Self> {
+    pub fn new(path: &PathBuf, depth: usize) -> BinaryMerkleTreeResult<Self>
+    where
+        SchemeType: Default,
+    {
+        Self::check_widths()?;
         let db = DatabaseType::open(path)?;
-        Ok(Self {
-            db,
-            depth,
-            branch: PhantomData,
-            leaf: PhantomData,
-            data: PhantomData,
-            node: PhantomData,
-            hasher: PhantomData,
-            value: PhantomData,
-        })
+        Self::from_db_with_scheme(db, depth, SchemeType::default())
     }
 
     /// Create a new `MerkleBIT` from an already opened database
     #[inline]
-    pub fn from_db(db: DatabaseType, depth: usize) -> BinaryMerkleTreeResult<Self> {
+    pub fn from_db(db: DatabaseType, depth: usize) -> BinaryMerkleTreeResult<Self>
+    where
+        SchemeType: Default,
+    {
+        Self::from_db_with_scheme(db, depth, SchemeType::default())
+    }
+
+    /// Create a new `MerkleBIT` from an already opened database, using `scheme` to calculate the
+    /// locations of nodes
+    #[inline]
+    pub fn from_db_with_scheme(
+        db: DatabaseType,
+        depth: usize,
+        scheme: SchemeType,
+    ) -> BinaryMerkleTreeResult<Self> {
+        Self::check_widths()?;
         Ok(Self {
             db,
             depth,
+            scheme,
+            unreverted: false,
             branch: PhantomData,
             leaf: PhantomData,
             data: PhantomData,
@@ -135,7 +181,7 @@ where
     #[inline]
     pub fn get<'a>(
         &self,
-        root_hash: &[u8; KEY_LEN],
+        root_hash: &[u8; HASH_LEN],
         keys: &mut [&'a [u8; KEY_LEN]],
     ) -> BinaryMerkleTreeResult<HashMap<&'a [u8; KEY_LEN], Option<ValueType>>> {
         if keys.is_empty() {
@@ -243,10 +289,21 @@ where
     #[inline]
     pub fn insert(
         &mut self,
-        previous_root: Option<&[u8; KEY_LEN]>,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        keys: &mut [&[u8; KEY_LEN]],
+        values: &mut [&ValueType],
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
+        let result = self.stage_insert(previous_root, keys, values);
+        self.finish(result)
+    }
+
+    /// Queues the changes made by `insert` without writing them to the database.
+    fn stage_insert(
+        &mut self,
+        previous_root: Option<&[u8; HASH_LEN]>,
         keys: &mut [&[u8; KEY_LEN]],
         values: &mut [&ValueType],
-    ) -> BinaryMerkleTreeResult<[u8; KEY_LEN]> {
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
         if keys.len() != values.len() {
             return Err(Exception::new("Keys and values have different lengths"));
         }
@@ -267,7 +324,7 @@ where
         let mut tree_refs = Vec::with_capacity(keys.len());
         let mut key_map = HashMap::new();
         for (loc, &&key) in nodes.into_iter().zip(keys.iter()) {
-            key_map.insert(key, loc);
+            key_map.insert(key, Some(loc));
             let tree_ref = TreeRef::new(key, loc, 1, 1);
             tree_refs.push(tree_ref);
         }
@@ -281,13 +338,181 @@ where
         Ok(new_root)
     }
 
+    /// Delete items from the `MerkleBIT`.  Returns the new root hash for the `MerkleBIT`, or `None`
+    /// if no items remain.
+    #[inline]
+    pub fn delete(
+        &mut self,
+        previous_root: &[u8; HASH_LEN],
+        keys: &mut [&[u8; KEY_LEN]],
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        let result = self.stage_delete(previous_root, keys);
+        self.finish(result)
+    }
+
+    /// Queues the changes made by `delete` without writing them to the database.
+    fn stage_delete(
+        &mut self,
+        previous_root: &[u8; HASH_LEN],
+        keys: &mut [&[u8; KEY_LEN]],
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        if keys.is_empty() {
+            return Err(Exception::new("Keys are empty"));
+        }
+
+        keys.sort();
+
+        let mut key_map = HashMap::new();
+        for &&key in keys.iter() {
+            key_map.insert(key, None);
+        }
+
+        let tree_refs = self.generate_treerefs(previous_root, keys, &key_map)?;
+        if tree_refs.is_empty() {
+            return Ok(None);
+        }
+
+        let new_root = self.create_tree(tree_refs)?;
+        Ok(Some(new_root))
+    }
+
+    /// Merges the changes made from `base` to `theirs` into `ours`, returning the merged root, or
+    /// `None` if no items remain.  Keys changed on only one side take that side's value.  Keys
+    /// changed differently on both sides are passed to `resolver` along with their base, our, and
+    /// their values, where `None` means the key is not held, and take the value it returns.
+    /// Returns an error if any of the roots could not be found.
+    #[inline]
+    pub fn merge<ResolverType>(
+        &mut self,
+        base: &[u8; HASH_LEN],
+        ours: &[u8; HASH_LEN],
+        theirs: &[u8; HASH_LEN],
+        resolver: ResolverType,
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>>
+    where
+        ResolverType: FnMut(
+            &[u8; KEY_LEN],
+            Option<&ValueType>,
+            Option<&ValueType>,
+            Option<&ValueType>,
+        ) -> BinaryMerkleTreeResult<Option<ValueType>>,
+    {
+        let batch = self.merge_batch(base, ours, theirs, resolver)?;
+        if batch.is_empty() {
+            // The merged root is our root, which gains a reference as any other new root would
+            let result = self.stage_retain(ours).map(Some);
+            return self.finish(result);
+        }
+        self.apply(Some(ours), &batch)
+    }
+
+    /// Applies `changeset` on top of its previous root.  Returns the new root hash, which is checked
+    /// against the root recorded in the changeset.
+    #[inline]
+    pub fn apply_changeset(
+        &mut self,
+        changeset: Changeset<ValueType, KEY_LEN, HASH_LEN>,
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
+        let Changeset {
+            previous_root,
+            new_root,
+            changes,
+        } = changeset;
+
+        if changes.is_empty() {
+            if previous_root != Some(new_root) {
+                return Err(Exception::new("Changeset does not produce its new root"));
+            }
+            // The new root is the previous root, which gains a reference as any other new root would
+            let result = self.stage_retain(&new_root);
+            return self.finish(result);
+        }
+
+        let mut batch = WriteBatch::new();
+        for (key, value) in changes {
+            match value {
+                Some(v) => batch.put(key, v),
+                None => batch.delete(key),
+            }
+        }
+
+        // The new tree is only written once it is known to match the changeset
+        let result = self
+            .stage_apply(previous_root.as_ref(), &batch)
+            .and_then(|root| match root {
+                Some(r) if r == new_root => Ok(r),
+                _ => Err(Exception::new("Changeset does not produce its new root")),
+            });
+        self.finish(result)
+    }
+
+    /// Applies the changes in `batch` to the `MerkleBIT` in a single pass.  Returns the new root
+    /// hash for the `MerkleBIT`, or `None` if no items remain.
+    #[inline]
+    pub fn apply(
+        &mut self,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        batch: &WriteBatch<ValueType, KEY_LEN>,
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        let result = self.stage_apply(previous_root, batch);
+        self.finish(result)
+    }
+
+    /// Queues the changes made by `apply` without writing them to the database.
+    fn stage_apply(
+        &mut self,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        batch: &WriteBatch<ValueType, KEY_LEN>,
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        if batch.is_empty() {
+            return Err(Exception::new("Write batch is empty"));
+        }
+
+        // Changes are held in key order, so both sets of keys are already sorted
+        let mut keys = Vec::with_capacity(batch.len());
+        let mut upsert_keys = Vec::with_capacity(batch.len());
+        let mut value_map = HashMap::new();
+        for (key, value) in batch.changes().iter() {
+            keys.push(key);
+            if let Some(v) = value {
+                upsert_keys.push(key);
+                value_map.insert(key, v);
+            }
+        }
+
+        let nodes = self.insert_leaves(&upsert_keys, &value_map)?;
+
+        let mut tree_refs = Vec::with_capacity(keys.len());
+        let mut key_map = HashMap::new();
+        for &&key in keys.iter() {
+            key_map.insert(key, None);
+        }
+        for (loc, &&key) in nodes.into_iter().zip(upsert_keys.iter()) {
+            key_map.insert(key, Some(loc));
+            let tree_ref = TreeRef::new(key, loc, 1, 1);
+            tree_refs.push(tree_ref);
+        }
+
+        if let Some(root) = previous_root {
+            let mut proof_nodes = self.generate_treerefs(root, &mut keys, &key_map)?;
+            tree_refs.append(&mut proof_nodes);
+        }
+
+        if tree_refs.is_empty() {
+            return Ok(None);
+        }
+
+        let new_root = self.create_tree(tree_refs)?;
+        Ok(Some(new_root))
+    }
+
     /// Traverses the tree and searches for nodes to include in the merkle proof.
     fn generate_treerefs(
         &mut self,
-        root: &[u8; KEY_LEN],
+        root: &[u8; HASH_LEN],
         keys: &mut [&[u8; KEY_LEN]],
-        key_map: &HashMap<[u8; KEY_LEN], [u8; KEY_LEN]>,
-    ) -> BinaryMerkleTreeResult<Vec<TreeRef>> {
+        key_map: &HashMap<[u8; KEY_LEN], Option<[u8; HASH_LEN]>>,
+    ) -> BinaryMerkleTreeResult<Vec<TreeRef<KEY_LEN, HASH_LEN>>> {
         // Nodes that form the merkle proof for the new tree
         let mut proof_nodes = Vec::with_capacity(keys.len());
 
@@ -298,7 +523,7 @@ where
         };
 
         let mut cell_queue = VecDeque::with_capacity(keys.len());
-        let root_cell: TreeCell<NodeType> =
+        let root_cell: TreeCell<NodeType, KEY_LEN, HASH_LEN> =
             TreeCell::new::<BranchType, LeafType, DataType>(*root, keys, root_node, 0);
         cell_queue.push_front(root_cell);
 
@@ -320,14 +545,10 @@ where
                 NodeVariant::Leaf(n) => {
                     let key = n.get_key();
 
-                    let mut update = false;
-
-                    // Check if we are updating an existing value
-                    if let Some(loc) = key_map.get(key) {
-                        update = loc == &tree_cell.location;
-                        if !update {
-                            continue;
-                        }
+                    // Leaves of keys being updated or deleted are replaced by their new leaves, if
+                    // any, which were already referenced when they were inserted
+                    if key_map.contains_key(key) {
+                        continue;
                     }
 
                     if let Some(mut l) = self.db.get_node(&tree_cell.location)? {
@@ -340,10 +561,6 @@ where
                         ));
                     }
 
-                    if update {
-                        continue;
-                    }
-
                     let tree_ref = TreeRef::new(*key, tree_cell.location, 1, 1);
                     proof_nodes.push(tree_ref);
                     continue;
@@ -474,18 +691,14 @@ where
         &mut self,
         keys: &[&[u8; KEY_LEN]],
         values: &HashMap<&[u8; KEY_LEN], &ValueType>,
-    ) -> BinaryMerkleTreeResult<Vec<[u8; KEY_LEN]>> {
+    ) -> BinaryMerkleTreeResult<Vec<[u8; HASH_LEN]>> {
         let mut nodes = Vec::with_capacity(keys.len());
         for &key in keys.iter() {
             // Create data node
             let mut data = DataType::new();
             data.set_value(&values[key].encode()?);
 
-            let mut data_hasher = HasherType::new(KEY_LEN);
-            data_hasher.update(b"d");
-            data_hasher.update(key);
-            data_hasher.update(data.get_value());
-            let data_node_location = data_hasher.finalize();
+            let data_node_location = self.scheme.data_location(key, data.get_value());
 
             let mut data_node = NodeType::new(NodeVariant::Data(data));
             data_node.set_references(1);
@@ -495,26 +708,23 @@ where
             leaf.set_data(data_node_location);
             leaf.set_key(*key);
 
-            let mut leaf_hasher = HasherType::new(KEY_LEN);
-            leaf_hasher.update(b"l");
-            leaf_hasher.update(key);
-            leaf_hasher.update(&leaf.get_data()[..]);
-            let leaf_node_location = leaf_hasher.finalize();
+            let leaf_node_location = self.scheme.leaf_location(key, leaf.get_data());
 
             let mut leaf_node = NodeType::new(NodeVariant::Leaf(leaf));
             leaf_node.set_references(1);
 
-            if let Some(n) = self.db.get_node(&data_node_location)? {
-                let references = n.get_references() + 1;
-                data_node.set_references(references);
-            }
-
+            // An existing leaf already holds its data node, so only a new leaf references it
             if let Some(n) = self.db.get_node(&leaf_node_location)? {
                 let references = n.get_references() + 1;
                 leaf_node.set_references(references);
+            } else {
+                if let Some(n) = self.db.get_node(&data_node_location)? {
+                    let references = n.get_references() + 1;
+                    data_node.set_references(references);
+                }
+                self.db.insert(data_node_location, data_node)?;
             }
 
-            self.db.insert(data_node_location, data_node)?;
             self.db.insert(leaf_node_location, leaf_node)?;
 
             nodes.push(leaf_node_location);
@@ -526,12 +736,11 @@ where
     /// new tree root.
     fn create_tree(
         &mut self,
-        mut tree_refs: Vec<TreeRef>,
-    ) -> BinaryMerkleTreeResult<[u8; KEY_LEN]> {
+        mut tree_refs: Vec<TreeRef<KEY_LEN, HASH_LEN>>,
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
         assert!(!tree_refs.is_empty());
 
         if tree_refs.len() == 1 {
-            self.db.batch_write()?;
             let node = tree_refs.remove(0);
             return Ok(node.location);
         }
@@ -558,10 +767,15 @@ where
     /// Performs the merging of `TreeRef`s until a single new root is left.
     fn merge_nodes(
         &mut self,
-        tree_refs_raw: *mut TreeRef,
-        level: Vec<(u8, *mut TreeRef, *mut TreeRef, usize)>,
-    ) -> BinaryMerkleTreeResult<Option<[u8; KEY_LEN]>> {
-        let mut root = [0; 32];
+        tree_refs_raw: *mut TreeRef<KEY_LEN, HASH_LEN>,
+        level: Vec<(
+            u8,
+            *mut TreeRef<KEY_LEN, HASH_LEN>,
+            *mut TreeRef<KEY_LEN, HASH_LEN>,
+            usize,
+        )>,
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        let mut root = [0; HASH_LEN];
         for (split_index, tree_ref_pointer, next_tree_ref_pointer, index) in level {
             let mut branch = BranchType::new();
 
@@ -594,11 +808,13 @@ where
             let count = unsafe { tree_ref_count + (*lookahead_tree_ref_pointer).node_count };
             let branch_node_location;
             {
-                let mut branch_hasher = HasherType::new(KEY_LEN);
-                branch_hasher.update(b"b");
-                branch_hasher.update(&tree_ref_location[..]);
-                branch_hasher.update(&next_tree_ref_location[..]);
-                branch_node_location = branch_hasher.finalize();
+                branch_node_location = self.scheme.branch_location(
+                    &tree_ref_location,
+                    &next_tree_ref_location,
+                    split_index,
+                    &tree_ref_key,
+                    count,
+                );
 
                 branch.set_zero(tree_ref_location);
                 branch.set_one(next_tree_ref_location);
@@ -607,10 +823,8 @@ where
                 branch.set_key(tree_ref_key);
             }
 
-            let mut branch_node = NodeType::new(NodeVariant::Branch(branch));
-            branch_node.set_references(1);
-
-            self.db.insert(branch_node_location, branch_node)?;
+            let branch_node = NodeType::new(NodeVariant::Branch(branch));
+            self.write_branch(branch_node_location, branch_node)?;
 
             unsafe {
                 (*lookahead_tree_ref_pointer).key = tree_ref_key;
@@ -628,9 +842,17 @@ where
 
     /// Generates the `TreeRef`s that will be made into the new tree.
     fn generate_tree_ref_queue(
-        tree_refs: &mut Vec<TreeRef>,
-        tree_ref_queue: &mut HashMap<u8, Vec<(u8, *mut TreeRef, *mut TreeRef, usize)>>,
-    ) -> BinaryMerkleTreeResult<(*mut TreeRef, HashSet<u8>)> {
+        tree_refs: &mut Vec<TreeRef<KEY_LEN, HASH_LEN>>,
+        tree_ref_queue: &mut HashMap<
+            u8,
+            Vec<(
+                u8,
+                *mut TreeRef<KEY_LEN, HASH_LEN>,
+                *mut TreeRef<KEY_LEN, HASH_LEN>,
+                usize,
+            )>,
+        >,
+    ) -> BinaryMerkleTreeResult<(*mut TreeRef<KEY_LEN, HASH_LEN>, HashSet<u8>)> {
         let tree_rcs_raw = tree_refs.as_mut_ptr();
         let mut unique_split_bits = HashSet::new();
         for i in 0..tree_refs.len() - 1 {
@@ -669,7 +891,13 @@ where
 
     /// Remove all items with less than 1 reference under the given root.
     #[inline]
-    pub fn remove(&mut self, root_hash: &[u8; KEY_LEN]) -> BinaryMerkleTreeResult<()> {
+    pub fn remove(&mut self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<()> {
+        let result = self.stage_remove(root_hash);
+        self.finish(result)
+    }
+
+    /// Queues the changes made by `remove` without writing them to the database.
+    fn stage_remove(&mut self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<()> {
         let mut nodes = VecDeque::with_capacity(128);
         nodes.push_front(*root_hash);
 
@@ -726,46 +954,80 @@ where
             new_node.set_references(refs);
             self.db.insert(node_location, new_node)?;
         }
-        self.db.batch_write()?;
 
         Ok(())
     }
 }
 
 #[cfg(feature = "use_rayon")]
-impl<DatabaseType, BranchType, LeafType, DataType, NodeType, HasherType, ValueType>
-    MerkleBIT<DatabaseType, BranchType, LeafType, DataType, NodeType, HasherType, ValueType>
+impl<
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        HasherType,
+        ValueType,
+        const KEY_LEN: usize,
+        const HASH_LEN: usize,
+        SchemeType,
+    >
+    MerkleBIT<
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        HasherType,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+        SchemeType,
+    >
 where
-    DatabaseType: Database<NodeType = NodeType> + Send + Sync,
-    BranchType: Branch,
-    LeafType: Leaf,
+    DatabaseType: Database<HASH_LEN, NodeType = NodeType> + Send + Sync,
+    BranchType: Branch<KEY_LEN, HASH_LEN>,
+    LeafType: Leaf<KEY_LEN, HASH_LEN>,
     DataType: Data,
     NodeType: Node<BranchType, LeafType, DataType> + Send + Sync,
-    HasherType: Hasher<HashType = HasherType>,
+    HasherType: Hasher<HASH_LEN, HashType = HasherType>,
+    SchemeType: HashingScheme<KEY_LEN, HASH_LEN> + Sync,
     ValueType: Decode + Encode + Sync + Send,
 {
     /// Create a new MerkleBIT from a saved database
     #[inline]
-    pub fn new(path: &PathBuf, depth: usize) -> BinaryMerkleTreeResult<Self> {
+    pub fn new(path: &PathBuf, depth: usize) -> BinaryMerkleTreeResult<Self>
+    where
+        SchemeType: Default,
+    {
+        Self::check_widths()?;
         let db = DatabaseType::open(path)?;
-        Ok(Self {
-            db,
-            depth,
-            branch: PhantomData,
-            leaf: PhantomData,
-            data: PhantomData,
-            node: PhantomData,
-            hasher: PhantomData,
-            value: PhantomData,
-        })
+        Self::from_db_with_scheme(db, depth, SchemeType::default())
     }
 
     /// Create a new MerkleBIT from an already opened database
     #[inline]
-    pub fn from_db(db: DatabaseType, depth: usize) -> BinaryMerkleTreeResult<Self> {
+    pub fn from_db(db: DatabaseType, depth: usize) -> BinaryMerkleTreeResult<Self>
+    where
+        SchemeType: Default,
+    {
+        Self::from_db_with_scheme(db, depth, SchemeType::default())
+    }
+
+    /// Create a new MerkleBIT from an already opened database, using `scheme` to calculate the
+    /// locations of nodes
+    #[inline]
+    pub fn from_db_with_scheme(
+        db: DatabaseType,
+        depth: usize,
+        scheme: SchemeType,
+    ) -> BinaryMerkleTreeResult<Self> {
+        Self::check_widths()?;
         Ok(Self {
             db,
             depth,
+            scheme,
+            unreverted: false,
             branch: PhantomData,
             leaf: PhantomData,
             data: PhantomData,
@@ -779,7 +1041,7 @@ where
     #[inline]
     pub fn get<'a>(
         &self,
-        root_hash: &[u8; KEY_LEN],
+        root_hash: &[u8; HASH_LEN],
         keys: &mut [&'a [u8; KEY_LEN]],
     ) -> BinaryMerkleTreeResult<HashMap<&'a [u8; KEY_LEN], Option<ValueType>>> {
         if keys.is_empty() {
@@ -887,10 +1149,21 @@ where
     #[inline]
     pub fn insert(
         &mut self,
-        previous_root: Option<&[u8; KEY_LEN]>,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        keys: &mut [&[u8; KEY_LEN]],
+        values: &mut [&ValueType],
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
+        let result = self.stage_insert(previous_root, keys, values);
+        self.finish(result)
+    }
+
+    /// Queues the changes made by `insert` without writing them to the database.
+    fn stage_insert(
+        &mut self,
+        previous_root: Option<&[u8; HASH_LEN]>,
         keys: &mut [&[u8; KEY_LEN]],
         values: &mut [&ValueType],
-    ) -> BinaryMerkleTreeResult<[u8; KEY_LEN]> {
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
         if keys.len() != values.len() {
             return Err(Exception::new("Keys and values have different lengths"));
         }
@@ -911,7 +1184,7 @@ where
         let mut tree_refs = Vec::with_capacity(keys.len());
         let mut key_map = HashMap::new();
         for (loc, &&key) in nodes.into_iter().zip(keys.iter()) {
-            key_map.insert(key, loc);
+            key_map.insert(key, Some(loc));
             let tree_ref = TreeRef::new(key, loc, 1, 1);
             tree_refs.push(tree_ref);
         }
@@ -925,13 +1198,181 @@ where
         Ok(new_root)
     }
 
+    /// Delete items from the `MerkleBIT`.  Returns the new root hash for the `MerkleBIT`, or `None`
+    /// if no items remain.
+    #[inline]
+    pub fn delete(
+        &mut self,
+        previous_root: &[u8; HASH_LEN],
+        keys: &mut [&[u8; KEY_LEN]],
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        let result = self.stage_delete(previous_root, keys);
+        self.finish(result)
+    }
+
+    /// Queues the changes made by `delete` without writing them to the database.
+    fn stage_delete(
+        &mut self,
+        previous_root: &[u8; HASH_LEN],
+        keys: &mut [&[u8; KEY_LEN]],
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        if keys.is_empty() {
+            return Err(Exception::new("Keys are empty"));
+        }
+
+        keys.par_sort();
+
+        let mut key_map = HashMap::new();
+        for &&key in keys.iter() {
+            key_map.insert(key, None);
+        }
+
+        let tree_refs = self.generate_treerefs(previous_root, keys, &key_map)?;
+        if tree_refs.is_empty() {
+            return Ok(None);
+        }
+
+        let new_root = self.create_tree(tree_refs)?;
+        Ok(Some(new_root))
+    }
+
+    /// Merges the changes made from `base` to `theirs` into `ours`, returning the merged root, or
+    /// `None` if no items remain.  Keys changed on only one side take that side's value.  Keys
+    /// changed differently on both sides are passed to `resolver` along with their base, our, and
+    /// their values, where `None` means the key is not held, and take the value it returns.
+    /// Returns an error if any of the roots could not be found.
+    #[inline]
+    pub fn merge<ResolverType>(
+        &mut self,
+        base: &[u8; HASH_LEN],
+        ours: &[u8; HASH_LEN],
+        theirs: &[u8; HASH_LEN],
+        resolver: ResolverType,
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>>
+    where
+        ResolverType: FnMut(
+            &[u8; KEY_LEN],
+            Option<&ValueType>,
+            Option<&ValueType>,
+            Option<&ValueType>,
+        ) -> BinaryMerkleTreeResult<Option<ValueType>>,
+    {
+        let batch = self.merge_batch(base, ours, theirs, resolver)?;
+        if batch.is_empty() {
+            // The merged root is our root, which gains a reference as any other new root would
+            let result = self.stage_retain(ours).map(Some);
+            return self.finish(result);
+        }
+        self.apply(Some(ours), &batch)
+    }
+
+    /// Applies `changeset` on top of its previous root.  Returns the new root hash, which is checked
+    /// against the root recorded in the changeset.
+    #[inline]
+    pub fn apply_changeset(
+        &mut self,
+        changeset: Changeset<ValueType, KEY_LEN, HASH_LEN>,
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
+        let Changeset {
+            previous_root,
+            new_root,
+            changes,
+        } = changeset;
+
+        if changes.is_empty() {
+            if previous_root != Some(new_root) {
+                return Err(Exception::new("Changeset does not produce its new root"));
+            }
+            // The new root is the previous root, which gains a reference as any other new root would
+            let result = self.stage_retain(&new_root);
+            return self.finish(result);
+        }
+
+        let mut batch = WriteBatch::new();
+        for (key, value) in changes {
+            match value {
+                Some(v) => batch.put(key, v),
+                None => batch.delete(key),
+            }
+        }
+
+        // The new tree is only written once it is known to match the changeset
+        let result = self
+            .stage_apply(previous_root.as_ref(), &batch)
+            .and_then(|root| match root {
+                Some(r) if r == new_root => Ok(r),
+                _ => Err(Exception::new("Changeset does not produce its new root")),
+            });
+        self.finish(result)
+    }
+
+    /// Applies the changes in `batch` to the `MerkleBIT` in a single pass.  Returns the new root
+    /// hash for the `MerkleBIT`, or `None` if no items remain.
+    #[inline]
+    pub fn apply(
+        &mut self,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        batch: &WriteBatch<ValueType, KEY_LEN>,
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        let result = self.stage_apply(previous_root, batch);
+        self.finish(result)
+    }
+
+    /// Queues the changes made by `apply` without writing them to the database.
+    fn stage_apply(
+        &mut self,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        batch: &WriteBatch<ValueType, KEY_LEN>,
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        if batch.is_empty() {
+            return Err(Exception::new("Write batch is empty"));
+        }
+
+        // Changes are held in key order, so both sets of keys are already sorted
+        let mut keys = Vec::with_capacity(batch.len());
+        let mut upsert_keys = Vec::with_capacity(batch.len());
+        let mut value_map = HashMap::new();
+        for (key, value) in batch.changes().iter() {
+            keys.push(key);
+            if let Some(v) = value {
+                upsert_keys.push(key);
+                value_map.insert(key, v);
+            }
+        }
+
+        let nodes = self.insert_leaves(&upsert_keys, &value_map)?;
+
+        let mut tree_refs = Vec::with_capacity(keys.len());
+        let mut key_map = HashMap::new();
+        for &&key in keys.iter() {
+            key_map.insert(key, None);
+        }
+        for (loc, &&key) in nodes.into_iter().zip(upsert_keys.iter()) {
+            key_map.insert(key, Some(loc));
+            let tree_ref = TreeRef::new(key, loc, 1, 1);
+            tree_refs.push(tree_ref);
+        }
+
+        if let Some(root) = previous_root {
+            let mut proof_nodes = self.generate_treerefs(root, &mut keys, &key_map)?;
+            tree_refs.append(&mut proof_nodes);
+        }
+
+        if tree_refs.is_empty() {
+            return Ok(None);
+        }
+
+        let new_root = self.create_tree(tree_refs)?;
+        Ok(Some(new_root))
+    }
+
     /// Traverses the tree and searches for nodes to include in the merkle proof.
     fn generate_treerefs(
         &mut self,
-        root: &[u8; KEY_LEN],
+        root: &[u8; HASH_LEN],
         keys: &mut [&[u8; KEY_LEN]],
-        key_map: &HashMap<[u8; KEY_LEN], [u8; KEY_LEN]>,
-    ) -> BinaryMerkleTreeResult<Vec<TreeRef>> {
+        key_map: &HashMap<[u8; KEY_LEN], Option<[u8; HASH_LEN]>>,
+    ) -> BinaryMerkleTreeResult<Vec<TreeRef<KEY_LEN, HASH_LEN>>> {
         // Nodes that form the merkle proof for the new tree
         let mut proof_nodes = Vec::with_capacity(keys.len());
 
@@ -942,7 +1383,7 @@ where
         };
 
         let mut cell_queue = VecDeque::with_capacity(keys.len());
-        let root_cell: TreeCell<NodeType> =
+        let root_cell: TreeCell<NodeType, KEY_LEN, HASH_LEN> =
             TreeCell::new::<BranchType, LeafType, DataType>(*root, keys, root_node, 0);
         cell_queue.push_front(root_cell);
 
@@ -964,14 +1405,10 @@ where
                 NodeVariant::Leaf(n) => {
                     let key = n.get_key();
 
-                    let mut update = false;
-
-                    // Check if we are updating an existing value
-                    if let Some(loc) = key_map.get(key) {
-                        update = loc == &tree_cell.location;
-                        if !update {
-                            continue;
-                        }
+                    // Leaves of keys being updated or deleted are replaced by their new leaves, if
+                    // any, which were already referenced when they were inserted
+                    if key_map.contains_key(key) {
+                        continue;
                     }
 
                     if let Some(mut l) = self.db.get_node(&tree_cell.location)? {
@@ -984,10 +1421,6 @@ where
                         ));
                     }
 
-                    if update {
-                        continue;
-                    }
-
                     let tree_ref = TreeRef::new(*key, tree_cell.location, 1, 1);
                     proof_nodes.push(tree_ref);
                     continue;
@@ -1118,8 +1551,9 @@ where
         &mut self,
         keys: &[&[u8; KEY_LEN]],
         values: &HashMap<&[u8; KEY_LEN], &ValueType>,
-    ) -> BinaryMerkleTreeResult<Vec<[u8; KEY_LEN]>> {
+    ) -> BinaryMerkleTreeResult<Vec<[u8; HASH_LEN]>> {
         let db = &self.db;
+        let scheme = &self.scheme;
 
         let nodes = keys
             .par_iter()
@@ -1127,11 +1561,7 @@ where
                 let mut data = DataType::new();
                 data.set_value(&values[key].encode().expect("Error encoding value"));
 
-                let mut data_hasher = HasherType::new(KEY_LEN);
-                data_hasher.update(b"d");
-                data_hasher.update(key);
-                data_hasher.update(data.get_value());
-                let data_node_location = data_hasher.finalize();
+                let data_node_location = scheme.data_location(key, data.get_value());
 
                 let mut data_node = NodeType::new(NodeVariant::Data(data));
                 data_node.set_references(1);
@@ -1141,43 +1571,42 @@ where
                 leaf.set_data(data_node_location);
                 leaf.set_key(*key);
 
-                let mut leaf_hasher = HasherType::new(KEY_LEN);
-                leaf_hasher.update(b"l");
-                leaf_hasher.update(key);
-                leaf_hasher.update(&leaf.get_data()[..]);
-                let leaf_node_location = leaf_hasher.finalize();
+                let leaf_node_location = scheme.leaf_location(key, leaf.get_data());
 
                 let mut leaf_node = NodeType::new(NodeVariant::Leaf(leaf));
                 leaf_node.set_references(1);
 
-                if let Some(n) = db
-                    .get_node(&data_node_location)
-                    .expect("Error loading data node")
-                {
-                    let references = n.get_references() + 1;
-                    data_node.set_references(references);
-                }
-
-                if let Some(n) = db
+                // An existing leaf already holds its data node, so only a new leaf references it
+                let data_node = if let Some(n) = db
                     .get_node(&leaf_node_location)
                     .expect("Error loading leaf node")
                 {
                     let references = n.get_references() + 1;
                     leaf_node.set_references(references);
-                }
+                    None
+                } else {
+                    if let Some(n) = db
+                        .get_node(&data_node_location)
+                        .expect("Error loading data node")
+                    {
+                        let references = n.get_references() + 1;
+                        data_node.set_references(references);
+                    }
+                    Some((data_node_location, data_node))
+                };
 
-                (data_node_location, data_node, leaf_node_location, leaf_node)
+                (data_node, leaf_node_location, leaf_node)
             })
             .collect::<Vec<_>>();
 
-        let node_locations = nodes
-            .into_iter()
-            .map(|x| {
-                self.db.insert(x.0, x.1).expect("Failed to insert into db");
-                self.db.insert(x.2, x.3).expect("Failed to insert into db");
-                x.2
-            })
-            .collect::<Vec<_>>();
+        let mut node_locations = Vec::with_capacity(nodes.len());
+        for (data_node, leaf_node_location, leaf_node) in nodes {
+            if let Some((data_node_location, data_node)) = data_node {
+                self.db.insert(data_node_location, data_node)?;
+            }
+            self.db.insert(leaf_node_location, leaf_node)?;
+            node_locations.push(leaf_node_location);
+        }
 
         Ok(node_locations)
     }
@@ -1186,12 +1615,11 @@ where
     /// new tree root.
     fn create_tree(
         &mut self,
-        mut tree_refs: Vec<TreeRef>,
-    ) -> BinaryMerkleTreeResult<[u8; KEY_LEN]> {
+        mut tree_refs: Vec<TreeRef<KEY_LEN, HASH_LEN>>,
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
         assert!(!tree_refs.is_empty());
 
         if tree_refs.len() == 1 {
-            self.db.batch_write()?;
             let node = tree_refs.remove(0);
             return Ok(node.location);
         }
@@ -1205,25 +1633,25 @@ where
         let mut indices = unique_split_bits.into_iter().collect::<Vec<_>>();
         indices.sort();
 
-        let mut root = [0; 32];
+        let mut root = [0; HASH_LEN];
         for i in indices.into_iter().rev() {
             let level = tree_ref_queue
                 .remove(&i)
                 .expect("Level should not be empty");
             root = self.merge_nodes(tree_refs_raw, level)?;
         }
-        self.db.batch_write()?;
         Ok(root)
     }
 
     /// Performs the merging of `TreeRef`s until a single new root is left.
     fn merge_nodes(
         &mut self,
-        tree_rcs_raw: *mut TreeRef,
-        level: Vec<MergeCell>,
-    ) -> BinaryMerkleTreeResult<[u8; KEY_LEN]> {
+        tree_rcs_raw: *mut TreeRef<KEY_LEN, HASH_LEN>,
+        level: Vec<MergeCell<KEY_LEN, HASH_LEN>>,
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
         let raw_tree_refs = TreeRefRaw(tree_rcs_raw);
-        let root: Vec<BinaryMerkleTreeResult<([u8; 32], u8, NodeType)>> = level
+        let scheme = &self.scheme;
+        let root: Vec<BinaryMerkleTreeResult<([u8; HASH_LEN], u8, NodeType)>> = level
             .into_par_iter()
             .map(|merge_cell| {
                 let (split_index, tree_ref_pointer, next_tree_ref_pointer, index) =
@@ -1259,11 +1687,13 @@ where
                 let count = unsafe { tree_ref_count + (*lookahead_tree_ref_pointer).node_count };
                 let branch_node_location;
                 {
-                    let mut branch_hasher = HasherType::new(KEY_LEN);
-                    branch_hasher.update(b"b");
-                    branch_hasher.update(&tree_ref_location[..]);
-                    branch_hasher.update(&next_tree_ref_location[..]);
-                    branch_node_location = branch_hasher.finalize();
+                    branch_node_location = scheme.branch_location(
+                        &tree_ref_location,
+                        &next_tree_ref_location,
+                        split_index,
+                        &tree_ref_key,
+                        count,
+                    );
 
                     branch.set_zero(tree_ref_location);
                     branch.set_one(next_tree_ref_location);
@@ -1272,8 +1702,7 @@ where
                     branch.set_key(tree_ref_key);
                 }
 
-                let mut branch_node = NodeType::new(NodeVariant::Branch(branch));
-                branch_node.set_references(1);
+                let branch_node = NodeType::new(NodeVariant::Branch(branch));
 
                 unsafe {
                     (*lookahead_tree_ref_pointer).key = tree_ref_key;
@@ -1292,7 +1721,7 @@ where
         let root_len = root.len() - 1;
         for (i, result) in root.into_iter().enumerate() {
             if let Ok(item) = result {
-                self.db.insert(item.0, item.2)?;
+                self.write_branch(item.0, item.2)?;
                 if i == root_len {
                     return Ok(item.0);
                 }
@@ -1301,16 +1730,14 @@ where
             }
         }
 
-        self.db.batch_write()?;
-
         Err(Exception::new("Failed to make tree root"))
     }
 
     /// Generates the `TreeRef`s that will be made into the new tree.
     fn generate_tree_ref_queue(
-        tree_refs: &mut Vec<TreeRef>,
-        tree_ref_queue: &mut HashMap<u8, Vec<MergeCell>>,
-    ) -> BinaryMerkleTreeResult<(*mut TreeRef, HashSet<u8>)> {
+        tree_refs: &mut Vec<TreeRef<KEY_LEN, HASH_LEN>>,
+        tree_ref_queue: &mut HashMap<u8, Vec<MergeCell<KEY_LEN, HASH_LEN>>>,
+    ) -> BinaryMerkleTreeResult<(*mut TreeRef<KEY_LEN, HASH_LEN>, HashSet<u8>)> {
         let tree_rcs_raw = tree_refs.as_mut_ptr();
         let mut unique_split_bits = HashSet::new();
         for i in 0..tree_refs.len() - 1 {
@@ -1350,7 +1777,13 @@ where
 
     /// Remove all items with less than 1 reference under the given root.
     #[inline]
-    pub fn remove(&mut self, root_hash: &[u8; KEY_LEN]) -> BinaryMerkleTreeResult<()> {
+    pub fn remove(&mut self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<()> {
+        let result = self.stage_remove(root_hash);
+        self.finish(result)
+    }
+
+    /// Queues the changes made by `remove` without writing them to the database.
+    fn stage_remove(&mut self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<()> {
         let mut nodes = VecDeque::with_capacity(128);
         nodes.push_front(*root_hash);
 
@@ -1407,31 +1840,1180 @@ where
             new_node.set_references(refs);
             self.db.insert(node_location, new_node)?;
         }
-        self.db.batch_write()?;
 
         Ok(())
     }
 }
 
-#[cfg(test)]
-pub mod tests {
-    use crate::utils::tree_utils::choose_zero;
+impl<
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        HasherType,
+        ValueType,
+        const KEY_LEN: usize,
+        const HASH_LEN: usize,
+        SchemeType,
+    >
+    MerkleBIT<
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        HasherType,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+        SchemeType,
+    >
+where
+    DatabaseType: Database<HASH_LEN, NodeType = NodeType>,
+    BranchType: Branch<KEY_LEN, HASH_LEN>,
+    LeafType: Leaf<KEY_LEN, HASH_LEN>,
+    DataType: Data,
+    NodeType: Node<BranchType, LeafType, DataType>,
+    HasherType: Hasher<HASH_LEN, HashType = HasherType>,
+    SchemeType: HashingScheme<KEY_LEN, HASH_LEN>,
+    ValueType: Decode + Encode,
+{
+    /// Iterates over the keys and values held under `root_hash` in ascending order of keys.  Nodes
+    /// are loaded from the database as they are needed.
+    #[inline]
+    pub fn iter(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+    ) -> TreeIter<
+        '_,
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    > {
+        TreeIter::new(
+            &self.db,
+            *root_hash,
+            self.depth,
+            (Bound::Unbounded, Bound::Unbounded),
+        )
+    }
 
-    use super::*;
+    /// Iterates over the keys and values held under `root_hash` with keys in `range`, in ascending
+    /// order of keys.  Subtrees that cannot hold keys in `range` are not visited.
+    #[inline]
+    pub fn range<RangeType>(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        range: RangeType,
+    ) -> TreeIter<
+        '_,
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    >
+    where
+        RangeType: RangeBounds<[u8; KEY_LEN]>,
+    {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        TreeIter::new(&self.db, *root_hash, self.depth, bounds)
+    }
 
-    #[test]
-    fn it_chooses_the_right_branch_easy() {
-        let key = [0x0F; KEY_LEN];
-        for i in 0..8 {
-            let expected_branch;
-            if i < 4 {
-                expected_branch = true;
-            } else {
-                expected_branch = false;
-            }
-            let branch = choose_zero(&key, i);
-            assert_eq!(branch, expected_branch);
-        }
+    /// Iterates over the keys and values held under `root_hash` whose keys share the first
+    /// `prefix_bits` bits of `prefix`, in ascending order of keys.
+    #[inline]
+    pub fn prefix(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        prefix: &[u8; KEY_LEN],
+        prefix_bits: usize,
+    ) -> TreeIter<
+        '_,
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    > {
+        let (min_key, max_key) = calc_prefix_bounds(prefix, prefix_bits);
+        self.range(root_hash, min_key..=max_key)
+    }
+
+    /// Generates a proof that `key` is held under `root_hash`.  Returns `None` if the key is not
+    /// in the tree.
+    #[inline]
+    pub fn get_proof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        key: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<Option<InclusionProof<KEY_LEN, HASH_LEN>>> {
+        let mut node = if let Some(n) = self.db.get_node(root_hash)? {
+            n
+        } else {
+            return Ok(None);
+        };
+
+        let mut branches = Vec::with_capacity(self.depth);
+        let mut depth = 0;
+
+        loop {
+            if depth > self.depth {
+                return Err(Exception::new("Depth of merkle tree exceeded"));
+            }
+
+            match node.get_variant() {
+                NodeVariant::Branch(branch) => {
+                    let (count, zero, one, split_index, branch_key) = branch.deconstruct();
+                    let (next, sibling) = if choose_zero(key, split_index) {
+                        (zero, one)
+                    } else {
+                        (one, zero)
+                    };
+                    branches.push(ProofBranch::new(sibling, split_index, branch_key, count));
+
+                    node = if let Some(n) = self.db.get_node(&next)? {
+                        n
+                    } else {
+                        return Err(Exception::new(
+                            "Corrupt merkle tree: Failed to get child node from DB",
+                        ));
+                    };
+                    depth += 1;
+                }
+                NodeVariant::Leaf(leaf) => {
+                    if leaf.get_key() != key {
+                        return Ok(None);
+                    }
+                    let (leaf_key, data_location) = leaf.deconstruct();
+                    if let Some(d) = self.db.get_node(&data_location)? {
+                        if let NodeVariant::Data(data) = d.get_variant() {
+                            let value = data.get_value().to_vec();
+                            return Ok(Some(InclusionProof::new(
+                                branches,
+                                leaf_key,
+                                data_location,
+                                value,
+                            )));
+                        }
+                        return Err(Exception::new(
+                            "Corrupt merkle tree: Found non data node after leaf",
+                        ));
+                    }
+                    return Err(Exception::new(
+                        "Corrupt merkle tree: Failed to get data node from DB",
+                    ));
+                }
+                NodeVariant::Data(_) => {
+                    return Err(Exception::new(
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Generates a proof that `key` is not held under `root_hash`.  Returns `None` if the key is
+    /// in the tree, or if the root could not be found.
+    ///
+    /// The proof can only be verified if the tree was built with a scheme that commits to the
+    /// position of branches, such as `TaggedScheme::committed`, so an error is returned for any
+    /// other scheme.
+    #[inline]
+    pub fn get_absence_proof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        key: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<Option<AbsenceProof<KEY_LEN, HASH_LEN>>> {
+        if !self.scheme.commits_position() {
+            return Err(Exception::new(
+                "Absence proofs require a scheme that commits to the position of branches",
+            ));
+        }
+
+        let mut node = if let Some(n) = self.db.get_node(root_hash)? {
+            n
+        } else {
+            return Ok(None);
+        };
+
+        let mut branches = Vec::with_capacity(self.depth);
+        let mut depth = 0;
+
+        loop {
+            if depth > self.depth {
+                return Err(Exception::new("Depth of merkle tree exceeded"));
+            }
+
+            match node.get_variant() {
+                NodeVariant::Branch(branch) => {
+                    let (count, zero, one, split_index, branch_key) = branch.deconstruct();
+                    if !is_descendant(key, split_index, &branch_key) {
+                        let end = PathEnd::Branch {
+                            zero,
+                            one,
+                            split_index,
+                            key: branch_key,
+                            count,
+                        };
+                        return Ok(Some(AbsenceProof::new(branches, end)));
+                    }
+
+                    let (next, sibling) = if choose_zero(key, split_index) {
+                        (zero, one)
+                    } else {
+                        (one, zero)
+                    };
+                    branches.push(ProofBranch::new(sibling, split_index, branch_key, count));
+
+                    node = if let Some(n) = self.db.get_node(&next)? {
+                        n
+                    } else {
+                        return Err(Exception::new(
+                            "Corrupt merkle tree: Failed to get child node from DB",
+                        ));
+                    };
+                    depth += 1;
+                }
+                NodeVariant::Leaf(leaf) => {
+                    if leaf.get_key() == key {
+                        return Ok(None);
+                    }
+                    let (leaf_key, data) = leaf.deconstruct();
+                    let end = PathEnd::Leaf {
+                        key: leaf_key,
+                        data,
+                    };
+                    return Ok(Some(AbsenceProof::new(branches, end)));
+                }
+                NodeVariant::Data(_) => {
+                    return Err(Exception::new(
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Generates a single proof for the presence or absence of each of `keys` under `root_hash`.
+    /// Nodes shared by the paths of several keys are only included once.  Returns `None` if the
+    /// root could not be found.
+    ///
+    /// As with `get_absence_proof`, a key that is not in the tree can only be proven absent with a
+    /// scheme that commits to the position of branches, so an error is returned if any of `keys`
+    /// is absent and the tree uses any other scheme.
+    #[inline]
+    pub fn get_multiproof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        keys: &mut [&[u8; KEY_LEN]],
+    ) -> BinaryMerkleTreeResult<Option<MultiProof<KEY_LEN, HASH_LEN>>> {
+        if self.db.get_node(root_hash)?.is_none() {
+            return Ok(None);
+        }
+
+        #[cfg(not(feature = "use_rayon"))]
+        keys.sort();
+        #[cfg(feature = "use_rayon")]
+        keys.par_sort();
+
+        let mut nodes = Vec::with_capacity(keys.len() * 2 + 1);
+        let mut cell_stack = vec![(*root_hash, &keys[..], 0)];
+
+        while let Some((location, cell_keys, depth)) = cell_stack.pop() {
+            if cell_keys.is_empty() {
+                nodes.push(MultiProofNode::Hash(location));
+                continue;
+            }
+
+            if depth > self.depth {
+                return Err(Exception::new("Depth of merkle tree exceeded"));
+            }
+
+            let node = if let Some(n) = self.db.get_node(&location)? {
+                n
+            } else {
+                return Err(Exception::new(
+                    "Corrupt merkle tree: Failed to get child node from DB",
+                ));
+            };
+
+            match node.get_variant() {
+                NodeVariant::Branch(branch) => {
+                    let (count, zero, one, split_index, branch_key) = branch.deconstruct();
+                    let min_split_index = calc_min_split_index(cell_keys, &branch_key);
+                    let descendants =
+                        check_descendants(cell_keys, split_index, &branch_key, min_split_index);
+                    if descendants.len() != cell_keys.len() && !self.scheme.commits_position() {
+                        return Err(Exception::new(
+                            "Absence claims require a scheme that commits to the position of branches",
+                        ));
+                    }
+                    let (zeros, ones) = split_pairs(descendants, split_index);
+
+                    nodes.push(MultiProofNode::Branch {
+                        split_index,
+                        key: branch_key,
+                        count,
+                    });
+                    cell_stack.push((one, ones, depth + 1));
+                    cell_stack.push((zero, zeros, depth + 1));
+                }
+                NodeVariant::Leaf(leaf) => {
+                    let (key, data) = leaf.deconstruct();
+                    if cell_keys.iter().any(|&cell_key| *cell_key != key)
+                        && !self.scheme.commits_position()
+                    {
+                        return Err(Exception::new(
+                            "Absence claims require a scheme that commits to the position of branches",
+                        ));
+                    }
+                    nodes.push(MultiProofNode::Leaf { key, data });
+                }
+                NodeVariant::Data(_) => {
+                    return Err(Exception::new(
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    ));
+                }
+            }
+        }
+
+        Ok(Some(MultiProof::new(nodes)))
+    }
+
+    /// Generates a proof that the returned keys and values are every entry under `root_hash` with
+    /// a key between `start` and `end` inclusive.  Returns `None` if the root could not be found.
+    #[inline]
+    pub fn get_range_proof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        start: &[u8; KEY_LEN],
+        end: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<
+        Option<(
+            Vec<([u8; KEY_LEN], ValueType)>,
+            RangeProof<KEY_LEN, HASH_LEN>,
+        )>,
+    > {
+        if start > end {
+            return Err(Exception::new("Range start is greater than range end"));
+        }
+
+        if self.db.get_node(root_hash)?.is_none() {
+            return Ok(None);
+        }
+
+        let lower_neighbour = self.find_neighbour(root_hash, start, true)?;
+        let upper_neighbour = self.find_neighbour(root_hash, end, false)?;
+        let lower_bound = lower_neighbour.as_ref().unwrap_or(start);
+        let upper_bound = upper_neighbour.as_ref().unwrap_or(end);
+
+        let mut entries = vec![];
+        let mut nodes = vec![];
+        let mut cell_stack = vec![(*root_hash, true, 0)];
+
+        while let Some((location, expand, depth)) = cell_stack.pop() {
+            if !expand {
+                nodes.push(MultiProofNode::Hash(location));
+                continue;
+            }
+
+            if depth > self.depth {
+                return Err(Exception::new("Depth of merkle tree exceeded"));
+            }
+
+            let node = if let Some(n) = self.db.get_node(&location)? {
+                n
+            } else {
+                return Err(Exception::new(
+                    "Corrupt merkle tree: Failed to get child node from DB",
+                ));
+            };
+
+            match node.get_variant() {
+                NodeVariant::Branch(branch) => {
+                    let (count, zero, one, split_index, branch_key) = branch.deconstruct();
+                    let (zero_min, zero_max) = calc_child_bounds(&branch_key, split_index, true);
+                    let (one_min, one_max) = calc_child_bounds(&branch_key, split_index, false);
+
+                    nodes.push(MultiProofNode::Branch {
+                        split_index,
+                        key: branch_key,
+                        count,
+                    });
+                    let expand_one = one_min <= *upper_bound && one_max >= *lower_bound;
+                    let expand_zero = zero_min <= *upper_bound && zero_max >= *lower_bound;
+                    cell_stack.push((one, expand_one, depth + 1));
+                    cell_stack.push((zero, expand_zero, depth + 1));
+                }
+                NodeVariant::Leaf(leaf) => {
+                    let (key, data) = leaf.deconstruct();
+                    if key >= *start && key <= *end {
+                        if let Some(d) = self.db.get_node(&data)? {
+                            if let NodeVariant::Data(data_node) = d.get_variant() {
+                                entries.push((key, ValueType::decode(data_node.get_value())?));
+                            } else {
+                                return Err(Exception::new(
+                                    "Corrupt merkle tree: Found non data node after leaf",
+                                ));
+                            }
+                        } else {
+                            return Err(Exception::new(
+                                "Corrupt merkle tree: Failed to get data node from DB",
+                            ));
+                        }
+                    }
+                    nodes.push(MultiProofNode::Leaf { key, data });
+                }
+                NodeVariant::Data(_) => {
+                    return Err(Exception::new(
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    ));
+                }
+            }
+        }
+
+        Ok(Some((entries, RangeProof::new(nodes))))
+    }
+
+    /// Gets the number of keys held under `root_hash`.  Returns 0 if the root could not be found.
+    #[inline]
+    pub fn len(&self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<u64> {
+        self.db
+            .get_node(root_hash)?
+            .map_or(Ok(0), |node| Self::count_leaves(&node.get_variant()))
+    }
+
+    /// Gets the key and value at position `index` in ascending order of keys under `root_hash`.
+    /// Returns `None` if there are not more than `index` keys in the tree.  Runs in time
+    /// proportional to the depth of the tree by steering on the leaf counts of branches.
+    #[inline]
+    pub fn nth(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        index: u64,
+    ) -> BinaryMerkleTreeResult<Option<([u8; KEY_LEN], ValueType)>> {
+        let mut variant = if let Some(n) = self.db.get_node(root_hash)? {
+            n.get_variant()
+        } else {
+            return Ok(None);
+        };
+        if index >= Self::count_leaves(&variant)? {
+            return Ok(None);
+        }
+
+        let mut remaining = index;
+        let mut depth = 0;
+
+        loop {
+            if depth > self.depth {
+                return Err(Exception::new("Depth of merkle tree exceeded"));
+            }
+
+            match variant {
+                NodeVariant::Branch(branch) => {
+                    let zero_variant = self.get_child_variant(branch.get_zero())?;
+                    let zero_count = Self::count_leaves(&zero_variant)?;
+                    if remaining < zero_count {
+                        variant = zero_variant;
+                    } else {
+                        remaining -= zero_count;
+                        variant = self.get_child_variant(branch.get_one())?;
+                    }
+                }
+                NodeVariant::Leaf(leaf) => {
+                    let value = self.get_value(leaf.get_data())?;
+                    return Ok(Some((*leaf.get_key(), value)));
+                }
+                NodeVariant::Data(_) => {
+                    return Err(Exception::new(
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    ));
+                }
+            }
+            depth += 1;
+        }
+    }
+
+    /// Gets the number of keys under `root_hash` that are less than `key`.  `key` does not need to
+    /// be in the tree.  Returns 0 if the root could not be found.  Runs in time proportional to the
+    /// depth of the tree by steering on the leaf counts of branches.
+    #[inline]
+    pub fn rank(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        key: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<u64> {
+        let mut variant = if let Some(n) = self.db.get_node(root_hash)? {
+            n.get_variant()
+        } else {
+            return Ok(0);
+        };
+
+        let mut rank = 0;
+        let mut depth = 0;
+
+        loop {
+            if depth > self.depth {
+                return Err(Exception::new("Depth of merkle tree exceeded"));
+            }
+
+            match variant {
+                NodeVariant::Branch(branch) => {
+                    let split_index = branch.get_split_index();
+                    let (min_key, max_key) =
+                        calc_prefix_bounds(branch.get_key(), split_index as usize);
+                    if *key <= min_key {
+                        return Ok(rank);
+                    }
+                    if *key > max_key {
+                        return Ok(rank + branch.get_count());
+                    }
+
+                    let zero_variant = self.get_child_variant(branch.get_zero())?;
+                    if choose_zero(key, split_index) {
+                        variant = zero_variant;
+                    } else {
+                        rank += Self::count_leaves(&zero_variant)?;
+                        variant = self.get_child_variant(branch.get_one())?;
+                    }
+                }
+                NodeVariant::Leaf(leaf) => {
+                    if leaf.get_key() < key {
+                        rank += 1;
+                    }
+                    return Ok(rank);
+                }
+                NodeVariant::Data(_) => {
+                    return Err(Exception::new(
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    ));
+                }
+            }
+            depth += 1;
+        }
+    }
+
+    /// Finds the keys that were added, removed, or changed going from `old_root` to `new_root`.
+    /// Subtrees found at the same location under both roots are skipped, so the cost grows with
+    /// the size of the change rather than the size of the tree.  Returns an error if either root
+    /// could not be found.
+    #[inline]
+    pub fn diff(
+        &self,
+        old_root: &[u8; HASH_LEN],
+        new_root: &[u8; HASH_LEN],
+    ) -> BinaryMerkleTreeResult<TreeDiff<ValueType, [u8; KEY_LEN]>> {
+        if self.db.get_node(old_root)?.is_none() {
+            return Err(Exception::new("Failed to find the old root"));
+        }
+        if self.db.get_node(new_root)?.is_none() {
+            return Err(Exception::new("Failed to find the new root"));
+        }
+        self.diff_from(Some(*old_root), Some(*new_root))
+    }
+
+    /// Exports the changes going from `previous_root` to `new_root` as a `Changeset`.  A
+    /// `previous_root` of `None` is an empty tree.
+    #[inline]
+    pub fn export_changeset(
+        &self,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        new_root: &[u8; HASH_LEN],
+    ) -> BinaryMerkleTreeResult<Changeset<ValueType, KEY_LEN, HASH_LEN>> {
+        if self.db.get_node(new_root)?.is_none() {
+            return Err(Exception::new("Failed to find the new root"));
+        }
+        let previous_start = match previous_root {
+            Some(root) => {
+                if self.db.get_node(root)?.is_none() {
+                    return Err(Exception::new("Failed to find the previous root"));
+                }
+                Some(*root)
+            }
+            None => None,
+        };
+
+        let diff = self.diff_from(previous_start, Some(*new_root))?;
+        let mut changes =
+            Vec::with_capacity(diff.added.len() + diff.removed.len() + diff.changed.len());
+        for (key, value) in diff.added {
+            changes.push((key, Some(value)));
+        }
+        for (key, _) in diff.removed {
+            changes.push((key, None));
+        }
+        for (key, _, new_value) in diff.changed {
+            changes.push((key, Some(new_value)));
+        }
+        changes.sort_by_key(|change| change.0);
+
+        Ok(Changeset::new(previous_start, *new_root, changes))
+    }
+
+    /// Checks the nodes held under `live_roots` against the database.  Every node reachable from a
+    /// live root is visited, and its references are compared with the number of nodes pointing to
+    /// it plus the number of times it is given as a live root.  Any node not reached is reported
+    /// as unreachable, so every root still in use must be given.  With `repair`, mismatched
+    /// references are rewritten with the expected counts, while other problems are only reported.
+    /// Returns an error if the database can not list the locations of its nodes.
+    #[inline]
+    pub fn check(
+        &mut self,
+        live_roots: &[[u8; HASH_LEN]],
+        repair: bool,
+    ) -> BinaryMerkleTreeResult<CheckReport<HASH_LEN>> {
+        let mut report = CheckReport::new();
+
+        let mut expected = BTreeMap::new();
+        for root in live_roots {
+            *expected.entry(*root).or_insert(0) += 1;
+        }
+
+        // Each entry holds the node pointing to the location, and whether it should be a data node
+        let mut queue = expected
+            .keys()
+            .map(|root| (None, *root, false))
+            .collect::<VecDeque<_>>();
+        let mut visited = BTreeSet::new();
+        let mut references = BTreeMap::new();
+        while let Some((parent, location, is_data)) = queue.pop_front() {
+            if visited.contains(&location) {
+                continue;
+            }
+            let node = if let Some(n) = self.db.get_node(&location)? {
+                n
+            } else {
+                report.dangling.push((parent, location));
+                continue;
+            };
+            visited.insert(location);
+
+            let stored_references = node.get_references();
+            match node.get_variant() {
+                NodeVariant::Branch(b) if !is_data => {
+                    for child in &[*b.get_zero(), *b.get_one()] {
+                        *expected.entry(*child).or_insert(0) += 1;
+                        queue.push_back((Some(location), *child, false));
+                    }
+                }
+                NodeVariant::Leaf(l) if !is_data => {
+                    let data = *l.get_data();
+                    *expected.entry(data).or_insert(0) += 1;
+                    queue.push_back((Some(location), data, true));
+                }
+                NodeVariant::Data(_) if is_data => {}
+                // Data nodes can only be pointed to by leaves, and leaves only hold data nodes
+                NodeVariant::Branch(_) | NodeVariant::Leaf(_) | NodeVariant::Data(_) => {
+                    report.corrupt.push(location);
+                    continue;
+                }
+            }
+            references.insert(location, stored_references);
+        }
+
+        for (location, stored_references) in references {
+            let expected_references = expected.get(&location).copied().unwrap_or(0);
+            if stored_references != expected_references {
+                report.mismatched_references.push((
+                    location,
+                    stored_references,
+                    expected_references,
+                ));
+            }
+        }
+
+        report.unreachable = self
+            .db
+            .locations()?
+            .into_iter()
+            .filter(|location| !visited.contains(location))
+            .collect();
+        report.unreachable.sort_unstable();
+        report.dangling.sort_unstable();
+        report.corrupt.sort_unstable();
+
+        if repair && !report.mismatched_references.is_empty() {
+            let result = self.rewrite_references(&report.mismatched_references);
+            self.finish(result)?;
+            report.repaired = true;
+        }
+        Ok(report)
+    }
+
+    /// Finds the changes to make on top of `ours` to merge in the changes from `base` to `theirs`.
+    /// Keys changed differently on both sides are passed to `resolver`.
+    fn merge_batch<ResolverType>(
+        &self,
+        base: &[u8; HASH_LEN],
+        ours: &[u8; HASH_LEN],
+        theirs: &[u8; HASH_LEN],
+        mut resolver: ResolverType,
+    ) -> BinaryMerkleTreeResult<WriteBatch<ValueType, KEY_LEN>>
+    where
+        ResolverType: FnMut(
+            &[u8; KEY_LEN],
+            Option<&ValueType>,
+            Option<&ValueType>,
+            Option<&ValueType>,
+        ) -> BinaryMerkleTreeResult<Option<ValueType>>,
+    {
+        let our_changes = Self::collect_changes(self.diff(base, ours)?);
+        let their_changes = Self::collect_changes(self.diff(base, theirs)?);
+
+        let mut batch = WriteBatch::new();
+        for (key, (base_value, their_value)) in their_changes {
+            let our_value = match our_changes.get(&key) {
+                // Only changed on their side
+                None => {
+                    match their_value {
+                        Some(v) => batch.put(key, v),
+                        None => batch.delete(key),
+                    }
+                    continue;
+                }
+                Some((_, our_value)) => our_value.as_ref(),
+            };
+
+            if Self::same_value(our_value, their_value.as_ref())? {
+                continue;
+            }
+            let merged_value =
+                resolver(&key, base_value.as_ref(), our_value, their_value.as_ref())?;
+            if Self::same_value(our_value, merged_value.as_ref())? {
+                continue;
+            }
+            match merged_value {
+                Some(v) => batch.put(key, v),
+                None => batch.delete(key),
+            }
+        }
+        Ok(batch)
+    }
+
+    /// Gets the old and new value of each key changed in `diff`, where `None` means the key is not
+    /// held.
+    fn collect_changes(
+        diff: TreeDiff<ValueType, [u8; KEY_LEN]>,
+    ) -> BTreeMap<[u8; KEY_LEN], (Option<ValueType>, Option<ValueType>)> {
+        let mut changes = BTreeMap::new();
+        for (key, value) in diff.added {
+            changes.insert(key, (None, Some(value)));
+        }
+        for (key, value) in diff.removed {
+            changes.insert(key, (Some(value), None));
+        }
+        for (key, old_value, new_value) in diff.changed {
+            changes.insert(key, (Some(old_value), Some(new_value)));
+        }
+        changes
+    }
+
+    /// Checks if two optional values have the same encoding.
+    fn same_value(
+        left: Option<&ValueType>,
+        right: Option<&ValueType>,
+    ) -> BinaryMerkleTreeResult<bool> {
+        match (left, right) {
+            (Some(l), Some(r)) => Ok(l.encode()? == r.encode()?),
+            (None, None) => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Finds the keys that differ between the subtrees at `old_start` and `new_start`, where
+    /// `None` is an empty tree.
+    fn diff_from(
+        &self,
+        old_start: Option<[u8; HASH_LEN]>,
+        new_start: Option<[u8; HASH_LEN]>,
+    ) -> BinaryMerkleTreeResult<TreeDiff<ValueType, [u8; KEY_LEN]>> {
+        let mut diff = TreeDiff::new();
+
+        // Subtrees covering the same keys are compared together, zero subtrees before one subtrees
+        let mut cell_stack = vec![(old_start, new_start, 0)];
+
+        while let Some((old_location, new_location, depth)) = cell_stack.pop() {
+            if old_location == new_location {
+                continue;
+            }
+
+            if depth > self.depth {
+                return Err(Exception::new("Depth of merkle tree exceeded"));
+            }
+
+            let old_variant = match old_location {
+                Some(location) => Some(self.get_child_variant(&location)?),
+                None => None,
+            };
+            let new_variant = match new_location {
+                Some(location) => Some(self.get_child_variant(&location)?),
+                None => None,
+            };
+
+            let old_region = old_variant.as_ref().map(Self::region).transpose()?;
+            let new_region = new_variant.as_ref().map(Self::region).transpose()?;
+
+            match (old_variant, new_variant) {
+                (Some(NodeVariant::Leaf(old_leaf)), Some(NodeVariant::Leaf(new_leaf)))
+                    if old_region == new_region =>
+                {
+                    diff.changed.push((
+                        *old_leaf.get_key(),
+                        self.get_value(old_leaf.get_data())?,
+                        self.get_value(new_leaf.get_data())?,
+                    ));
+                }
+                (Some(NodeVariant::Branch(old_branch)), Some(NodeVariant::Branch(new_branch)))
+                    if old_region == new_region =>
+                {
+                    cell_stack.push((
+                        Some(*old_branch.get_one()),
+                        Some(*new_branch.get_one()),
+                        depth + 1,
+                    ));
+                    cell_stack.push((
+                        Some(*old_branch.get_zero()),
+                        Some(*new_branch.get_zero()),
+                        depth + 1,
+                    ));
+                }
+                (Some(NodeVariant::Branch(old_branch)), Some(_))
+                    if Self::covers(old_region, new_region) =>
+                {
+                    let split_index = old_branch.get_split_index();
+                    if new_region.map_or(false, |(key, _)| choose_zero(&key, split_index)) {
+                        cell_stack.push((Some(*old_branch.get_one()), None, depth + 1));
+                        cell_stack.push((Some(*old_branch.get_zero()), new_location, depth + 1));
+                    } else {
+                        cell_stack.push((Some(*old_branch.get_one()), new_location, depth + 1));
+                        cell_stack.push((Some(*old_branch.get_zero()), None, depth + 1));
+                    }
+                }
+                (Some(_), Some(NodeVariant::Branch(new_branch)))
+                    if Self::covers(new_region, old_region) =>
+                {
+                    let split_index = new_branch.get_split_index();
+                    if old_region.map_or(false, |(key, _)| choose_zero(&key, split_index)) {
+                        cell_stack.push((None, Some(*new_branch.get_one()), depth + 1));
+                        cell_stack.push((old_location, Some(*new_branch.get_zero()), depth + 1));
+                    } else {
+                        cell_stack.push((old_location, Some(*new_branch.get_one()), depth + 1));
+                        cell_stack.push((None, Some(*new_branch.get_zero()), depth + 1));
+                    }
+                }
+                (Some(NodeVariant::Data(_)), _) | (_, Some(NodeVariant::Data(_))) => {
+                    return Err(Exception::new(
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    ));
+                }
+                (Some(_), Some(_)) => {
+                    // The subtrees hold no keys in common
+                    cell_stack.push((None, new_location, depth));
+                    cell_stack.push((old_location, None, depth));
+                }
+                (Some(NodeVariant::Branch(branch)), None) => {
+                    cell_stack.push((Some(*branch.get_one()), None, depth + 1));
+                    cell_stack.push((Some(*branch.get_zero()), None, depth + 1));
+                }
+                (None, Some(NodeVariant::Branch(branch))) => {
+                    cell_stack.push((None, Some(*branch.get_one()), depth + 1));
+                    cell_stack.push((None, Some(*branch.get_zero()), depth + 1));
+                }
+                (Some(NodeVariant::Leaf(leaf)), None) => {
+                    diff.removed
+                        .push((*leaf.get_key(), self.get_value(leaf.get_data())?));
+                }
+                (None, Some(NodeVariant::Leaf(leaf))) => {
+                    diff.added
+                        .push((*leaf.get_key(), self.get_value(leaf.get_data())?));
+                }
+                (None, None) => {}
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// Queues each node in `mismatched_references` to be written with its expected references.
+    fn rewrite_references(
+        &mut self,
+        mismatched_references: &[([u8; HASH_LEN], u64, u64)],
+    ) -> BinaryMerkleTreeResult<()> {
+        for &(location, _, expected_references) in mismatched_references {
+            if let Some(mut node) = self.db.get_node(&location)? {
+                node.set_references(expected_references);
+                self.db.insert(location, node)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the changes queued by an operation to the database if it succeeded, or discards them
+    /// if it failed, so that a failed operation leaves the database as it was.  If the database
+    /// can not discard them, nothing more is written until a later rollback succeeds.
+    fn finish<ResultType>(
+        &mut self,
+        result: BinaryMerkleTreeResult<ResultType>,
+    ) -> BinaryMerkleTreeResult<ResultType> {
+        let written = if self.unreverted {
+            Err(Exception::new(
+                "Failed to write changes: Changes of an earlier failed operation could not be rolled back",
+            ))
+        } else {
+            result.and_then(|r| self.db.batch_write().map(|()| r))
+        };
+        if written.is_err() {
+            self.unreverted = self.db.rollback().is_err();
+        }
+        written
+    }
+
+    /// Adds a reference to the node at `root`, as an operation that changes nothing under `root`
+    /// would, and returns `root`.
+    pub(crate) fn retain_root(
+        &mut self,
+        root: &[u8; HASH_LEN],
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
+        let result = self.stage_retain(root);
+        self.finish(result)
+    }
+
+    /// Queues a new reference to the node at `root`, so that it can be returned as the root of
+    /// another tree.
+    fn stage_retain(&mut self, root: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
+        if let Some(mut node) = self.db.get_node(root)? {
+            let references = node.get_references() + 1;
+            node.set_references(references);
+            self.db.insert(*root, node)?;
+            Ok(*root)
+        } else {
+            Err(Exception::new("Could not find root"))
+        }
+    }
+
+    /// Writes a newly merged branch node.  If another tree already holds the same branch, it gains
+    /// a reference instead, and the references just taken on its children are given back, as the
+    /// children are already held by the existing branch.  A child with no reference left to give
+    /// back means the references in the database are wrong, which is returned as an error.
+    fn write_branch(
+        &mut self,
+        location: [u8; HASH_LEN],
+        mut branch_node: NodeType,
+    ) -> BinaryMerkleTreeResult<()> {
+        let existing = if let Some(n) = self.db.get_node(&location)? {
+            n
+        } else {
+            branch_node.set_references(1);
+            return self.db.insert(location, branch_node);
+        };
+
+        branch_node.set_references(existing.get_references() + 1);
+        self.db.insert(location, branch_node)?;
+
+        if let NodeVariant::Branch(branch) = existing.get_variant() {
+            for child_location in &[*branch.get_zero(), *branch.get_one()] {
+                let mut child = if let Some(n) = self.db.get_node(child_location)? {
+                    n
+                } else {
+                    return Err(Exception::new(
+                        "Corrupt merkle tree: Failed to find child of existing branch",
+                    ));
+                };
+                let references = child.get_references();
+                if references == 0 {
+                    return Err(Exception::new(
+                        "Corrupt merkle tree: Child of existing branch has no references",
+                    ));
+                }
+                child.set_references(references - 1);
+                self.db.insert(*child_location, child)?;
+            }
+            return Ok(());
+        }
+        Err(Exception::new(
+            "Corrupt merkle tree: Found non branch node at branch location",
+        ))
+    }
+
+    /// Checks that the key width fits the `u8` split indexes of branches, and that both widths
+    /// can be recorded in the header of an encoded proof.
+    fn check_widths() -> BinaryMerkleTreeResult<()> {
+        if KEY_LEN == 0 || KEY_LEN > MAX_KEY_LEN {
+            return Err(Exception::new("Key length must be between 1 and 32 bytes"));
+        }
+        if HASH_LEN == 0 || HASH_LEN > u8::MAX as usize {
+            return Err(Exception::new(
+                "Hash length must be between 1 and 255 bytes",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Gets the number of leaves under a node.
+    fn count_leaves(
+        variant: &NodeVariant<BranchType, LeafType, DataType>,
+    ) -> BinaryMerkleTreeResult<u64> {
+        match variant {
+            NodeVariant::Branch(branch) => Ok(branch.get_count()),
+            NodeVariant::Leaf(_) => Ok(1),
+            NodeVariant::Data(_) => Err(Exception::new(
+                "Corrupt merkle tree: Found data node while traversing tree",
+            )),
+        }
+    }
+
+    /// Gets the child node of a branch at `location`.
+    fn get_child_variant(
+        &self,
+        location: &[u8; HASH_LEN],
+    ) -> BinaryMerkleTreeResult<NodeVariant<BranchType, LeafType, DataType>> {
+        self.db
+            .get_node(location)?
+            .map(Node::get_variant)
+            .ok_or_else(|| Exception::new("Corrupt merkle tree: Failed to get child node from DB"))
+    }
+
+    /// Gets the smallest key and the number of leading bits shared by every key under a node.
+    fn region(
+        variant: &NodeVariant<BranchType, LeafType, DataType>,
+    ) -> BinaryMerkleTreeResult<([u8; KEY_LEN], usize)> {
+        match variant {
+            NodeVariant::Branch(branch) => {
+                let prefix_bits = branch.get_split_index() as usize;
+                let (min_key, _) = calc_prefix_bounds(branch.get_key(), prefix_bits);
+                Ok((min_key, prefix_bits))
+            }
+            NodeVariant::Leaf(leaf) => Ok((*leaf.get_key(), KEY_LEN * 8)),
+            NodeVariant::Data(_) => Err(Exception::new(
+                "Corrupt merkle tree: Found data node while traversing tree",
+            )),
+        }
+    }
+
+    /// Checks if every key that could be under the `inner` region is also under the `outer`
+    /// region, and the regions are not the same.
+    fn covers(
+        outer: Option<([u8; KEY_LEN], usize)>,
+        inner: Option<([u8; KEY_LEN], usize)>,
+    ) -> bool {
+        match (outer, inner) {
+            (Some((outer_key, outer_bits)), Some((inner_key, inner_bits))) => {
+                outer_bits < inner_bits && calc_prefix_bounds(&inner_key, outer_bits).0 == outer_key
+            }
+            _ => false,
+        }
+    }
+
+    /// Gets the value held in the `Data` node at `location`.
+    fn get_value(&self, location: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<ValueType> {
+        let data_node = if let Some(d) = self.db.get_node(location)? {
+            d
+        } else {
+            return Err(Exception::new(
+                "Corrupt merkle tree: Failed to get data node from DB",
+            ));
+        };
+        if let NodeVariant::Data(data) = data_node.get_variant() {
+            return ValueType::decode(data.get_value());
+        }
+        Err(Exception::new(
+            "Corrupt merkle tree: Found non data node after leaf",
+        ))
+    }
+
+    /// Finds the closest key under `root_hash` that is less than `key` if `below` is set, or
+    /// greater than `key` otherwise.
+    fn find_neighbour(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        key: &[u8; KEY_LEN],
+        below: bool,
+    ) -> BinaryMerkleTreeResult<Option<[u8; KEY_LEN]>> {
+        // Subtrees are visited so that leaves are found in order moving away from `key`
+        let mut cell_stack = vec![(*root_hash, 0)];
+
+        while let Some((location, depth)) = cell_stack.pop() {
+            if depth > self.depth {
+                return Err(Exception::new("Depth of merkle tree exceeded"));
+            }
+
+            let node = if let Some(n) = self.db.get_node(&location)? {
+                n
+            } else {
+                return Err(Exception::new(
+                    "Corrupt merkle tree: Failed to get child node from DB",
+                ));
+            };
+
+            match node.get_variant() {
+                NodeVariant::Branch(branch) => {
+                    let (_, zero, one, split_index, branch_key) = branch.deconstruct();
+                    let (zero_min, _) = calc_child_bounds(&branch_key, split_index, true);
+                    let (_, one_max) = calc_child_bounds(&branch_key, split_index, false);
+                    if below {
+                        if zero_min >= *key {
+                            continue;
+                        }
+                        cell_stack.push((zero, depth + 1));
+                        cell_stack.push((one, depth + 1));
+                    } else {
+                        if one_max <= *key {
+                            continue;
+                        }
+                        cell_stack.push((one, depth + 1));
+                        cell_stack.push((zero, depth + 1));
+                    }
+                }
+                NodeVariant::Leaf(leaf) => {
+                    let leaf_key = leaf.get_key();
+                    if (below && leaf_key < key) || (!below && leaf_key > key) {
+                        return Ok(Some(*leaf_key));
+                    }
+                }
+                NodeVariant::Data(_) => {
+                    return Err(Exception::new(
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    ));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::constants::KEY_LEN;
+    use crate::utils::tree_utils::choose_zero;
+
+    use super::*;
+
+    #[test]
+    fn it_chooses_the_right_branch_easy() {
+        let key = [0x0F; KEY_LEN];
+        for i in 0..8 {
+            let expected_branch;
+            if i < 4 {
+                expected_branch = true;
+            } else {
+                expected_branch = false;
+            }
+            let branch = choose_zero(&key, i);
+            assert_eq!(branch, expected_branch);
+        }
     }
 
     #[test]
@@ -1487,6 +3069,48 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn it_calculates_the_bounds_of_the_children_of_a_branch() {
+        let branch_key = [0xA5u8; KEY_LEN];
+
+        let (zero_min, zero_max) = calc_child_bounds(&branch_key, 3, true);
+        let (one_min, one_max) = calc_child_bounds(&branch_key, 3, false);
+        let mut expected_zero_min = [0x00u8; KEY_LEN];
+        expected_zero_min[0] = 0xA0;
+        let mut expected_zero_max = [0xFFu8; KEY_LEN];
+        expected_zero_max[0] = 0xAF;
+        let mut expected_one_min = [0x00u8; KEY_LEN];
+        expected_one_min[0] = 0xB0;
+        let mut expected_one_max = [0xFFu8; KEY_LEN];
+        expected_one_max[0] = 0xBF;
+        assert_eq!(zero_min, expected_zero_min);
+        assert_eq!(zero_max, expected_zero_max);
+        assert_eq!(one_min, expected_one_min);
+        assert_eq!(one_max, expected_one_max);
+    }
+
+    #[test]
+    fn it_calculates_the_bounds_of_the_children_of_a_branch_on_a_boundary() {
+        let branch_key = [0xA5u8; KEY_LEN];
+
+        let (zero_min, zero_max) = calc_child_bounds(&branch_key, 8, true);
+        let (one_min, one_max) = calc_child_bounds(&branch_key, 8, false);
+        let mut expected_zero_min = [0x00u8; KEY_LEN];
+        expected_zero_min[0] = 0xA5;
+        let mut expected_zero_max = [0xFFu8; KEY_LEN];
+        expected_zero_max[0] = 0xA5;
+        expected_zero_max[1] = 0x7F;
+        let mut expected_one_min = [0x00u8; KEY_LEN];
+        expected_one_min[0] = 0xA5;
+        expected_one_min[1] = 0x80;
+        let mut expected_one_max = [0xFFu8; KEY_LEN];
+        expected_one_max[0] = 0xA5;
+        assert_eq!(zero_min, expected_zero_min);
+        assert_eq!(zero_max, expected_zero_max);
+        assert_eq!(one_min, expected_one_min);
+        assert_eq!(one_max, expected_one_max);
+    }
+
     #[test]
     fn it_splits_an_all_zeros_sorted_list_of_pairs() {
         // The complexity of these tests result from the fact that getting a key and splitting the
@@ -0,0 +1,110 @@
+use crate::proof::encoding::{ProofReader, ProofWriter, ABSENCE_PROOF_TAG, BRANCH_TAG, LEAF_TAG};
+use crate::proof::proof_branch::ProofBranch;
+use crate::traits::{Decode, Encode, Exception};
+
+/// The node at which the path of a key that is not in the tree ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathEnd<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    /// The path ends at a leaf holding a different key.
+    Leaf {
+        /// The associated key with the leaf.
+        key: [u8; KEY_LEN],
+        /// The location of the `Data` node under the leaf.
+        data: [u8; HASH_LEN],
+    },
+    /// The path ends at a branch whose key differs from the queried key before its split index.
+    Branch {
+        /// The location of the zero child of the branch.
+        zero: [u8; HASH_LEN],
+        /// The location of the one child of the branch.
+        one: [u8; HASH_LEN],
+        /// The index bit of the key on which the branch splits.
+        split_index: u8,
+        /// The associated key with the branch.
+        key: [u8; KEY_LEN],
+        /// The number of leaf nodes under the branch.
+        count: u64,
+    },
+}
+
+/// A proof that a key is not held under a given root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbsenceProof<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    /// The branches on the path towards the key, ordered from the root downwards.
+    pub branches: Vec<ProofBranch<KEY_LEN, HASH_LEN>>,
+    /// The node at which the path towards the key ends.
+    pub end: PathEnd<KEY_LEN, HASH_LEN>,
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> AbsenceProof<KEY_LEN, HASH_LEN> {
+    /// Creates a new `AbsenceProof`.
+    #[inline]
+    pub const fn new(
+        branches: Vec<ProofBranch<KEY_LEN, HASH_LEN>>,
+        end: PathEnd<KEY_LEN, HASH_LEN>,
+    ) -> Self {
+        Self { branches, end }
+    }
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for AbsenceProof<KEY_LEN, HASH_LEN> {
+    #[inline]
+    fn encode(&self) -> Result<Vec<u8>, Exception> {
+        let mut writer = ProofWriter::new(ABSENCE_PROOF_TAG);
+        writer.write_branches(&self.branches)?;
+        match &self.end {
+            PathEnd::Leaf { key, data } => {
+                writer.write_u8(LEAF_TAG);
+                writer.write_key(key);
+                writer.write_location(data);
+            }
+            PathEnd::Branch {
+                zero,
+                one,
+                split_index,
+                key,
+                count,
+            } => {
+                writer.write_u8(BRANCH_TAG);
+                writer.write_location(zero);
+                writer.write_location(one);
+                writer.write_u8(*split_index);
+                writer.write_key(key);
+                writer.write_u64(*count);
+            }
+        }
+        Ok(writer.finish())
+    }
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for AbsenceProof<KEY_LEN, HASH_LEN> {
+    #[inline]
+    fn decode(buffer: &[u8]) -> Result<Self, Exception> {
+        let mut reader = ProofReader::new(buffer, ABSENCE_PROOF_TAG)?;
+        let branches = reader.read_branches()?;
+        let end = match reader.read_u8()? {
+            LEAF_TAG => {
+                let key = reader.read_key()?;
+                let data = reader.read_location()?;
+                PathEnd::Leaf { key, data }
+            }
+            BRANCH_TAG => {
+                let zero = reader.read_location()?;
+                let one = reader.read_location()?;
+                let split_index = reader.read_u8()?;
+                let key = reader.read_key()?;
+                let count = reader.read_u64()?;
+                PathEnd::Branch {
+                    zero,
+                    one,
+                    split_index,
+                    key,
+                    count,
+                }
+            }
+            _ => return Err(Exception::new("Failed to decode proof: Unknown node tag")),
+        };
+        reader.finish()?;
+        Ok(Self::new(branches, end))
+    }
+}
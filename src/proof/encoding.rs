@@ -0,0 +1,315 @@
+use crate::constants::{LEGACY_PROOF_ENCODING_VERSION, PROOF_ENCODING_VERSION};
+use crate::proof::multi_proof::MultiProofNode;
+use crate::proof::proof_branch::ProofBranch;
+use crate::traits::Exception;
+
+/// The type tag of an encoded `InclusionProof`.
+pub const INCLUSION_PROOF_TAG: u8 = 0x01;
+/// The type tag of an encoded `AbsenceProof`.
+pub const ABSENCE_PROOF_TAG: u8 = 0x02;
+/// The type tag of an encoded `MultiProof`.
+pub const MULTI_PROOF_TAG: u8 = 0x03;
+/// The type tag of an encoded `RangeProof`.
+pub const RANGE_PROOF_TAG: u8 = 0x04;
+/// The type tag of an encoded `Changeset`.
+pub const CHANGESET_TAG: u8 = 0x05;
+
+/// The tag of a `PathEnd::Leaf` or `MultiProofNode::Leaf`.
+pub const LEAF_TAG: u8 = 0x00;
+/// The tag of a `PathEnd::Branch` or `MultiProofNode::Branch`.
+pub const BRANCH_TAG: u8 = 0x01;
+/// The tag of a `MultiProofNode::Hash`.
+pub const HASH_TAG: u8 = 0x02;
+
+/// Writes proofs in the canonical byte encoding, independent of any serialization feature.
+///
+/// Every encoded proof starts with a header of four bytes: `PROOF_ENCODING_VERSION`, the type
+/// tag of the proof, `KEY_LEN`, and `HASH_LEN`.  After the header:
+/// * Keys are written as `KEY_LEN` raw bytes, and locations as `HASH_LEN` raw bytes.
+/// * Split indexes and tags are written as a single byte.
+/// * Counts are written as big endian `u64`s.
+/// * Lists and byte strings are prefixed by their length as a big endian `u32`.
+/// * A `ProofBranch` is its sibling, split index, key, and count.
+/// * A leaf is `0x00`, its key, and its data location.
+/// * A branch ending an `AbsenceProof` is `0x01`, its zero and one locations, split index, key,
+///   and count.
+/// * A `MultiProofNode::Branch` is `0x01`, its split index, key, and count.
+/// * A `MultiProofNode::Hash` is `0x02` and its location.
+/// * A `Changeset` is `0x00`, or `0x01` and the previous root, then the new root and the list of
+///   changes.  Each change is its key followed by `0x00` for a removal, or `0x01` and the encoded
+///   value as a byte string.
+///
+/// Proofs written by version 1 of the encoding are still read.  Their header is three bytes,
+/// `LEGACY_PROOF_ENCODING_VERSION`, the type tag, and a key length of 32, with 32 byte keys and
+/// locations after it.
+pub struct ProofWriter<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    /// The bytes written so far.
+    buffer: Vec<u8>,
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> ProofWriter<KEY_LEN, HASH_LEN> {
+    /// Creates a new `ProofWriter` and writes the header for the given type of proof.
+    #[inline]
+    pub fn new(proof_tag: u8) -> Self {
+        Self {
+            buffer: vec![
+                PROOF_ENCODING_VERSION,
+                proof_tag,
+                KEY_LEN as u8,
+                HASH_LEN as u8,
+            ],
+        }
+    }
+
+    /// Writes a single byte.
+    #[inline]
+    pub fn write_u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    /// Writes the length of a list or byte string.
+    #[inline]
+    pub fn write_len(&mut self, len: usize) -> Result<(), Exception> {
+        if len > u32::MAX as usize {
+            return Err(Exception::new(
+                "Failed to encode proof: Length exceeds maximum",
+            ));
+        }
+        self.buffer.extend_from_slice(&(len as u32).to_be_bytes());
+        Ok(())
+    }
+
+    /// Writes a count.
+    #[inline]
+    pub fn write_u64(&mut self, value: u64) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /// Writes a key.
+    #[inline]
+    pub fn write_key(&mut self, key: &[u8; KEY_LEN]) {
+        self.buffer.extend_from_slice(key);
+    }
+
+    /// Writes a location.
+    #[inline]
+    pub fn write_location(&mut self, location: &[u8; HASH_LEN]) {
+        self.buffer.extend_from_slice(location);
+    }
+
+    /// Writes a byte string prefixed by its length.
+    #[inline]
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Exception> {
+        self.write_len(bytes.len())?;
+        self.buffer.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Writes a list of `ProofBranch`es prefixed by its length.
+    #[inline]
+    pub fn write_branches(
+        &mut self,
+        branches: &[ProofBranch<KEY_LEN, HASH_LEN>],
+    ) -> Result<(), Exception> {
+        self.write_len(branches.len())?;
+        for branch in branches {
+            self.write_location(&branch.sibling);
+            self.write_u8(branch.split_index);
+            self.write_key(&branch.key);
+            self.write_u64(branch.count);
+        }
+        Ok(())
+    }
+
+    /// Writes a list of `MultiProofNode`s prefixed by its length.
+    #[inline]
+    pub fn write_nodes(
+        &mut self,
+        nodes: &[MultiProofNode<KEY_LEN, HASH_LEN>],
+    ) -> Result<(), Exception> {
+        self.write_len(nodes.len())?;
+        for node in nodes {
+            match node {
+                MultiProofNode::Branch {
+                    split_index,
+                    key,
+                    count,
+                } => {
+                    self.write_u8(BRANCH_TAG);
+                    self.write_u8(*split_index);
+                    self.write_key(key);
+                    self.write_u64(*count);
+                }
+                MultiProofNode::Leaf { key, data } => {
+                    self.write_u8(LEAF_TAG);
+                    self.write_key(key);
+                    self.write_location(data);
+                }
+                MultiProofNode::Hash(location) => {
+                    self.write_u8(HASH_TAG);
+                    self.write_location(location);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the encoded bytes.
+    #[inline]
+    pub fn finish(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+/// Reads proofs written in the canonical byte encoding.  See `ProofWriter` for the layout.
+pub struct ProofReader<'a, const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    /// The bytes being read.
+    buffer: &'a [u8],
+    /// The position of the next byte to read.
+    position: usize,
+}
+
+impl<'a, const KEY_LEN: usize, const HASH_LEN: usize> ProofReader<'a, KEY_LEN, HASH_LEN> {
+    /// Creates a new `ProofReader`, checking the header for the given type of proof.
+    #[inline]
+    pub fn new(buffer: &'a [u8], proof_tag: u8) -> Result<Self, Exception> {
+        let mut reader = Self {
+            buffer,
+            position: 0,
+        };
+        let version = reader.read_u8()?;
+        if version != PROOF_ENCODING_VERSION && version != LEGACY_PROOF_ENCODING_VERSION {
+            return Err(Exception::new(
+                "Failed to decode proof: Unsupported encoding version",
+            ));
+        }
+        if reader.read_u8()? != proof_tag {
+            return Err(Exception::new("Failed to decode proof: Wrong proof type"));
+        }
+        if reader.read_u8()? as usize != KEY_LEN {
+            return Err(Exception::new("Failed to decode proof: Wrong key length"));
+        }
+        if version == LEGACY_PROOF_ENCODING_VERSION {
+            // Version 1 had no hash length, as keys and hashes were always 32 bytes.
+            if KEY_LEN != 32 || HASH_LEN != 32 {
+                return Err(Exception::new(
+                    "Failed to decode proof: Version 1 only holds 32 byte keys and hashes",
+                ));
+            }
+        } else if reader.read_u8()? as usize != HASH_LEN {
+            return Err(Exception::new("Failed to decode proof: Wrong hash length"));
+        }
+        Ok(reader)
+    }
+
+    /// Reads the next `len` bytes.
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Exception> {
+        if self.buffer.len() - self.position < len {
+            return Err(Exception::new(
+                "Failed to decode proof: Unexpected end of buffer",
+            ));
+        }
+        let bytes = &self.buffer[self.position..self.position + len];
+        self.position += len;
+        Ok(bytes)
+    }
+
+    /// Reads a single byte.
+    #[inline]
+    pub fn read_u8(&mut self) -> Result<u8, Exception> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads the length of a list or byte string.
+    #[inline]
+    pub fn read_len(&mut self) -> Result<usize, Exception> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_be_bytes(bytes) as usize)
+    }
+
+    /// Reads a count.
+    #[inline]
+    pub fn read_u64(&mut self) -> Result<u64, Exception> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    /// Reads a key.
+    #[inline]
+    pub fn read_key(&mut self) -> Result<[u8; KEY_LEN], Exception> {
+        let mut key = [0; KEY_LEN];
+        key.copy_from_slice(self.take(KEY_LEN)?);
+        Ok(key)
+    }
+
+    /// Reads a location.
+    #[inline]
+    pub fn read_location(&mut self) -> Result<[u8; HASH_LEN], Exception> {
+        let mut location = [0; HASH_LEN];
+        location.copy_from_slice(self.take(HASH_LEN)?);
+        Ok(location)
+    }
+
+    /// Reads a byte string prefixed by its length.
+    #[inline]
+    pub fn read_bytes(&mut self) -> Result<Vec<u8>, Exception> {
+        let len = self.read_len()?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    /// Reads a list of `ProofBranch`es prefixed by its length.
+    #[inline]
+    pub fn read_branches(&mut self) -> Result<Vec<ProofBranch<KEY_LEN, HASH_LEN>>, Exception> {
+        let len = self.read_len()?;
+        let mut branches = Vec::new();
+        for _ in 0..len {
+            let sibling = self.read_location()?;
+            let split_index = self.read_u8()?;
+            let key = self.read_key()?;
+            let count = self.read_u64()?;
+            branches.push(ProofBranch::new(sibling, split_index, key, count));
+        }
+        Ok(branches)
+    }
+
+    /// Reads a list of `MultiProofNode`s prefixed by its length.
+    #[inline]
+    pub fn read_nodes(&mut self) -> Result<Vec<MultiProofNode<KEY_LEN, HASH_LEN>>, Exception> {
+        let len = self.read_len()?;
+        let mut nodes = Vec::new();
+        for _ in 0..len {
+            let node = match self.read_u8()? {
+                BRANCH_TAG => {
+                    let split_index = self.read_u8()?;
+                    let key = self.read_key()?;
+                    let count = self.read_u64()?;
+                    MultiProofNode::Branch {
+                        split_index,
+                        key,
+                        count,
+                    }
+                }
+                LEAF_TAG => {
+                    let key = self.read_key()?;
+                    let data = self.read_location()?;
+                    MultiProofNode::Leaf { key, data }
+                }
+                HASH_TAG => MultiProofNode::Hash(self.read_location()?),
+                _ => return Err(Exception::new("Failed to decode proof: Unknown node tag")),
+            };
+            nodes.push(node);
+        }
+        Ok(nodes)
+    }
+
+    /// Checks that every byte has been read.
+    #[inline]
+    pub fn finish(self) -> Result<(), Exception> {
+        if self.position != self.buffer.len() {
+            return Err(Exception::new("Failed to decode proof: Trailing bytes"));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use crate::proof::encoding::{ProofReader, ProofWriter, INCLUSION_PROOF_TAG};
+use crate::proof::proof_branch::ProofBranch;
+use crate::traits::{Decode, Encode, Exception};
+
+/// A proof that a key and its value are held under a given root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InclusionProof<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    /// The branches on the path to the leaf, ordered from the root downwards.
+    pub branches: Vec<ProofBranch<KEY_LEN, HASH_LEN>>,
+    /// The associated key with the leaf.
+    pub key: [u8; KEY_LEN],
+    /// The location of the `Data` node under the leaf.
+    pub data: [u8; HASH_LEN],
+    /// The encoded value held by the `Data` node.
+    pub value: Vec<u8>,
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> InclusionProof<KEY_LEN, HASH_LEN> {
+    /// Creates a new `InclusionProof`.
+    #[inline]
+    pub const fn new(
+        branches: Vec<ProofBranch<KEY_LEN, HASH_LEN>>,
+        key: [u8; KEY_LEN],
+        data: [u8; HASH_LEN],
+        value: Vec<u8>,
+    ) -> Self {
+        Self {
+            branches,
+            key,
+            data,
+            value,
+        }
+    }
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for InclusionProof<KEY_LEN, HASH_LEN> {
+    #[inline]
+    fn encode(&self) -> Result<Vec<u8>, Exception> {
+        let mut writer = ProofWriter::new(INCLUSION_PROOF_TAG);
+        writer.write_branches(&self.branches)?;
+        writer.write_key(&self.key);
+        writer.write_location(&self.data);
+        writer.write_bytes(&self.value)?;
+        Ok(writer.finish())
+    }
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for InclusionProof<KEY_LEN, HASH_LEN> {
+    #[inline]
+    fn decode(buffer: &[u8]) -> Result<Self, Exception> {
+        let mut reader = ProofReader::new(buffer, INCLUSION_PROOF_TAG)?;
+        let branches = reader.read_branches()?;
+        let key = reader.read_key()?;
+        let data = reader.read_location()?;
+        let value = reader.read_bytes()?;
+        reader.finish()?;
+        Ok(Self::new(branches, key, data, value))
+    }
+}
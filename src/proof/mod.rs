@@ -0,0 +1,14 @@
+/// Holds the `AbsenceProof` struct.
+pub mod absence_proof;
+/// Holds the canonical byte encoding for proofs.
+pub mod encoding;
+/// Holds the `InclusionProof` struct.
+pub mod inclusion_proof;
+/// Holds the `MultiProof` struct.
+pub mod multi_proof;
+/// Holds the `ProofBranch` struct.
+pub mod proof_branch;
+/// Holds the `RangeProof` struct.
+pub mod range_proof;
+/// Holds the functions for verifying proofs without access to the tree.
+pub mod verify;
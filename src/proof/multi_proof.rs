@@ -0,0 +1,61 @@
+use crate::proof::encoding::{ProofReader, ProofWriter, MULTI_PROOF_TAG};
+use crate::traits::{Decode, Encode, Exception};
+
+/// A node of a `MultiProof`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultiProofNode<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    /// A branch on the path of at least one key.  It is followed by its zero subtree, then its one
+    /// subtree.
+    Branch {
+        /// The index bit of the key on which the branch splits.
+        split_index: u8,
+        /// The associated key with the branch.
+        key: [u8; KEY_LEN],
+        /// The number of leaf nodes under the branch.
+        count: u64,
+    },
+    /// A leaf at the end of the path of at least one key.
+    Leaf {
+        /// The associated key with the leaf.
+        key: [u8; KEY_LEN],
+        /// The location of the `Data` node under the leaf.
+        data: [u8; HASH_LEN],
+    },
+    /// The location of a subtree that is not on the path of any key.
+    Hash([u8; HASH_LEN]),
+}
+
+/// A proof for the presence or absence of several keys under a given root.  Nodes shared by the
+/// paths of the keys are only held once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiProof<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    /// The nodes of the pruned tree, ordered depth first with zero subtrees before one subtrees.
+    pub nodes: Vec<MultiProofNode<KEY_LEN, HASH_LEN>>,
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> MultiProof<KEY_LEN, HASH_LEN> {
+    /// Creates a new `MultiProof`.
+    #[inline]
+    pub const fn new(nodes: Vec<MultiProofNode<KEY_LEN, HASH_LEN>>) -> Self {
+        Self { nodes }
+    }
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for MultiProof<KEY_LEN, HASH_LEN> {
+    #[inline]
+    fn encode(&self) -> Result<Vec<u8>, Exception> {
+        let mut writer = ProofWriter::new(MULTI_PROOF_TAG);
+        writer.write_nodes(&self.nodes)?;
+        Ok(writer.finish())
+    }
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for MultiProof<KEY_LEN, HASH_LEN> {
+    #[inline]
+    fn decode(buffer: &[u8]) -> Result<Self, Exception> {
+        let mut reader = ProofReader::new(buffer, MULTI_PROOF_TAG)?;
+        let nodes = reader.read_nodes()?;
+        reader.finish()?;
+        Ok(Self::new(nodes))
+    }
+}
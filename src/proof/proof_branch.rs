@@ -0,0 +1,30 @@
+/// A branch on the path from a root to a leaf, as recorded in a proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofBranch<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    /// The location of the child of this branch that is not on the path.
+    pub sibling: [u8; HASH_LEN],
+    /// The index bit of the key on which this branch splits.
+    pub split_index: u8,
+    /// The associated key with this branch.
+    pub key: [u8; KEY_LEN],
+    /// The number of leaf nodes under this branch.
+    pub count: u64,
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> ProofBranch<KEY_LEN, HASH_LEN> {
+    /// Creates a new `ProofBranch`.
+    #[inline]
+    pub const fn new(
+        sibling: [u8; HASH_LEN],
+        split_index: u8,
+        key: [u8; KEY_LEN],
+        count: u64,
+    ) -> Self {
+        Self {
+            sibling,
+            split_index,
+            key,
+            count,
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::proof::encoding::{ProofReader, ProofWriter, RANGE_PROOF_TAG};
+use crate::proof::multi_proof::MultiProofNode;
+use crate::traits::{Decode, Encode, Exception};
+
+/// A proof that a set of keys is every key held within an interval under a given root.  The leaves
+/// holding the closest keys on either side of the interval are included as its boundaries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeProof<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    /// The nodes of the pruned tree, ordered depth first with zero subtrees before one subtrees.
+    pub nodes: Vec<MultiProofNode<KEY_LEN, HASH_LEN>>,
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> RangeProof<KEY_LEN, HASH_LEN> {
+    /// Creates a new `RangeProof`.
+    #[inline]
+    pub const fn new(nodes: Vec<MultiProofNode<KEY_LEN, HASH_LEN>>) -> Self {
+        Self { nodes }
+    }
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for RangeProof<KEY_LEN, HASH_LEN> {
+    #[inline]
+    fn encode(&self) -> Result<Vec<u8>, Exception> {
+        let mut writer = ProofWriter::new(RANGE_PROOF_TAG);
+        writer.write_nodes(&self.nodes)?;
+        Ok(writer.finish())
+    }
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for RangeProof<KEY_LEN, HASH_LEN> {
+    #[inline]
+    fn decode(buffer: &[u8]) -> Result<Self, Exception> {
+        let mut reader = ProofReader::new(buffer, RANGE_PROOF_TAG)?;
+        let nodes = reader.read_nodes()?;
+        reader.finish()?;
+        Ok(Self::new(nodes))
+    }
+}
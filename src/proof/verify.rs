@@ -0,0 +1,437 @@
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::proof::absence_proof::{AbsenceProof, PathEnd};
+use crate::proof::inclusion_proof::InclusionProof;
+use crate::proof::multi_proof::{MultiProof, MultiProofNode};
+use crate::proof::proof_branch::ProofBranch;
+use crate::proof::range_proof::RangeProof;
+use crate::traits::{Encode, Exception, Hasher, HashingScheme};
+use crate::tree_hasher::tagged_scheme::TaggedScheme;
+use crate::utils::tree_utils::{choose_zero, is_descendant};
+
+/// Verifies that `key` and `value` are held under `root` using the given `InclusionProof`.
+/// Only the hashing function of the tree is needed; no `Database` or `MerkleBIT` is required.
+#[inline]
+pub fn verify_proof<HasherType, ValueType, const KEY_LEN: usize, const HASH_LEN: usize>(
+    root: &[u8; HASH_LEN],
+    key: &[u8; KEY_LEN],
+    value: &ValueType,
+    proof: &InclusionProof<KEY_LEN, HASH_LEN>,
+) -> BinaryMerkleTreeResult<bool>
+where
+    HasherType: Hasher<HASH_LEN, HashType = HasherType>,
+    ValueType: Encode,
+{
+    verify_proof_with_scheme(
+        &TaggedScheme::<HasherType>::default(),
+        root,
+        key,
+        value,
+        proof,
+    )
+}
+
+/// Verifies an `InclusionProof` as in `verify_proof`, for a tree whose node locations are
+/// calculated with `scheme`.
+#[inline]
+pub fn verify_proof_with_scheme<
+    SchemeType,
+    ValueType,
+    const KEY_LEN: usize,
+    const HASH_LEN: usize,
+>(
+    scheme: &SchemeType,
+    root: &[u8; HASH_LEN],
+    key: &[u8; KEY_LEN],
+    value: &ValueType,
+    proof: &InclusionProof<KEY_LEN, HASH_LEN>,
+) -> BinaryMerkleTreeResult<bool>
+where
+    SchemeType: HashingScheme<KEY_LEN, HASH_LEN>,
+    ValueType: Encode,
+{
+    if proof.key != *key {
+        return Ok(false);
+    }
+
+    let data_location = scheme.data_location(key, &value.encode()?);
+    if proof.data != data_location {
+        return Ok(false);
+    }
+
+    let leaf = scheme.leaf_location(key, &data_location);
+    Ok(calc_root(scheme, key, leaf, &proof.branches, None) == Some(*root))
+}
+
+/// Verifies that `key` is not held under `root` using the given `AbsenceProof`, for a tree whose
+/// node locations are calculated with `scheme`.
+///
+/// The path of `key` is followed using the split indexes and keys given in the proof, so `scheme`
+/// must commit to both in the location of each branch, as `TaggedScheme::committed` does.  Returns
+/// an error for any other scheme.
+#[inline]
+pub fn verify_absence_proof<SchemeType, const KEY_LEN: usize, const HASH_LEN: usize>(
+    scheme: &SchemeType,
+    root: &[u8; HASH_LEN],
+    key: &[u8; KEY_LEN],
+    proof: &AbsenceProof<KEY_LEN, HASH_LEN>,
+) -> BinaryMerkleTreeResult<bool>
+where
+    SchemeType: HashingScheme<KEY_LEN, HASH_LEN>,
+{
+    if !scheme.commits_position() {
+        return Err(Exception::new(
+            "Absence proofs require a scheme that commits to the position of branches",
+        ));
+    }
+
+    let (end_location, end_split_index) = match &proof.end {
+        PathEnd::Leaf {
+            key: leaf_key,
+            data,
+        } => {
+            if leaf_key == key {
+                return Ok(false);
+            }
+            (scheme.leaf_location(leaf_key, data), None)
+        }
+        PathEnd::Branch {
+            zero,
+            one,
+            split_index,
+            key: branch_key,
+            count,
+        } => {
+            if is_descendant(key, *split_index, branch_key) {
+                return Ok(false);
+            }
+            (
+                scheme.branch_location(zero, one, *split_index, branch_key, *count),
+                Some(*split_index),
+            )
+        }
+    };
+
+    Ok(calc_root(scheme, key, end_location, &proof.branches, end_split_index) == Some(*root))
+}
+
+/// Verifies a set of claims about keys held under `root` using the given `MultiProof`.  Each claim
+/// is a key paired with either its value, or `None` if the key is claimed to be absent.  Returns
+/// `false` if any claim does not hold, or if the nodes of the proof do not form a single tree.
+///
+/// As with `verify_absence_proof`, keys can only be claimed absent for a tree built with a scheme
+/// that commits to the position of branches, so claims of absence return an error here.
+#[inline]
+pub fn verify_multiproof<HasherType, ValueType, const KEY_LEN: usize, const HASH_LEN: usize>(
+    root: &[u8; HASH_LEN],
+    claims: &[(&[u8; KEY_LEN], Option<&ValueType>)],
+    proof: &MultiProof<KEY_LEN, HASH_LEN>,
+) -> BinaryMerkleTreeResult<bool>
+where
+    HasherType: Hasher<HASH_LEN, HashType = HasherType>,
+    ValueType: Encode,
+{
+    verify_multiproof_with_scheme(&TaggedScheme::<HasherType>::default(), root, claims, proof)
+}
+
+/// Verifies a `MultiProof` as in `verify_multiproof`, for a tree whose node locations are
+/// calculated with `scheme`.  Returns an error if a key is claimed absent and `scheme` does not
+/// commit to the position of branches.
+#[inline]
+pub fn verify_multiproof_with_scheme<
+    SchemeType,
+    ValueType,
+    const KEY_LEN: usize,
+    const HASH_LEN: usize,
+>(
+    scheme: &SchemeType,
+    root: &[u8; HASH_LEN],
+    claims: &[(&[u8; KEY_LEN], Option<&ValueType>)],
+    proof: &MultiProof<KEY_LEN, HASH_LEN>,
+) -> BinaryMerkleTreeResult<bool>
+where
+    SchemeType: HashingScheme<KEY_LEN, HASH_LEN>,
+    ValueType: Encode,
+{
+    if !scheme.commits_position() && claims.iter().any(|claim| claim.1.is_none()) {
+        return Err(Exception::new(
+            "Absence claims require a scheme that commits to the position of branches",
+        ));
+    }
+
+    let mut data_claims = Vec::with_capacity(claims.len());
+    for &(key, value) in claims {
+        let data = match value {
+            Some(v) => Some(scheme.data_location(key, &v.encode()?)),
+            None => None,
+        };
+        data_claims.push((key, data));
+    }
+
+    data_claims.sort_by(|a, b| a.0.cmp(b.0));
+    if data_claims.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+        return Ok(false);
+    }
+
+    let mut position = 0;
+    let calculated_root =
+        calc_multiproof_root(scheme, &proof.nodes, &mut position, &data_claims, None);
+    Ok(position == proof.nodes.len() && calculated_root == Some(*root))
+}
+
+/// Verifies that `entries` are every key and value held under `root` with a key between `start`
+/// and `end` inclusive, using the given `RangeProof`.  `entries` must be sorted by key.
+///
+/// Keys are ordered by their position in the tree, so no pruned subtree may lie between the
+/// boundary leaves of the interval.
+#[inline]
+pub fn verify_range_proof<HasherType, ValueType, const KEY_LEN: usize, const HASH_LEN: usize>(
+    root: &[u8; HASH_LEN],
+    start: &[u8; KEY_LEN],
+    end: &[u8; KEY_LEN],
+    entries: &[(&[u8; KEY_LEN], &ValueType)],
+    proof: &RangeProof<KEY_LEN, HASH_LEN>,
+) -> BinaryMerkleTreeResult<bool>
+where
+    HasherType: Hasher<HASH_LEN, HashType = HasherType>,
+    ValueType: Encode,
+{
+    verify_range_proof_with_scheme(
+        &TaggedScheme::<HasherType>::default(),
+        root,
+        start,
+        end,
+        entries,
+        proof,
+    )
+}
+
+/// Verifies a `RangeProof` as in `verify_range_proof`, for a tree whose node locations are
+/// calculated with `scheme`.
+#[inline]
+pub fn verify_range_proof_with_scheme<
+    SchemeType,
+    ValueType,
+    const KEY_LEN: usize,
+    const HASH_LEN: usize,
+>(
+    scheme: &SchemeType,
+    root: &[u8; HASH_LEN],
+    start: &[u8; KEY_LEN],
+    end: &[u8; KEY_LEN],
+    entries: &[(&[u8; KEY_LEN], &ValueType)],
+    proof: &RangeProof<KEY_LEN, HASH_LEN>,
+) -> BinaryMerkleTreeResult<bool>
+where
+    SchemeType: HashingScheme<KEY_LEN, HASH_LEN>,
+    ValueType: Encode,
+{
+    if start > end {
+        return Ok(false);
+    }
+
+    let mut position = 0;
+    let mut terminals = Vec::with_capacity(proof.nodes.len());
+    let calculated_root =
+        calc_range_root(scheme, &proof.nodes, &mut position, &mut terminals, None);
+    if position != proof.nodes.len() || calculated_root != Some(*root) {
+        return Ok(false);
+    }
+
+    // Leaves must appear in ascending order of their keys
+    let mut previous_key = None;
+    for &(key, _) in terminals.iter().flatten() {
+        if previous_key.map_or(false, |previous| previous >= key) {
+            return Ok(false);
+        }
+        previous_key = Some(key);
+    }
+
+    let lower = terminals
+        .iter()
+        .rposition(|terminal| terminal.map_or(false, |(key, _)| key < start))
+        .map_or(0, |index| index + 1);
+    let upper = terminals
+        .iter()
+        .position(|terminal| terminal.map_or(false, |(key, _)| key > end))
+        .unwrap_or(terminals.len());
+    if lower > upper || upper - lower != entries.len() {
+        return Ok(false);
+    }
+
+    for (terminal, &(key, value)) in terminals[lower..upper].iter().zip(entries.iter()) {
+        if let Some((leaf_key, data)) = *terminal {
+            if leaf_key != key || *data != scheme.data_location(key, &value.encode()?) {
+                return Ok(false);
+            }
+        } else {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Calculates the location of the subtree starting at `position` in `nodes`, consuming its nodes.
+/// Each leaf or pruned subtree found is appended to `terminals` in order, with `None` for pruned
+/// subtrees.  Returns `None` if the split indexes of the branches do not increase on the way down,
+/// which also bounds the depth of the recursion.
+fn calc_range_root<'a, SchemeType, const KEY_LEN: usize, const HASH_LEN: usize>(
+    scheme: &SchemeType,
+    nodes: &'a [MultiProofNode<KEY_LEN, HASH_LEN>],
+    position: &mut usize,
+    terminals: &mut Vec<Option<(&'a [u8; KEY_LEN], &'a [u8; HASH_LEN])>>,
+    parent_split_index: Option<u8>,
+) -> Option<[u8; HASH_LEN]>
+where
+    SchemeType: HashingScheme<KEY_LEN, HASH_LEN>,
+{
+    let node = nodes.get(*position)?;
+    *position += 1;
+
+    match node {
+        MultiProofNode::Branch {
+            split_index,
+            key,
+            count,
+        } => {
+            // Split indexes strictly increase on the way down from the root
+            if let Some(index) = parent_split_index {
+                if *split_index <= index {
+                    return None;
+                }
+            }
+
+            let zero = calc_range_root(scheme, nodes, position, terminals, Some(*split_index))?;
+            let one = calc_range_root(scheme, nodes, position, terminals, Some(*split_index))?;
+            Some(scheme.branch_location(&zero, &one, *split_index, key, *count))
+        }
+        MultiProofNode::Leaf { key, data } => {
+            terminals.push(Some((key, data)));
+            Some(scheme.leaf_location(key, data))
+        }
+        MultiProofNode::Hash(location) => {
+            terminals.push(None);
+            Some(*location)
+        }
+    }
+}
+
+/// A key paired with the location of its claimed `Data` node, or `None` if it is claimed absent.
+type DataClaim<'a, const KEY_LEN: usize, const HASH_LEN: usize> =
+    (&'a [u8; KEY_LEN], Option<[u8; HASH_LEN]>);
+
+/// Calculates the location of the subtree starting at `position` in `nodes`, consuming its nodes.
+/// Returns `None` if the subtree does not support the claims that reach it.
+fn calc_multiproof_root<SchemeType, const KEY_LEN: usize, const HASH_LEN: usize>(
+    scheme: &SchemeType,
+    nodes: &[MultiProofNode<KEY_LEN, HASH_LEN>],
+    position: &mut usize,
+    claims: &[DataClaim<KEY_LEN, HASH_LEN>],
+    parent_split_index: Option<u8>,
+) -> Option<[u8; HASH_LEN]>
+where
+    SchemeType: HashingScheme<KEY_LEN, HASH_LEN>,
+{
+    let node = nodes.get(*position)?;
+    *position += 1;
+
+    match node {
+        MultiProofNode::Branch {
+            split_index,
+            key: branch_key,
+            count,
+        } => {
+            // Split indexes strictly increase on the way down from the root
+            if let Some(index) = parent_split_index {
+                if *split_index <= index {
+                    return None;
+                }
+            }
+
+            let mut zeros = Vec::with_capacity(claims.len());
+            let mut ones = Vec::with_capacity(claims.len());
+            for claim in claims {
+                if !is_descendant(claim.0, *split_index, branch_key) {
+                    if claim.1.is_some() {
+                        return None;
+                    }
+                } else if choose_zero(claim.0, *split_index) {
+                    zeros.push(*claim);
+                } else {
+                    ones.push(*claim);
+                }
+            }
+
+            let zero = calc_multiproof_root(scheme, nodes, position, &zeros, Some(*split_index))?;
+            let one = calc_multiproof_root(scheme, nodes, position, &ones, Some(*split_index))?;
+            Some(scheme.branch_location(&zero, &one, *split_index, branch_key, *count))
+        }
+        MultiProofNode::Leaf {
+            key: leaf_key,
+            data,
+        } => {
+            for claim in claims {
+                if claim.0 == leaf_key {
+                    if claim.1 != Some(*data) {
+                        return None;
+                    }
+                } else if claim.1.is_some() {
+                    return None;
+                }
+            }
+            Some(scheme.leaf_location(leaf_key, data))
+        }
+        MultiProofNode::Hash(location) => {
+            if !claims.is_empty() {
+                return None;
+            }
+            Some(*location)
+        }
+    }
+}
+
+/// Calculates the root from the location of the node at the end of the path of `key`.  Returns
+/// `None` if the branches do not describe a valid path for `key`.
+fn calc_root<SchemeType, const KEY_LEN: usize, const HASH_LEN: usize>(
+    scheme: &SchemeType,
+    key: &[u8; KEY_LEN],
+    end_location: [u8; HASH_LEN],
+    branches: &[ProofBranch<KEY_LEN, HASH_LEN>],
+    end_split_index: Option<u8>,
+) -> Option<[u8; HASH_LEN]>
+where
+    SchemeType: HashingScheme<KEY_LEN, HASH_LEN>,
+{
+    let mut location = end_location;
+    let mut previous_split_index = end_split_index;
+    for branch in branches.iter().rev() {
+        // Split indexes strictly increase on the way down from the root
+        if let Some(index) = previous_split_index {
+            if branch.split_index >= index {
+                return None;
+            }
+        }
+        if !is_descendant(key, branch.split_index, &branch.key) {
+            return None;
+        }
+        previous_split_index = Some(branch.split_index);
+
+        location = if choose_zero(key, branch.split_index) {
+            scheme.branch_location(
+                &location,
+                &branch.sibling,
+                branch.split_index,
+                &branch.key,
+                branch.count,
+            )
+        } else {
+            scheme.branch_location(
+                &branch.sibling,
+                &location,
+                branch.split_index,
+                &branch.key,
+                branch.count,
+            )
+        };
+    }
+    Some(location)
+}
@@ -1,30 +1,57 @@
 #[cfg(not(any(feature = "use_hashbrown")))]
 use std::collections::HashMap;
+use std::ops::RangeBounds;
 use std::path::PathBuf;
 
 #[cfg(feature = "use_hashbrown")]
 use hashbrown::HashMap;
 
-use crate::constants::KEY_LEN;
 use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT};
-use crate::traits::{Database, Decode, Encode};
+use crate::proof::absence_proof::AbsenceProof;
+use crate::proof::inclusion_proof::InclusionProof;
+use crate::proof::multi_proof::MultiProof;
+use crate::proof::range_proof::RangeProof;
+use crate::traits::{Database, Decode, Encode, Hasher};
 use crate::tree::tree_branch::TreeBranch;
 use crate::tree::tree_data::TreeData;
 use crate::tree::tree_leaf::TreeLeaf;
 use crate::tree::tree_node::TreeNode;
 use crate::tree_db::rocksdb::RocksDB;
+use crate::tree_hasher::tagged_scheme::TaggedScheme;
 use crate::tree_hasher::TreeHasher;
+use crate::utils::changeset::Changeset;
+use crate::utils::check_report::CheckReport;
+use crate::utils::tree_diff::TreeDiff;
+use crate::utils::tree_iter::TreeIter;
+use crate::utils::write_batch::WriteBatch;
 
-pub struct RocksTree<ValueType>
-where
+pub struct RocksTree<
+    ValueType,
+    const KEY_LEN: usize = 32,
+    const HASH_LEN: usize = 32,
+    HasherType = TreeHasher,
+> where
     ValueType: Encode + Decode + Sync + Send,
+    HasherType: Hasher<HASH_LEN>,
 {
-    tree: MerkleBIT<RocksDB, TreeBranch, TreeLeaf, TreeData, TreeNode, TreeHasher, ValueType>,
+    tree: MerkleBIT<
+        RocksDB<KEY_LEN, HASH_LEN>,
+        TreeBranch<KEY_LEN, HASH_LEN>,
+        TreeLeaf<KEY_LEN, HASH_LEN>,
+        TreeData,
+        TreeNode<KEY_LEN, HASH_LEN>,
+        HasherType,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    >,
 }
 
-impl<ValueType> RocksTree<ValueType>
+impl<ValueType, const KEY_LEN: usize, const HASH_LEN: usize, HasherType>
+    RocksTree<ValueType, KEY_LEN, HASH_LEN, HasherType>
 where
     ValueType: Encode + Decode + Sync + Send,
+    HasherType: Hasher<HASH_LEN, HashType = HasherType>,
 {
     #[inline]
     pub fn open(path: &PathBuf, depth: usize) -> BinaryMerkleTreeResult<Self> {
@@ -34,32 +61,239 @@ where
     }
 
     #[inline]
-    pub fn from_db(db: RocksDB, depth: usize) -> BinaryMerkleTreeResult<Self> {
+    pub fn from_db(db: RocksDB<KEY_LEN, HASH_LEN>, depth: usize) -> BinaryMerkleTreeResult<Self> {
         let tree = MerkleBIT::from_db(db, depth)?;
         Ok(Self { tree })
     }
 
+    #[inline]
+    pub fn from_db_with_scheme(
+        db: RocksDB<KEY_LEN, HASH_LEN>,
+        depth: usize,
+        scheme: TaggedScheme<HasherType>,
+    ) -> BinaryMerkleTreeResult<Self> {
+        let tree = MerkleBIT::from_db_with_scheme(db, depth, scheme)?;
+        Ok(Self { tree })
+    }
+
     #[inline]
     pub fn get<'a>(
         &self,
-        root_hash: &[u8; KEY_LEN],
+        root_hash: &[u8; HASH_LEN],
         keys: &mut [&'a [u8; KEY_LEN]],
     ) -> BinaryMerkleTreeResult<HashMap<&'a [u8; KEY_LEN], Option<ValueType>>> {
         self.tree.get(root_hash, keys)
     }
 
+    #[inline]
+    pub fn iter(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+    ) -> TreeIter<
+        '_,
+        RocksDB<KEY_LEN, HASH_LEN>,
+        TreeBranch<KEY_LEN, HASH_LEN>,
+        TreeLeaf<KEY_LEN, HASH_LEN>,
+        TreeData,
+        TreeNode<KEY_LEN, HASH_LEN>,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    > {
+        self.tree.iter(root_hash)
+    }
+
+    #[inline]
+    pub fn range<RangeType>(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        range: RangeType,
+    ) -> TreeIter<
+        '_,
+        RocksDB<KEY_LEN, HASH_LEN>,
+        TreeBranch<KEY_LEN, HASH_LEN>,
+        TreeLeaf<KEY_LEN, HASH_LEN>,
+        TreeData,
+        TreeNode<KEY_LEN, HASH_LEN>,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    >
+    where
+        RangeType: RangeBounds<[u8; KEY_LEN]>,
+    {
+        self.tree.range(root_hash, range)
+    }
+
+    #[inline]
+    pub fn prefix(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        prefix: &[u8; KEY_LEN],
+        prefix_bits: usize,
+    ) -> TreeIter<
+        '_,
+        RocksDB<KEY_LEN, HASH_LEN>,
+        TreeBranch<KEY_LEN, HASH_LEN>,
+        TreeLeaf<KEY_LEN, HASH_LEN>,
+        TreeData,
+        TreeNode<KEY_LEN, HASH_LEN>,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    > {
+        self.tree.prefix(root_hash, prefix, prefix_bits)
+    }
+
+    #[inline]
+    pub fn len(&self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<u64> {
+        self.tree.len(root_hash)
+    }
+
+    #[inline]
+    pub fn nth(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        index: u64,
+    ) -> BinaryMerkleTreeResult<Option<([u8; KEY_LEN], ValueType)>> {
+        self.tree.nth(root_hash, index)
+    }
+
+    #[inline]
+    pub fn rank(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        key: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<u64> {
+        self.tree.rank(root_hash, key)
+    }
+
+    #[inline]
+    pub fn diff(
+        &self,
+        old_root: &[u8; HASH_LEN],
+        new_root: &[u8; HASH_LEN],
+    ) -> BinaryMerkleTreeResult<TreeDiff<ValueType, [u8; KEY_LEN]>> {
+        self.tree.diff(old_root, new_root)
+    }
+
+    #[inline]
+    pub fn get_proof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        key: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<Option<InclusionProof<KEY_LEN, HASH_LEN>>> {
+        self.tree.get_proof(root_hash, key)
+    }
+
+    #[inline]
+    pub fn get_absence_proof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        key: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<Option<AbsenceProof<KEY_LEN, HASH_LEN>>> {
+        self.tree.get_absence_proof(root_hash, key)
+    }
+
+    #[inline]
+    pub fn get_multiproof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        keys: &mut [&[u8; KEY_LEN]],
+    ) -> BinaryMerkleTreeResult<Option<MultiProof<KEY_LEN, HASH_LEN>>> {
+        self.tree.get_multiproof(root_hash, keys)
+    }
+
+    #[inline]
+    pub fn get_range_proof(
+        &self,
+        root_hash: &[u8; HASH_LEN],
+        start: &[u8; KEY_LEN],
+        end: &[u8; KEY_LEN],
+    ) -> BinaryMerkleTreeResult<
+        Option<(
+            Vec<([u8; KEY_LEN], ValueType)>,
+            RangeProof<KEY_LEN, HASH_LEN>,
+        )>,
+    > {
+        self.tree.get_range_proof(root_hash, start, end)
+    }
+
     #[inline]
     pub fn insert(
         &mut self,
-        previous_root: Option<&[u8; KEY_LEN]>,
+        previous_root: Option<&[u8; HASH_LEN]>,
         keys: &mut [&[u8; KEY_LEN]],
         values: &mut [&ValueType],
-    ) -> BinaryMerkleTreeResult<[u8; KEY_LEN]> {
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
         self.tree.insert(previous_root, keys, values)
     }
 
     #[inline]
-    pub fn remove(&mut self, root_hash: &[u8; KEY_LEN]) -> BinaryMerkleTreeResult<()> {
+    pub fn delete(
+        &mut self,
+        previous_root: &[u8; HASH_LEN],
+        keys: &mut [&[u8; KEY_LEN]],
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        self.tree.delete(previous_root, keys)
+    }
+
+    #[inline]
+    pub fn apply(
+        &mut self,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        batch: &WriteBatch<ValueType, KEY_LEN>,
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>> {
+        self.tree.apply(previous_root, batch)
+    }
+
+    #[inline]
+    pub fn export_changeset(
+        &self,
+        previous_root: Option<&[u8; HASH_LEN]>,
+        new_root: &[u8; HASH_LEN],
+    ) -> BinaryMerkleTreeResult<Changeset<ValueType, KEY_LEN, HASH_LEN>> {
+        self.tree.export_changeset(previous_root, new_root)
+    }
+
+    #[inline]
+    pub fn check(
+        &mut self,
+        live_roots: &[[u8; HASH_LEN]],
+        repair: bool,
+    ) -> BinaryMerkleTreeResult<CheckReport<HASH_LEN>> {
+        self.tree.check(live_roots, repair)
+    }
+
+    #[inline]
+    pub fn apply_changeset(
+        &mut self,
+        changeset: Changeset<ValueType, KEY_LEN, HASH_LEN>,
+    ) -> BinaryMerkleTreeResult<[u8; HASH_LEN]> {
+        self.tree.apply_changeset(changeset)
+    }
+
+    #[inline]
+    pub fn merge<ResolverType>(
+        &mut self,
+        base: &[u8; HASH_LEN],
+        ours: &[u8; HASH_LEN],
+        theirs: &[u8; HASH_LEN],
+        resolver: ResolverType,
+    ) -> BinaryMerkleTreeResult<Option<[u8; HASH_LEN]>>
+    where
+        ResolverType: FnMut(
+            &[u8; KEY_LEN],
+            Option<&ValueType>,
+            Option<&ValueType>,
+            Option<&ValueType>,
+        ) -> BinaryMerkleTreeResult<Option<ValueType>>,
+    {
+        self.tree.merge(base, ours, theirs, resolver)
+    }
+
+    #[inline]
+    pub fn remove(&mut self, root_hash: &[u8; HASH_LEN]) -> BinaryMerkleTreeResult<()> {
         self.tree.remove(root_hash)
     }
 }
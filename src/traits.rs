@@ -5,10 +5,9 @@ use std::path::PathBuf;
 #[cfg(feature = "use_serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::constants::KEY_LEN;
-
-/// The required interface for structs representing a hasher.
-pub trait Hasher {
+/// The required interface for structs representing a hasher.  `HASH_LEN` is the number of bytes
+/// in the output.
+pub trait Hasher<const HASH_LEN: usize> {
     /// The type of hasher.
     type HashType;
     /// Creates a new `HashType`.
@@ -16,19 +15,47 @@ pub trait Hasher {
     /// Adds data to be hashed.
     fn update(&mut self, data: &[u8]);
     /// Outputs the hash from updated data.
-    fn finalize(self) -> [u8; KEY_LEN];
+    fn finalize(self) -> [u8; HASH_LEN];
+}
+
+/// The required interface for structs that calculate the locations of nodes in the tree.  A scheme
+/// decides which tags separate each kind of node, and what the location of a branch commits to.
+pub trait HashingScheme<const KEY_LEN: usize, const HASH_LEN: usize> {
+    /// Calculates the location of a `Data` node holding the encoded `value` for `key`.
+    fn data_location(&self, key: &[u8; KEY_LEN], value: &[u8]) -> [u8; HASH_LEN];
+    /// Calculates the location of a `Leaf` node for `key` pointing to the `Data` node at `data`.
+    fn leaf_location(&self, key: &[u8; KEY_LEN], data: &[u8; HASH_LEN]) -> [u8; HASH_LEN];
+    /// Calculates the location of a `Branch` node from the locations of its children, its split
+    /// index, a key held beneath it, and the count of leaves beneath it.  `key` may be any key
+    /// beneath the branch, so only its bits before `split_index` should be used.
+    fn branch_location(
+        &self,
+        zero: &[u8; HASH_LEN],
+        one: &[u8; HASH_LEN],
+        split_index: u8,
+        key: &[u8; KEY_LEN],
+        count: u64,
+    ) -> [u8; HASH_LEN];
+    /// Whether the location of a branch covers its split index and the bits before it that every
+    /// key beneath it shares.  Without both, a proof can move a branch to another position in the
+    /// tree, so keys can not be proven absent.
+    #[inline]
+    fn commits_position(&self) -> bool {
+        false
+    }
 }
 
-/// The required interface for structs representing branches in the tree.
-pub trait Branch {
+/// The required interface for structs representing branches in the tree.  `KEY_LEN` is the number
+/// of bytes in a key and `HASH_LEN` is the number of bytes in the location of a node.
+pub trait Branch<const KEY_LEN: usize, const HASH_LEN: usize> {
     /// Creates a new `Branch`.
     fn new() -> Self;
     /// Gets the count of leaves beneath this node.
     fn get_count(&self) -> u64;
     /// Gets the location of the zero branch beneath this node.
-    fn get_zero(&self) -> &[u8; KEY_LEN];
+    fn get_zero(&self) -> &[u8; HASH_LEN];
     /// Gets the location of the one branch beneath this node.
-    fn get_one(&self) -> &[u8; KEY_LEN];
+    fn get_one(&self) -> &[u8; HASH_LEN];
     /// Gets the index on which to split keys when traversing this node.
     fn get_split_index(&self) -> u8;
     /// Gets the associated key with this node.
@@ -36,31 +63,32 @@ pub trait Branch {
     /// Sets the count of leaves below this node.
     fn set_count(&mut self, count: u64);
     /// Sets the location of the zero branch beneath this node.
-    fn set_zero(&mut self, zero: [u8; KEY_LEN]);
+    fn set_zero(&mut self, zero: [u8; HASH_LEN]);
     /// Sets the location of the one branch beneath this node..
-    fn set_one(&mut self, one: [u8; KEY_LEN]);
+    fn set_one(&mut self, one: [u8; HASH_LEN]);
     /// Sets the index on which to split keys when traversing this node.
     fn set_split_index(&mut self, index: u8);
     /// Sets the associated key for this node.
     fn set_key(&mut self, key: [u8; KEY_LEN]);
     /// Decomposes the `Branch` into its constituent parts.
-    fn deconstruct(self) -> (u64, [u8; KEY_LEN], [u8; KEY_LEN], u8, [u8; KEY_LEN]);
+    fn deconstruct(self) -> (u64, [u8; HASH_LEN], [u8; HASH_LEN], u8, [u8; KEY_LEN]);
 }
 
-/// The required interface for structs representing leaves in the tree.
-pub trait Leaf {
+/// The required interface for structs representing leaves in the tree.  `KEY_LEN` is the number of
+/// bytes in a key and `HASH_LEN` is the number of bytes in the location of a node.
+pub trait Leaf<const KEY_LEN: usize, const HASH_LEN: usize> {
     /// Creates a new `Leaf` node.
     fn new() -> Self;
     /// Gets the associated key with this node.
     fn get_key(&self) -> &[u8; KEY_LEN];
     /// Gets the location of the `Data` node.
-    fn get_data(&self) -> &[u8; KEY_LEN];
+    fn get_data(&self) -> &[u8; HASH_LEN];
     /// Sets the associated key with this node.
     fn set_key(&mut self, key: [u8; KEY_LEN]);
     /// Sets the location of the `Data` node.
-    fn set_data(&mut self, data: [u8; KEY_LEN]);
+    fn set_data(&mut self, data: [u8; HASH_LEN]);
     /// Decomposes the `Leaf` into its constituent parts.
-    fn deconstruct(self) -> ([u8; KEY_LEN], [u8; KEY_LEN]);
+    fn deconstruct(self) -> ([u8; KEY_LEN], [u8; HASH_LEN]);
 }
 
 /// The required interface for structs representing data stored in the tree.
@@ -74,12 +102,7 @@ pub trait Data {
 }
 
 /// The required interface for structs representing nodes in the tree.
-pub trait Node<BranchType, LeafType, DataType>
-where
-    BranchType: Branch,
-    LeafType: Leaf,
-    DataType: Data,
-{
+pub trait Node<BranchType, LeafType, DataType> {
     /// Creates a new `Node`.
     fn new(node_variant: NodeVariant<BranchType, LeafType, DataType>) -> Self;
     /// Gets the number of references to this node.
@@ -99,12 +122,7 @@ where
 /// Contains the distinguishing data from the node
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(any(feature = "use_serde",), derive(Serialize, Deserialize))]
-pub enum NodeVariant<BranchType, LeafType, DataType>
-where
-    BranchType: Branch,
-    LeafType: Leaf,
-    DataType: Data,
-{
+pub enum NodeVariant<BranchType, LeafType, DataType> {
     /// Variant containing a `Branch` node.
     Branch(BranchType),
     /// Variant containing a `Leaf` node.
@@ -114,7 +132,8 @@ where
 }
 
 /// This trait defines the required interface for connecting a storage mechanism to the `MerkleBIT`.
-pub trait Database {
+/// Nodes are stored at locations of `HASH_LEN` bytes.
+pub trait Database<const HASH_LEN: usize> {
     /// The type of node to insert into the database.
     type NodeType;
     /// The type of entry for insertion.  Primarily for convenience and tracking what goes into the database.
@@ -124,13 +143,29 @@ pub trait Database {
     where
         Self: Sized;
     /// Gets a value from the database based on the given key.
-    fn get_node(&self, key: &[u8; KEY_LEN]) -> Result<Option<Self::NodeType>, Exception>;
+    fn get_node(&self, key: &[u8; HASH_LEN]) -> Result<Option<Self::NodeType>, Exception>;
+    /// Gets the location of every node written to the database, in any order.  Only needed by
+    /// `MerkleBIT::check`, which returns an error for databases that keep the default.
+    #[inline]
+    fn locations(&self) -> Result<Vec<[u8; HASH_LEN]>, Exception> {
+        Err(Exception::new(
+            "Listing locations is not supported by this database",
+        ))
+    }
     /// Queues a key and its associated value for insertion to the database.
-    fn insert(&mut self, key: [u8; KEY_LEN], node: Self::NodeType) -> Result<(), Exception>;
-    /// Removes a key and its associated value from the database.
-    fn remove(&mut self, key: &[u8; KEY_LEN]) -> Result<(), Exception>;
-    /// Confirms previous inserts and writes the changes to the database.
+    fn insert(&mut self, key: [u8; HASH_LEN], node: Self::NodeType) -> Result<(), Exception>;
+    /// Queues a key and its associated value for removal from the database.  Queued removals are
+    /// seen by `get_node`, and are written along with queued inserts by `batch_write`.
+    fn remove(&mut self, key: &[u8; HASH_LEN]) -> Result<(), Exception>;
+    /// Confirms previous inserts and removals and writes the changes to the database.
     fn batch_write(&mut self) -> Result<(), Exception>;
+    /// Discards the changes queued since the last `batch_write`, leaving the database as it was.
+    /// Databases that can not discard their changes may keep the default, in which case a
+    /// `MerkleBIT` writes nothing more after a failed operation.
+    #[inline]
+    fn rollback(&mut self) -> Result<(), Exception> {
+        Err(Exception::new("Rollback is not supported by this database"))
+    }
 }
 
 /// This trait must be implemented to allow a struct to be serialized.
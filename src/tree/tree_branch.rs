@@ -18,7 +18,6 @@ use serde_pickle;
 #[cfg(feature = "use_yaml")]
 use serde_yaml;
 
-use crate::constants::KEY_LEN;
 #[cfg(feature = "use_serde")]
 use crate::merkle_bit::BinaryMerkleTreeResult;
 use crate::traits::Branch;
@@ -28,26 +27,29 @@ use crate::traits::{Decode, Encode, Exception};
 /// A struct representing a branch in the tree.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(any(feature = "use_serde"), derive(Serialize, Deserialize))]
-pub struct TreeBranch {
+pub struct TreeBranch<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
     /// The number of leaf nodes under this branch.
     count: u64,
     /// The location of the next node when traversing the zero branch.
-    zero: [u8; KEY_LEN],
+    #[cfg_attr(feature = "use_serde", serde(with = "crate::utils::byte_array"))]
+    zero: [u8; HASH_LEN],
     /// The location of the next node when traversing the one branch.
-    one: [u8; KEY_LEN],
+    #[cfg_attr(feature = "use_serde", serde(with = "crate::utils::byte_array"))]
+    one: [u8; HASH_LEN],
     /// The index bit of the associated key on which to make a decision to go down the zero or one branch.
     split_index: u8,
     /// The associated key with this branch.
+    #[cfg_attr(feature = "use_serde", serde(with = "crate::utils::byte_array"))]
     key: [u8; KEY_LEN],
 }
 
-impl TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> TreeBranch<KEY_LEN, HASH_LEN> {
     /// Create a new `TreeBranch`
     const fn new() -> Self {
         Self {
             count: 0,
-            zero: [0; KEY_LEN],
-            one: [0; KEY_LEN],
+            zero: [0; HASH_LEN],
+            one: [0; HASH_LEN],
             split_index: 0,
             key: [0; KEY_LEN],
         }
@@ -59,12 +61,12 @@ impl TreeBranch {
     }
 
     /// Get the location of the next node when going down the zero side.
-    const fn get_zero(&self) -> &[u8; KEY_LEN] {
+    const fn get_zero(&self) -> &[u8; HASH_LEN] {
         &self.zero
     }
 
     /// Get the location of the next node when going down the one side.
-    const fn get_one(&self) -> &[u8; KEY_LEN] {
+    const fn get_one(&self) -> &[u8; HASH_LEN] {
         &self.one
     }
 
@@ -84,13 +86,13 @@ impl TreeBranch {
     }
 
     /// Set the location of the next node to traverse when going down the zero side.
-    fn set_zero(&mut self, zero: [u8; KEY_LEN]) {
+    fn set_zero(&mut self, zero: [u8; HASH_LEN]) {
         self.zero = zero;
     }
 
     /// Set the location of the next node to traverse when going down the one side.
-    fn set_one(&mut self, one: [u8; KEY_LEN]) {
-        self.one = one;;
+    fn set_one(&mut self, one: [u8; HASH_LEN]) {
+        self.one = one;
     }
 
     /// Sets the index of the key to split on when deciding which child to traverse.
@@ -104,12 +106,14 @@ impl TreeBranch {
     }
 
     /// Decomposes the `TreeBranch` into its constituent parts.
-    const fn deconstruct(self) -> (u64, [u8; KEY_LEN], [u8; KEY_LEN], u8, [u8; KEY_LEN]) {
+    const fn deconstruct(self) -> (u64, [u8; HASH_LEN], [u8; HASH_LEN], u8, [u8; KEY_LEN]) {
         (self.count, self.zero, self.one, self.split_index, self.key)
     }
 }
 
-impl Branch for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Branch<KEY_LEN, HASH_LEN>
+    for TreeBranch<KEY_LEN, HASH_LEN>
+{
     #[inline]
     fn new() -> Self {
         Self::new()
@@ -120,11 +124,11 @@ impl Branch for TreeBranch {
         Self::get_count(self)
     }
     #[inline]
-    fn get_zero(&self) -> &[u8; KEY_LEN] {
+    fn get_zero(&self) -> &[u8; HASH_LEN] {
         Self::get_zero(self)
     }
     #[inline]
-    fn get_one(&self) -> &[u8; KEY_LEN] {
+    fn get_one(&self) -> &[u8; HASH_LEN] {
         Self::get_one(self)
     }
     #[inline]
@@ -141,11 +145,11 @@ impl Branch for TreeBranch {
         Self::set_count(self, count)
     }
     #[inline]
-    fn set_zero(&mut self, zero: [u8; KEY_LEN]) {
+    fn set_zero(&mut self, zero: [u8; HASH_LEN]) {
         Self::set_zero(self, zero)
     }
     #[inline]
-    fn set_one(&mut self, one: [u8; KEY_LEN]) {
+    fn set_one(&mut self, one: [u8; HASH_LEN]) {
         Self::set_one(self, one)
     }
     #[inline]
@@ -158,13 +162,13 @@ impl Branch for TreeBranch {
     }
 
     #[inline]
-    fn deconstruct(self) -> (u64, [u8; KEY_LEN], [u8; KEY_LEN], u8, [u8; KEY_LEN]) {
+    fn deconstruct(self) -> (u64, [u8; HASH_LEN], [u8; HASH_LEN], u8, [u8; KEY_LEN]) {
         Self::deconstruct(self)
     }
 }
 
 #[cfg(feature = "use_bincode")]
-impl Encode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serialize(self)?)
@@ -180,7 +184,7 @@ impl From<Box<bincode::ErrorKind>> for Exception {
 }
 
 #[cfg(feature = "use_json")]
-impl Encode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         let encoded = serde_json::to_string(&self)?;
@@ -205,7 +209,7 @@ impl From<FromUtf8Error> for Exception {
 }
 
 #[cfg(feature = "use_cbor")]
-impl Encode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serde_cbor::to_vec(&self)?)
@@ -221,7 +225,7 @@ impl From<serde_cbor::error::Error> for Exception {
 }
 
 #[cfg(feature = "use_yaml")]
-impl Encode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serde_yaml::to_vec(&self)?)
@@ -237,7 +241,7 @@ impl From<serde_yaml::Error> for Exception {
 }
 
 #[cfg(feature = "use_pickle")]
-impl Encode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serde_pickle::to_vec(&self, true)?)
@@ -253,7 +257,7 @@ impl From<serde_pickle::Error> for Exception {
 }
 
 #[cfg(feature = "use_ron")]
-impl Encode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(ron::ser::to_string(&self)?.as_bytes().to_vec())
@@ -277,7 +281,7 @@ impl From<ron::de::Error> for Exception {
 }
 
 #[cfg(feature = "use_bincode")]
-impl Decode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(deserialize(buffer)?)
@@ -285,7 +289,7 @@ impl Decode for TreeBranch {
 }
 
 #[cfg(feature = "use_json")]
-impl Decode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         let decoded_string = String::from_utf8(buffer.to_vec())?;
@@ -295,7 +299,7 @@ impl Decode for TreeBranch {
 }
 
 #[cfg(feature = "use_cbor")]
-impl Decode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(serde_cbor::from_slice(buffer)?)
@@ -303,7 +307,7 @@ impl Decode for TreeBranch {
 }
 
 #[cfg(feature = "use_yaml")]
-impl Decode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(serde_yaml::from_slice(buffer)?)
@@ -311,7 +315,7 @@ impl Decode for TreeBranch {
 }
 
 #[cfg(feature = "use_pickle")]
-impl Decode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(serde_pickle::from_slice(buffer)?)
@@ -319,7 +323,7 @@ impl Decode for TreeBranch {
 }
 
 #[cfg(feature = "use_ron")]
-impl Decode for TreeBranch {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeBranch<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(ron::de::from_bytes(buffer)?)
@@ -13,7 +13,6 @@ use serde_pickle;
 #[cfg(feature = "use_yaml")]
 use serde_yaml;
 
-use crate::constants::KEY_LEN;
 #[cfg(feature = "use_serialization")]
 use crate::merkle_bit::BinaryMerkleTreeResult;
 use crate::traits::Leaf;
@@ -21,22 +20,24 @@ use crate::traits::Leaf;
 use crate::traits::{Decode, Encode};
 
 /// Represents a leaf of the tree.  Holds a pointer to the location of the underlying `Data` node.
-#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
-pub struct TreeLeaf {
+pub struct TreeLeaf<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
     /// The associated key with this node.
+    #[cfg_attr(feature = "use_serde", serde(with = "crate::utils::byte_array"))]
     key: [u8; KEY_LEN],
     /// The location of the `Data` node in the tree.
-    data: [u8; KEY_LEN],
+    #[cfg_attr(feature = "use_serde", serde(with = "crate::utils::byte_array"))]
+    data: [u8; HASH_LEN],
 }
 
-impl TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> TreeLeaf<KEY_LEN, HASH_LEN> {
     /// Creates a new `TreeLeaf`.
     #[inline]
     pub const fn new() -> Self {
         Self {
             key: [0; KEY_LEN],
-            data: [0; KEY_LEN],
+            data: [0; HASH_LEN],
         }
     }
 
@@ -46,7 +47,7 @@ impl TreeLeaf {
     }
 
     /// Gets the location of the `Data` node from this node.
-    const fn get_data(&self) -> &[u8; KEY_LEN] {
+    const fn get_data(&self) -> &[u8; HASH_LEN] {
         &self.data
     }
 
@@ -56,17 +57,26 @@ impl TreeLeaf {
     }
 
     /// Sets the location of the `Data` node.
-    fn set_data(&mut self, data: [u8; KEY_LEN]) {
+    fn set_data(&mut self, data: [u8; HASH_LEN]) {
         self.data = data;
     }
 
     /// Decomposes the `TreeLeaf` into its constituent parts.
-    const fn deconstruct(self) -> ([u8; KEY_LEN], [u8; KEY_LEN]) {
+    const fn deconstruct(self) -> ([u8; KEY_LEN], [u8; HASH_LEN]) {
         (self.key, self.data)
     }
 }
 
-impl Leaf for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Default for TreeLeaf<KEY_LEN, HASH_LEN> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Leaf<KEY_LEN, HASH_LEN>
+    for TreeLeaf<KEY_LEN, HASH_LEN>
+{
     /// Creates a new `TreeLeaf`
     #[inline]
     fn new() -> Self {
@@ -81,7 +91,7 @@ impl Leaf for TreeLeaf {
 
     /// Gets the location of the `Data` node.
     #[inline]
-    fn get_data(&self) -> &[u8; KEY_LEN] {
+    fn get_data(&self) -> &[u8; HASH_LEN] {
         Self::get_data(self)
     }
 
@@ -93,19 +103,19 @@ impl Leaf for TreeLeaf {
 
     /// Sets the location for the `Data` node.
     #[inline]
-    fn set_data(&mut self, data: [u8; KEY_LEN]) {
+    fn set_data(&mut self, data: [u8; HASH_LEN]) {
         Self::set_data(self, data)
     }
 
     /// Decomposes the struct into its constituent parts.
     #[inline]
-    fn deconstruct(self) -> ([u8; KEY_LEN], [u8; KEY_LEN]) {
+    fn deconstruct(self) -> ([u8; KEY_LEN], [u8; HASH_LEN]) {
         Self::deconstruct(self)
     }
 }
 
 #[cfg(feature = "use_bincode")]
-impl Encode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serialize(self)?)
@@ -113,7 +123,7 @@ impl Encode for TreeLeaf {
 }
 
 #[cfg(feature = "use_json")]
-impl Encode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         let encoded = serde_json::to_string(&self)?;
@@ -122,7 +132,7 @@ impl Encode for TreeLeaf {
 }
 
 #[cfg(feature = "use_cbor")]
-impl Encode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serde_cbor::to_vec(&self)?)
@@ -130,7 +140,7 @@ impl Encode for TreeLeaf {
 }
 
 #[cfg(feature = "use_yaml")]
-impl Encode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serde_yaml::to_vec(&self)?)
@@ -138,7 +148,7 @@ impl Encode for TreeLeaf {
 }
 
 #[cfg(feature = "use_pickle")]
-impl Encode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serde_pickle::to_vec(&self, true)?)
@@ -146,7 +156,7 @@ impl Encode for TreeLeaf {
 }
 
 #[cfg(feature = "use_ron")]
-impl Encode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(ron::ser::to_string(&self)?.as_bytes().to_vec())
@@ -154,7 +164,7 @@ impl Encode for TreeLeaf {
 }
 
 #[cfg(feature = "use_bincode")]
-impl Decode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(deserialize(buffer)?)
@@ -162,7 +172,7 @@ impl Decode for TreeLeaf {
 }
 
 #[cfg(feature = "use_json")]
-impl Decode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         let decoded_string = String::from_utf8(buffer.to_vec())?;
@@ -172,7 +182,7 @@ impl Decode for TreeLeaf {
 }
 
 #[cfg(feature = "use_cbor")]
-impl Decode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(serde_cbor::from_slice(buffer)?)
@@ -180,7 +190,7 @@ impl Decode for TreeLeaf {
 }
 
 #[cfg(feature = "use_yaml")]
-impl Decode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(serde_yaml::from_slice(buffer)?)
@@ -188,7 +198,7 @@ impl Decode for TreeLeaf {
 }
 
 #[cfg(feature = "use_pickle")]
-impl Decode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(serde_pickle::from_slice(buffer)?)
@@ -196,7 +206,7 @@ impl Decode for TreeLeaf {
 }
 
 #[cfg(feature = "use_ron")]
-impl Decode for TreeLeaf {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeLeaf<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(ron::de::from_bytes(buffer)?)
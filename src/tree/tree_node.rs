@@ -27,17 +27,23 @@ use evmap::ShallowCopy;
 /// A node in the tree.
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(any(feature = "use_serde"), derive(Serialize, Deserialize))]
-pub struct TreeNode {
+pub struct TreeNode<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
     /// The number of references to this node.
     pub references: u64,
     /// The `NodeVariant` of the node.
-    pub node: NodeVariant<TreeBranch, TreeLeaf, TreeData>,
+    pub node: NodeVariant<TreeBranch<KEY_LEN, HASH_LEN>, TreeLeaf<KEY_LEN, HASH_LEN>, TreeData>,
 }
 
-impl TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> TreeNode<KEY_LEN, HASH_LEN> {
     /// Creates a new `TreeNode`.
     #[inline]
-    pub const fn new(node_variant: NodeVariant<TreeBranch, TreeLeaf, TreeData>) -> Self {
+    pub const fn new(
+        node_variant: NodeVariant<
+            TreeBranch<KEY_LEN, HASH_LEN>,
+            TreeLeaf<KEY_LEN, HASH_LEN>,
+            TreeData,
+        >,
+    ) -> Self {
         Self {
             references: 0,
             node: node_variant,
@@ -55,12 +61,12 @@ impl TreeNode {
     }
 
     /// Sets the node as a `NodeVariant::Branch`.
-    fn set_branch(&mut self, branch: TreeBranch) {
+    fn set_branch(&mut self, branch: TreeBranch<KEY_LEN, HASH_LEN>) {
         self.node = NodeVariant::Branch(branch);
     }
 
     /// Sets the node as a `NodeVariant::Leaf`.
-    fn set_leaf(&mut self, leaf: TreeLeaf) {
+    fn set_leaf(&mut self, leaf: TreeLeaf<KEY_LEN, HASH_LEN>) {
         self.node = NodeVariant::Leaf(leaf);
     }
 
@@ -70,7 +76,11 @@ impl TreeNode {
     }
     #[cfg(feature = "use_rayon")]
     unsafe fn from_raw(
-        node_variant: *const NodeVariant<TreeBranch, TreeLeaf, TreeData>,
+        node_variant: *const NodeVariant<
+            TreeBranch<KEY_LEN, HASH_LEN>,
+            TreeLeaf<KEY_LEN, HASH_LEN>,
+            TreeData,
+        >,
         references: u64,
     ) -> Self {
         let node = std::ptr::read(node_variant);
@@ -78,9 +88,18 @@ impl TreeNode {
     }
 }
 
-impl Node<TreeBranch, TreeLeaf, TreeData> for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize>
+    Node<TreeBranch<KEY_LEN, HASH_LEN>, TreeLeaf<KEY_LEN, HASH_LEN>, TreeData>
+    for TreeNode<KEY_LEN, HASH_LEN>
+{
     #[inline]
-    fn new(node_variant: NodeVariant<TreeBranch, TreeLeaf, TreeData>) -> Self {
+    fn new(
+        node_variant: NodeVariant<
+            TreeBranch<KEY_LEN, HASH_LEN>,
+            TreeLeaf<KEY_LEN, HASH_LEN>,
+            TreeData,
+        >,
+    ) -> Self {
         Self::new(node_variant)
     }
 
@@ -89,7 +108,9 @@ impl Node<TreeBranch, TreeLeaf, TreeData> for TreeNode {
         Self::get_references(self)
     }
     #[inline]
-    fn get_variant(self) -> NodeVariant<TreeBranch, TreeLeaf, TreeData> {
+    fn get_variant(
+        self,
+    ) -> NodeVariant<TreeBranch<KEY_LEN, HASH_LEN>, TreeLeaf<KEY_LEN, HASH_LEN>, TreeData> {
         self.node
     }
 
@@ -98,11 +119,11 @@ impl Node<TreeBranch, TreeLeaf, TreeData> for TreeNode {
         Self::set_references(self, references)
     }
     #[inline]
-    fn set_branch(&mut self, branch: TreeBranch) {
+    fn set_branch(&mut self, branch: TreeBranch<KEY_LEN, HASH_LEN>) {
         Self::set_branch(self, branch)
     }
     #[inline]
-    fn set_leaf(&mut self, leaf: TreeLeaf) {
+    fn set_leaf(&mut self, leaf: TreeLeaf<KEY_LEN, HASH_LEN>) {
         Self::set_leaf(self, leaf)
     }
     #[inline]
@@ -112,7 +133,7 @@ impl Node<TreeBranch, TreeLeaf, TreeData> for TreeNode {
 }
 
 #[cfg(feature = "use_bincode")]
-impl Encode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serialize(self)?)
@@ -120,7 +141,7 @@ impl Encode for TreeNode {
 }
 
 #[cfg(feature = "use_json")]
-impl Encode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         let encoded = serde_json::to_string(&self)?;
@@ -129,7 +150,7 @@ impl Encode for TreeNode {
 }
 
 #[cfg(feature = "use_cbor")]
-impl Encode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serde_cbor::to_vec(&self)?)
@@ -137,7 +158,7 @@ impl Encode for TreeNode {
 }
 
 #[cfg(feature = "use_yaml")]
-impl Encode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serde_yaml::to_vec(&self)?)
@@ -145,7 +166,7 @@ impl Encode for TreeNode {
 }
 
 #[cfg(feature = "use_pickle")]
-impl Encode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(serde_pickle::to_vec(&self, true)?)
@@ -153,7 +174,7 @@ impl Encode for TreeNode {
 }
 
 #[cfg(feature = "use_ron")]
-impl Encode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Encode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
         Ok(ron::ser::to_string(&self)?.as_bytes().to_vec())
@@ -161,7 +182,7 @@ impl Encode for TreeNode {
 }
 
 #[cfg(feature = "use_bincode")]
-impl Decode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(deserialize(buffer)?)
@@ -169,7 +190,7 @@ impl Decode for TreeNode {
 }
 
 #[cfg(feature = "use_json")]
-impl Decode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         let decoded_string = String::from_utf8(buffer.to_vec())?;
@@ -179,7 +200,7 @@ impl Decode for TreeNode {
 }
 
 #[cfg(feature = "use_cbor")]
-impl Decode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(serde_cbor::from_slice(buffer)?)
@@ -187,7 +208,7 @@ impl Decode for TreeNode {
 }
 
 #[cfg(feature = "use_yaml")]
-impl Decode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(serde_yaml::from_slice(buffer)?)
@@ -195,7 +216,7 @@ impl Decode for TreeNode {
 }
 
 #[cfg(feature = "use_pickle")]
-impl Decode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(serde_pickle::from_slice(buffer)?)
@@ -203,7 +224,7 @@ impl Decode for TreeNode {
 }
 
 #[cfg(feature = "use_ron")]
-impl Decode for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Decode for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
         Ok(ron::de::from_bytes(buffer)?)
@@ -211,7 +232,7 @@ impl Decode for TreeNode {
 }
 
 #[cfg(feature = "use_rayon")]
-impl ShallowCopy for TreeNode {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> ShallowCopy for TreeNode<KEY_LEN, HASH_LEN> {
     #[inline]
     unsafe fn shallow_copy(&mut self) -> Self {
         let raw_node = &self.node as *const _;
@@ -1,32 +1,35 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
-use crate::constants::KEY_LEN;
 use crate::traits::{Database, Exception};
 use crate::tree::tree_node::TreeNode;
 
 use evmap::{ReadHandle, WriteHandle};
 use parking_lot::Mutex;
 
-pub struct HashDB {
-    read: ReadHandle<[u8; KEY_LEN], TreeNode>,
-    write: Mutex<WriteHandle<[u8; KEY_LEN], TreeNode>>,
+pub struct HashDB<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    read: ReadHandle<[u8; HASH_LEN], TreeNode<KEY_LEN, HASH_LEN>>,
+    write: Mutex<WriteHandle<[u8; HASH_LEN], TreeNode<KEY_LEN, HASH_LEN>>>,
+    /// The changes queued since the last `batch_write`, which readers can not see until then.
+    /// Removed nodes are held as `None`.
+    pending: BTreeMap<[u8; HASH_LEN], Option<TreeNode<KEY_LEN, HASH_LEN>>>,
 }
 
-impl HashDB {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> HashDB<KEY_LEN, HASH_LEN> {
     #[inline]
-    pub fn new(_: HashMap<[u8; KEY_LEN], TreeNode>) -> Self {
+    pub fn new(_: HashMap<[u8; HASH_LEN], TreeNode<KEY_LEN, HASH_LEN>>) -> Self {
         let (read, write) = evmap::new();
         Self {
             read,
             write: Mutex::new(write),
+            pending: BTreeMap::new(),
         }
     }
 }
 
-impl Database for HashDB {
-    type NodeType = TreeNode;
-    type EntryType = ([u8; KEY_LEN], TreeNode);
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Database<HASH_LEN> for HashDB<KEY_LEN, HASH_LEN> {
+    type NodeType = TreeNode<KEY_LEN, HASH_LEN>;
+    type EntryType = ([u8; HASH_LEN], TreeNode<KEY_LEN, HASH_LEN>);
 
     #[inline]
     fn open(_path: &PathBuf) -> Result<Self, Exception> {
@@ -34,7 +37,10 @@ impl Database for HashDB {
     }
 
     #[inline]
-    fn get_node(&self, key: &[u8; KEY_LEN]) -> Result<Option<Self::NodeType>, Exception> {
+    fn get_node(&self, key: &[u8; HASH_LEN]) -> Result<Option<Self::NodeType>, Exception> {
+        if let Some(node) = self.pending.get(key) {
+            return Ok(node.clone());
+        }
         if let Some(m) = self.read.get_and(key, |x| x[x.len() - 1].clone()) {
             return Ok(Some(m));
         } else {
@@ -43,24 +49,41 @@ impl Database for HashDB {
     }
 
     #[inline]
-    fn insert(&mut self, key: [u8; KEY_LEN], value: Self::NodeType) -> Result<(), Exception> {
-        self.write.lock().insert(key, value);
+    fn locations(&self) -> Result<Vec<[u8; HASH_LEN]>, Exception> {
+        Ok(self.read.map_into(|key, _| *key))
+    }
+
+    #[inline]
+    fn insert(&mut self, key: [u8; HASH_LEN], value: Self::NodeType) -> Result<(), Exception> {
+        self.pending.insert(key, Some(value));
         Ok(())
     }
 
     #[inline]
-    fn remove(&mut self, key: &[u8; KEY_LEN]) -> Result<(), Exception> {
-        self.write.lock().empty(*key);
-        self.write.lock().refresh();
+    fn remove(&mut self, key: &[u8; HASH_LEN]) -> Result<(), Exception> {
+        self.pending.insert(*key, None);
         Ok(())
     }
 
     #[inline]
     fn batch_write(&mut self) -> Result<(), Exception> {
-        self.write.lock().refresh();
+        let mut write = self.write.lock();
+        for (key, node) in std::mem::take(&mut self.pending) {
+            match node {
+                Some(n) => write.update(key, n),
+                None => write.empty(key),
+            };
+        }
+        write.refresh();
+        Ok(())
+    }
+
+    #[inline]
+    fn rollback(&mut self) -> Result<(), Exception> {
+        self.pending.clear();
         Ok(())
     }
 }
 
-unsafe impl Sync for HashDB {}
-unsafe impl Send for HashDB {}
+unsafe impl<const KEY_LEN: usize, const HASH_LEN: usize> Sync for HashDB<KEY_LEN, HASH_LEN> {}
+unsafe impl<const KEY_LEN: usize, const HASH_LEN: usize> Send for HashDB<KEY_LEN, HASH_LEN> {}
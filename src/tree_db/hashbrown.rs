@@ -1,25 +1,29 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use hashbrown::HashMap;
 
-use crate::constants::KEY_LEN;
 use crate::traits::{Database, Exception};
 use crate::tree::tree_node::TreeNode;
 
-pub struct HashDB {
-    map: HashMap<[u8; KEY_LEN], TreeNode>,
+pub struct HashDB<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    map: HashMap<[u8; HASH_LEN], TreeNode<KEY_LEN, HASH_LEN>>,
+    pending: BTreeMap<[u8; HASH_LEN], Option<TreeNode<KEY_LEN, HASH_LEN>>>,
 }
 
-impl HashDB {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> HashDB<KEY_LEN, HASH_LEN> {
     #[inline]
-    pub const fn new(map: HashMap<[u8; KEY_LEN], TreeNode>) -> Self {
-        Self { map }
+    pub const fn new(map: HashMap<[u8; HASH_LEN], TreeNode<KEY_LEN, HASH_LEN>>) -> Self {
+        Self {
+            map,
+            pending: BTreeMap::new(),
+        }
     }
 }
 
-impl Database for HashDB {
-    type NodeType = TreeNode;
-    type EntryType = (Vec<u8>, TreeNode);
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Database<HASH_LEN> for HashDB<KEY_LEN, HASH_LEN> {
+    type NodeType = TreeNode<KEY_LEN, HASH_LEN>;
+    type EntryType = (Vec<u8>, TreeNode<KEY_LEN, HASH_LEN>);
 
     #[inline]
     fn open(_path: &PathBuf) -> Result<Self, Exception> {
@@ -27,7 +31,10 @@ impl Database for HashDB {
     }
 
     #[inline]
-    fn get_node(&self, key: &[u8; KEY_LEN]) -> Result<Option<Self::NodeType>, Exception> {
+    fn get_node(&self, key: &[u8; HASH_LEN]) -> Result<Option<Self::NodeType>, Exception> {
+        if let Some(node) = self.pending.get(key) {
+            return Ok(node.clone());
+        }
         if let Some(m) = self.map.get(key) {
             let node = m.clone();
             return Ok(Some(node));
@@ -37,19 +44,36 @@ impl Database for HashDB {
     }
 
     #[inline]
-    fn insert(&mut self, key: [u8; KEY_LEN], value: Self::NodeType) -> Result<(), Exception> {
-        self.map.insert(key, value);
+    fn locations(&self) -> Result<Vec<[u8; HASH_LEN]>, Exception> {
+        Ok(self.map.keys().copied().collect())
+    }
+
+    #[inline]
+    fn insert(&mut self, key: [u8; HASH_LEN], value: Self::NodeType) -> Result<(), Exception> {
+        self.pending.insert(key, Some(value));
         Ok(())
     }
 
     #[inline]
-    fn remove(&mut self, key: &[u8; KEY_LEN]) -> Result<(), Exception> {
-        self.map.remove(key);
+    fn remove(&mut self, key: &[u8; HASH_LEN]) -> Result<(), Exception> {
+        self.pending.insert(*key, None);
         Ok(())
     }
 
     #[inline]
     fn batch_write(&mut self) -> Result<(), Exception> {
+        for (key, node) in std::mem::take(&mut self.pending) {
+            match node {
+                Some(n) => self.map.insert(key, n),
+                None => self.map.remove(&key),
+            };
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn rollback(&mut self) -> Result<(), Exception> {
+        self.pending.clear();
         Ok(())
     }
 }
@@ -1,27 +1,32 @@
 use std::collections::hash_map::HashMap;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-use crate::constants::KEY_LEN;
 use crate::traits::{Database, Exception};
 use crate::tree::tree_node::TreeNode;
 
 /// A database consisting of a `HashMap`.
-pub struct HashDB {
+pub struct HashDB<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
     /// The internal `HashMap` for storing nodes.
-    map: HashMap<[u8; KEY_LEN], TreeNode>,
+    map: HashMap<[u8; HASH_LEN], TreeNode<KEY_LEN, HASH_LEN>>,
+    /// The changes queued since the last `batch_write`, with removed nodes held as `None`.
+    pending: BTreeMap<[u8; HASH_LEN], Option<TreeNode<KEY_LEN, HASH_LEN>>>,
 }
 
-impl HashDB {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> HashDB<KEY_LEN, HASH_LEN> {
     /// Creates a new `HashDB`.
     #[inline]
-    pub const fn new(map: HashMap<[u8; KEY_LEN], TreeNode>) -> Self {
-        Self { map }
+    pub const fn new(map: HashMap<[u8; HASH_LEN], TreeNode<KEY_LEN, HASH_LEN>>) -> Self {
+        Self {
+            map,
+            pending: BTreeMap::new(),
+        }
     }
 }
 
-impl Database for HashDB {
-    type NodeType = TreeNode;
-    type EntryType = ([u8; KEY_LEN], Vec<u8>);
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Database<HASH_LEN> for HashDB<KEY_LEN, HASH_LEN> {
+    type NodeType = TreeNode<KEY_LEN, HASH_LEN>;
+    type EntryType = ([u8; HASH_LEN], Vec<u8>);
 
     #[inline]
     fn open(_path: &PathBuf) -> Result<Self, Exception> {
@@ -29,7 +34,10 @@ impl Database for HashDB {
     }
 
     #[inline]
-    fn get_node(&self, key: &[u8; KEY_LEN]) -> Result<Option<Self::NodeType>, Exception> {
+    fn get_node(&self, key: &[u8; HASH_LEN]) -> Result<Option<Self::NodeType>, Exception> {
+        if let Some(node) = self.pending.get(key) {
+            return Ok(node.clone());
+        }
         if let Some(m) = self.map.get(key) {
             let node = m.clone();
             return Ok(Some(node));
@@ -39,19 +47,36 @@ impl Database for HashDB {
     }
 
     #[inline]
-    fn insert(&mut self, key: [u8; KEY_LEN], value: Self::NodeType) -> Result<(), Exception> {
-        self.map.insert(key, value);
+    fn locations(&self) -> Result<Vec<[u8; HASH_LEN]>, Exception> {
+        Ok(self.map.keys().copied().collect())
+    }
+
+    #[inline]
+    fn insert(&mut self, key: [u8; HASH_LEN], value: Self::NodeType) -> Result<(), Exception> {
+        self.pending.insert(key, Some(value));
         Ok(())
     }
 
     #[inline]
-    fn remove(&mut self, key: &[u8; KEY_LEN]) -> Result<(), Exception> {
-        self.map.remove(key);
+    fn remove(&mut self, key: &[u8; HASH_LEN]) -> Result<(), Exception> {
+        self.pending.insert(*key, None);
         Ok(())
     }
 
     #[inline]
     fn batch_write(&mut self) -> Result<(), Exception> {
+        for (key, node) in std::mem::take(&mut self.pending) {
+            match node {
+                Some(n) => self.map.insert(key, n),
+                None => self.map.remove(&key),
+            };
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn rollback(&mut self) -> Result<(), Exception> {
+        self.pending.clear();
         Ok(())
     }
 }
@@ -10,8 +10,11 @@ pub mod rocksdb;
 
 /// The type of database for the `HashTree`.
 #[cfg(not(any(feature = "use_hashbrown", feature = "use_rayon")))]
-pub type HashTreeDB = crate::tree_db::hashmap::HashDB;
+pub type HashTreeDB<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> =
+    crate::tree_db::hashmap::HashDB<KEY_LEN, HASH_LEN>;
 #[cfg(feature = "use_hashbrown")]
-pub type HashTreeDB = crate::tree_db::hashbrown::HashDB;
+pub type HashTreeDB<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> =
+    crate::tree_db::hashbrown::HashDB<KEY_LEN, HASH_LEN>;
 #[cfg(feature = "use_rayon")]
-pub type HashTreeDB = crate::tree_db::evmap::HashDB;
+pub type HashTreeDB<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> =
+    crate::tree_db::evmap::HashDB<KEY_LEN, HASH_LEN>;
@@ -1,9 +1,12 @@
+#[cfg(not(any(feature = "use_hashbrown")))]
+use std::collections::HashMap;
 use std::error::Error;
 use std::path::PathBuf;
 
-use rocksdb::{WriteBatch, DB};
+#[cfg(feature = "use_hashbrown")]
+use hashbrown::HashMap;
+use rocksdb::{IteratorMode, WriteBatch, DB};
 
-use crate::constants::KEY_LEN;
 use crate::traits::{Database, Decode, Encode, Exception};
 use crate::tree::tree_node::TreeNode;
 
@@ -14,23 +17,34 @@ impl From<rocksdb::Error> for Exception {
     }
 }
 
-pub struct RocksDB {
+pub struct RocksDB<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
     db: DB,
-    pending_inserts: Option<WriteBatch>,
+    pending_writes: Option<WriteBatch>,
+    /// The nodes written to `pending_writes`, so they can be read back before `batch_write`.
+    /// Nodes queued for removal are held as `None`.
+    pending_nodes: HashMap<[u8; HASH_LEN], Option<TreeNode<KEY_LEN, HASH_LEN>>>,
 }
 
-impl RocksDB {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> RocksDB<KEY_LEN, HASH_LEN> {
     #[inline]
     pub fn new(db: DB) -> Self {
         Self {
             db,
-            pending_inserts: Some(WriteBatch::default()),
+            pending_writes: Some(WriteBatch::default()),
+            pending_nodes: HashMap::new(),
         }
     }
+
+    /// Gets the write batch holding the queued inserts and removals.
+    fn pending_batch(&mut self) -> &mut WriteBatch {
+        self.pending_writes.get_or_insert_with(WriteBatch::default)
+    }
 }
 
-impl Database for RocksDB {
-    type NodeType = TreeNode;
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Database<HASH_LEN>
+    for RocksDB<KEY_LEN, HASH_LEN>
+{
+    type NodeType = TreeNode<KEY_LEN, HASH_LEN>;
     type EntryType = (usize, usize);
 
     #[inline]
@@ -39,7 +53,10 @@ impl Database for RocksDB {
     }
 
     #[inline]
-    fn get_node(&self, key: &[u8; KEY_LEN]) -> Result<Option<Self::NodeType>, Exception> {
+    fn get_node(&self, key: &[u8; HASH_LEN]) -> Result<Option<Self::NodeType>, Exception> {
+        if let Some(node) = self.pending_nodes.get(key) {
+            return Ok(node.clone());
+        }
         if let Some(buffer) = self.db.get(key)? {
             Ok(Some(Self::NodeType::decode(buffer.as_ref())?))
         } else {
@@ -48,29 +65,50 @@ impl Database for RocksDB {
     }
 
     #[inline]
-    fn insert(&mut self, key: [u8; KEY_LEN], value: Self::NodeType) -> Result<(), Exception> {
-        let serialized = value.encode()?;
-        if let Some(wb) = &mut self.pending_inserts {
-            wb.put(key, serialized)?;
-        } else {
-            let mut wb = WriteBatch::default();
-            wb.put(key, serialized)?;
-            self.pending_inserts = Some(wb);
+    fn locations(&self) -> Result<Vec<[u8; HASH_LEN]>, Exception> {
+        let mut locations = Vec::new();
+        for (key, _) in self.db.iterator(IteratorMode::Start) {
+            if key.len() != HASH_LEN {
+                return Err(Exception::new(
+                    "Corrupt database: Found key of the wrong length",
+                ));
+            }
+            let mut location = [0; HASH_LEN];
+            location.copy_from_slice(&key);
+            locations.push(location);
         }
+        Ok(locations)
+    }
+
+    #[inline]
+    fn insert(&mut self, key: [u8; HASH_LEN], value: Self::NodeType) -> Result<(), Exception> {
+        let serialized = value.encode()?;
+        self.pending_batch().put(key, serialized)?;
+        self.pending_nodes.insert(key, Some(value));
         Ok(())
     }
 
     #[inline]
-    fn remove(&mut self, key: &[u8; KEY_LEN]) -> Result<(), Exception> {
-        Ok(self.db.delete(key)?)
+    fn remove(&mut self, key: &[u8; HASH_LEN]) -> Result<(), Exception> {
+        self.pending_batch().delete(key)?;
+        self.pending_nodes.insert(*key, None);
+        Ok(())
     }
 
     #[inline]
     fn batch_write(&mut self) -> Result<(), Exception> {
-        if let Some(wb) = self.pending_inserts.replace(WriteBatch::default()) {
+        // Inserts and removals are written together, so either all of them are applied or none
+        if let Some(wb) = self.pending_writes.take() {
             self.db.write(wb)?;
         }
-        self.pending_inserts = None;
+        self.pending_nodes.clear();
+        Ok(())
+    }
+
+    #[inline]
+    fn rollback(&mut self) -> Result<(), Exception> {
+        self.pending_writes = None;
+        self.pending_nodes.clear();
         Ok(())
     }
 }
@@ -1,16 +1,24 @@
 use blake2_rfc;
 
-use crate::constants::KEY_LEN;
-
 #[derive(Clone)]
 pub struct Blake2bHasher(blake2_rfc::blake2b::Blake2b);
 
-impl crate::traits::Hasher for Blake2bHasher {
+/// The width of a hash made by `Blake2bHasher`.
+struct HashWidth<const N: usize>;
+
+impl<const N: usize> HashWidth<N> {
+    /// Fails to compile when used for a width `Blake2b` can not produce.
+    const CHECK: () = assert!(N >= 1 && N <= 64, "BLAKE2b hashes must be 1 to 64 bytes");
+}
+
+/// `Blake2b` produces hashes of 1 to 64 bytes, so using it for any other width fails to compile.
+impl<const N: usize> crate::traits::Hasher<N> for Blake2bHasher {
     type HashType = Self;
 
     #[inline]
-    fn new(size: usize) -> Self {
-        let hasher = blake2_rfc::blake2b::Blake2b::new(size);
+    fn new(_size: usize) -> Self {
+        let () = HashWidth::<N>::CHECK;
+        let hasher = blake2_rfc::blake2b::Blake2b::new(N);
         Self(hasher)
     }
 
@@ -20,9 +28,9 @@ impl crate::traits::Hasher for Blake2bHasher {
     }
 
     #[inline]
-    fn finalize(self) -> [u8; KEY_LEN] {
+    fn finalize(self) -> [u8; N] {
         let result = self.0.finalize();
-        let mut finalized = [0; KEY_LEN];
+        let mut finalized = [0; N];
         finalized.copy_from_slice(result.as_ref());
         finalized
     }
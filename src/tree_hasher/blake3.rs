@@ -0,0 +1,24 @@
+pub struct Blake3Hasher(blake3::Hasher);
+
+impl<const N: usize> crate::traits::Hasher<N> for Blake3Hasher {
+    type HashType = Self;
+
+    #[inline]
+    fn new(_size: usize) -> Self {
+        let hasher = blake3::Hasher::new();
+        Self(hasher)
+    }
+
+    #[inline]
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    #[inline]
+    fn finalize(self) -> [u8; N] {
+        // BLAKE3 can produce output of any length, and its first 32 bytes are the usual hash
+        let mut finalized = [0; N];
+        self.0.finalize_xof().fill(&mut finalized);
+        finalized
+    }
+}
@@ -1,26 +1,62 @@
 use std::collections::hash_map::DefaultHasher;
 use std::hash::Hasher;
 
-use crate::constants::KEY_LEN;
+/// A non-cryptographic hasher built on the default Rust hashing function that fills every byte of
+/// the hash.  The input is hashed once, then each 8 byte lane of the output is finished from a copy
+/// of that state seeded with the index of the lane.
+#[derive(Clone, Debug, Default)]
+pub struct DefaultTreeHasher(DefaultHasher);
 
-impl crate::traits::Hasher for DefaultHasher {
+impl<const N: usize> crate::traits::Hasher<N> for DefaultTreeHasher {
     type HashType = Self;
 
     #[inline]
     fn new(_size: usize) -> Self {
-        Self::new()
+        Self(DefaultHasher::new())
     }
 
     #[inline]
     fn update(&mut self, data: &[u8]) {
-        Self::write(self, data)
+        self.0.write(data)
     }
 
     #[inline]
-    fn finalize(self) -> [u8; KEY_LEN] {
-        let value = Self::finish(&self).to_le_bytes();
-        let mut v = [0; KEY_LEN];
-        v[..8].copy_from_slice(&value);
+    fn finalize(self) -> [u8; N] {
+        let mut v = [0; N];
+        for (lane, chunk) in v.chunks_mut(8).enumerate() {
+            let mut lane_hasher = self.0.clone();
+            lane_hasher.write_u64(lane as u64);
+            let value = lane_hasher.finish().to_le_bytes();
+            chunk.copy_from_slice(&value[..chunk.len()]);
+        }
+        v
+    }
+}
+
+/// The previous default hasher, which only fills the first 8 bytes of the hash and leaves the rest
+/// as zeros.  Only use this for reading trees that were written with it.
+#[derive(Clone, Debug, Default)]
+pub struct LegacyDefaultHasher(DefaultHasher);
+
+impl<const N: usize> crate::traits::Hasher<N> for LegacyDefaultHasher {
+    type HashType = Self;
+
+    #[inline]
+    fn new(_size: usize) -> Self {
+        Self(DefaultHasher::new())
+    }
+
+    #[inline]
+    fn update(&mut self, data: &[u8]) {
+        self.0.write(data)
+    }
+
+    #[inline]
+    fn finalize(self) -> [u8; N] {
+        let value = self.0.finish().to_le_bytes();
+        let mut v = [0; N];
+        let width = N.min(8);
+        v[..width].copy_from_slice(&value[..width]);
         v
     }
 }
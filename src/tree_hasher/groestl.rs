@@ -1,10 +1,8 @@
 use groestl::{Digest, Groestl256};
 
-use crate::constants::KEY_LEN;
-
 pub struct GroestlHasher(Groestl256);
 
-impl crate::traits::Hasher for GroestlHasher {
+impl crate::traits::Hasher<32> for GroestlHasher {
     type HashType = Self;
 
     #[inline]
@@ -19,8 +17,8 @@ impl crate::traits::Hasher for GroestlHasher {
     }
 
     #[inline]
-    fn finalize(self) -> [u8; KEY_LEN] {
-        let mut finalized = [0; KEY_LEN];
+    fn finalize(self) -> [u8; 32] {
+        let mut finalized = [0; 32];
         let result = self.0.result();
         finalized.copy_from_slice(&result);
         finalized
@@ -1,10 +1,8 @@
 use tiny_keccak::Keccak;
 
-use crate::constants::KEY_LEN;
-
 pub struct KeccakHasher(Keccak);
 
-impl crate::traits::Hasher for KeccakHasher {
+impl crate::traits::Hasher<32> for KeccakHasher {
     type HashType = Self;
 
     #[inline]
@@ -19,8 +17,8 @@ impl crate::traits::Hasher for KeccakHasher {
     }
 
     #[inline]
-    fn finalize(self) -> [u8; KEY_LEN] {
-        let mut res = [0_u8; KEY_LEN];
+    fn finalize(self) -> [u8; 32] {
+        let mut res = [0_u8; 32];
         self.0.finalize(&mut res);
         res
     }
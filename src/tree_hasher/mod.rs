@@ -1,19 +1,18 @@
 #[cfg(feature = "use_blake2b")]
 pub mod blake2b;
-/// The default Rust hashing function expanded to 32 bytes.
-#[cfg(not(any(
-    feature = "use_blake2b",
-    feature = "use_groestl",
-    feature = "use_sha2",
-    feature = "use_sha3",
-    feature = "use_keccak",
-    feature = "use_seahash",
-)))]
+/// Holds the implementation of `crate::traits::Hasher` for BLAKE3
+#[cfg(feature = "use_blake3")]
+pub mod blake3;
+/// The default Rust hashing function expanded to the length of the hash, along with the legacy
+/// hasher that only fills 8 bytes.
 pub mod default;
 #[cfg(feature = "use_groestl")]
 pub mod groestl;
 #[cfg(feature = "use_keccak")]
 pub mod keccak;
+/// Holds a pure Rust implementation of SHA-256, for builds without OpenSSL
+#[cfg(feature = "use_rust_sha2")]
+pub mod rust_sha256;
 /// Holds the implementation of `crate::traits::Hasher` for `SeaHasher`
 #[cfg(feature = "use_seahash")]
 pub mod seahasher;
@@ -21,30 +20,91 @@ pub mod seahasher;
 pub mod sha256;
 #[cfg(feature = "use_sha3")]
 pub mod sha3;
+/// Holds the `TaggedScheme` struct, the default `crate::traits::HashingScheme`
+pub mod tagged_scheme;
+
+// Every enabled hasher can be used side by side by naming it as the `HasherType` of a tree.
+// `TreeHasher` is only the default choice, taken from the first enabled feature in the order
+// `use_blake2b`, `use_blake3`, `use_groestl`, `use_sha2`, `use_rust_sha2`, `use_sha3`,
+// `use_keccak`, `use_seahash`, falling back to `DefaultTreeHasher`.
 
 /// The kind of hasher to use in the tree.
 #[cfg(not(any(
     feature = "use_blake2b",
+    feature = "use_blake3",
     feature = "use_groestl",
     feature = "use_sha2",
+    feature = "use_rust_sha2",
     feature = "use_sha3",
     feature = "use_keccak",
     feature = "use_seahash",
 )))]
-pub type TreeHasher = std::collections::hash_map::DefaultHasher;
+pub type TreeHasher = default::DefaultTreeHasher;
 
 #[cfg(feature = "use_blake2b")]
 pub type TreeHasher = blake2b::Blake2bHasher;
 
-#[cfg(feature = "use_groestl")]
+#[cfg(all(feature = "use_blake3", not(any(feature = "use_blake2b"))))]
+pub type TreeHasher = blake3::Blake3Hasher;
+#[cfg(all(
+    feature = "use_groestl",
+    not(any(feature = "use_blake2b", feature = "use_blake3"))
+))]
 pub type TreeHasher = groestl::GroestlHasher;
-#[cfg(feature = "use_sha2")]
+#[cfg(all(
+    feature = "use_sha2",
+    not(any(
+        feature = "use_blake2b",
+        feature = "use_blake3",
+        feature = "use_groestl"
+    ))
+))]
 pub type TreeHasher = sha256::Sha256Hasher;
-#[cfg(feature = "use_sha3")]
+#[cfg(all(
+    feature = "use_rust_sha2",
+    not(any(
+        feature = "use_blake2b",
+        feature = "use_blake3",
+        feature = "use_groestl",
+        feature = "use_sha2"
+    ))
+))]
+pub type TreeHasher = rust_sha256::RustSha256Hasher;
+#[cfg(all(
+    feature = "use_sha3",
+    not(any(
+        feature = "use_blake2b",
+        feature = "use_blake3",
+        feature = "use_groestl",
+        feature = "use_sha2",
+        feature = "use_rust_sha2"
+    ))
+))]
 pub type TreeHasher = sha3::Sha3Hasher;
-#[cfg(feature = "use_keccak")]
+#[cfg(all(
+    feature = "use_keccak",
+    not(any(
+        feature = "use_blake2b",
+        feature = "use_blake3",
+        feature = "use_groestl",
+        feature = "use_sha2",
+        feature = "use_rust_sha2",
+        feature = "use_sha3"
+    ))
+))]
 pub type TreeHasher = keccak::KeccakHasher;
 
 /// The kind of hasher to use in the tree.
-#[cfg(feature = "use_seahash")]
+#[cfg(all(
+    feature = "use_seahash",
+    not(any(
+        feature = "use_blake2b",
+        feature = "use_blake3",
+        feature = "use_groestl",
+        feature = "use_sha2",
+        feature = "use_rust_sha2",
+        feature = "use_sha3",
+        feature = "use_keccak"
+    ))
+))]
 pub type TreeHasher = seahash::SeaHasher;
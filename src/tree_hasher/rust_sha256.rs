@@ -0,0 +1,26 @@
+use sha2::{Digest, Sha256};
+
+pub struct RustSha256Hasher(Sha256);
+
+impl crate::traits::Hasher<32> for RustSha256Hasher {
+    type HashType = Self;
+
+    #[inline]
+    fn new(_size: usize) -> Self {
+        let hasher = Sha256::new();
+        Self(hasher)
+    }
+
+    #[inline]
+    fn update(&mut self, data: &[u8]) {
+        self.0.input(data);
+    }
+
+    #[inline]
+    fn finalize(self) -> [u8; 32] {
+        let mut finalized = [0; 32];
+        let result = self.0.result();
+        finalized.copy_from_slice(&result);
+        finalized
+    }
+}
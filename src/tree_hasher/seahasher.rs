@@ -1,9 +1,30 @@
 use seahash::SeaHasher;
 use std::hash::Hasher;
 
-use crate::constants::KEY_LEN;
+/// `SeaHasher` produces 8 byte hashes, so it fills the whole hash of a tree with 8 byte locations.
+impl crate::traits::Hasher<8> for SeaHasher {
+    type HashType = Self;
+
+    #[inline]
+    fn new(_size: usize) -> Self {
+        Self::new()
+    }
+
+    #[inline]
+    fn update(&mut self, data: &[u8]) {
+        Hasher::write(self, data)
+    }
+
+    #[inline]
+    fn finalize(self) -> [u8; 8] {
+        Self::finish(&self).to_le_bytes()
+    }
+}
 
-impl crate::traits::Hasher for SeaHasher {
+/// For 32 byte locations, only the first 8 bytes are filled and the rest are left as zeros, as in
+/// trees written before the width of hashes could be chosen.  Locations are then no less likely to
+/// collide than 8 byte ones, so prefer a hasher that fills all 32 bytes for new trees.
+impl crate::traits::Hasher<32> for SeaHasher {
     type HashType = Self;
 
     #[inline]
@@ -17,9 +38,9 @@ impl crate::traits::Hasher for SeaHasher {
     }
 
     #[inline]
-    fn finalize(self) -> [u8; KEY_LEN] {
+    fn finalize(self) -> [u8; 32] {
         let value = Self::finish(&self).to_le_bytes();
-        let mut v = [0; KEY_LEN];
+        let mut v = [0; 32];
         v[..8].copy_from_slice(&value);
         v
     }
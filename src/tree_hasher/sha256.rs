@@ -1,10 +1,8 @@
 use openssl::sha::Sha256;
 
-use crate::constants::KEY_LEN;
-
 pub struct Sha256Hasher(Sha256);
 
-impl crate::traits::Hasher for Sha256Hasher {
+impl crate::traits::Hasher<32> for Sha256Hasher {
     type HashType = Self;
 
     #[inline]
@@ -19,7 +17,7 @@ impl crate::traits::Hasher for Sha256Hasher {
     }
 
     #[inline]
-    fn finalize(self) -> [u8; KEY_LEN] {
+    fn finalize(self) -> [u8; 32] {
         self.0.finish()
     }
 }
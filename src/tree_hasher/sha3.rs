@@ -1,10 +1,8 @@
 use tiny_keccak::Keccak;
 
-use crate::constants::KEY_LEN;
-
 pub struct Sha3Hasher(Keccak);
 
-impl crate::traits::Hasher for Sha3Hasher {
+impl crate::traits::Hasher<32> for Sha3Hasher {
     type HashType = Self;
 
     #[inline]
@@ -19,8 +17,8 @@ impl crate::traits::Hasher for Sha3Hasher {
     }
 
     #[inline]
-    fn finalize(self) -> [u8; KEY_LEN] {
-        let mut res = [0; KEY_LEN];
+    fn finalize(self) -> [u8; 32] {
+        let mut res = [0; 32];
         self.0.finalize(&mut res);
         res
     }
@@ -0,0 +1,139 @@
+use std::marker::PhantomData;
+
+use crate::traits::{Hasher, HashingScheme};
+
+/// A `HashingScheme` that hashes each kind of node with `HasherType`, starting with a tag for the
+/// kind of node.  By default the tags are `b"d"` for data, `b"l"` for leaves and `b"b"` for
+/// branches, and the location of a branch only covers the locations of its children.  This is the
+/// scheme existing trees were written with; `TaggedScheme::committed` also binds the position of
+/// each branch.
+pub struct TaggedScheme<HasherType> {
+    /// The tag hashed before the key and value of a `Data` node.
+    pub data_tag: Vec<u8>,
+    /// The tag hashed before the key and data location of a `Leaf` node.
+    pub leaf_tag: Vec<u8>,
+    /// The tag hashed before the child locations of a `Branch` node.
+    pub branch_tag: Vec<u8>,
+    /// Whether the location of a branch also covers its split index.
+    pub commit_split_index: bool,
+    /// Whether the location of a branch also covers the bits before its split index that every key
+    /// beneath it shares.
+    pub commit_key: bool,
+    /// Whether the location of a branch also covers the count of leaves beneath it.
+    pub commit_count: bool,
+    /// Marker for dealing with `HasherType`.
+    pub hasher: PhantomData<fn() -> HasherType>,
+}
+
+impl<HasherType> TaggedScheme<HasherType> {
+    /// Creates a new `TaggedScheme` with the given tags, where the location of a branch only covers
+    /// the locations of its children.  No tag should be a prefix of another.
+    #[inline]
+    pub fn new(data_tag: &[u8], leaf_tag: &[u8], branch_tag: &[u8]) -> Self {
+        Self {
+            data_tag: data_tag.to_vec(),
+            leaf_tag: leaf_tag.to_vec(),
+            branch_tag: branch_tag.to_vec(),
+            commit_split_index: false,
+            commit_key: false,
+            commit_count: false,
+            hasher: PhantomData,
+        }
+    }
+
+    /// Creates a `TaggedScheme` with the default tags where the location of a branch also covers
+    /// its split index and the shared bits of its keys, so proofs can not move a branch to another
+    /// position in the tree.  Set `commit_count` to cover the count of leaves too.
+    #[inline]
+    pub fn committed() -> Self {
+        Self {
+            commit_split_index: true,
+            commit_key: true,
+            ..Self::default()
+        }
+    }
+}
+
+impl<HasherType> Default for TaggedScheme<HasherType> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(b"d", b"l", b"b")
+    }
+}
+
+impl<HasherType> Clone for TaggedScheme<HasherType> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            data_tag: self.data_tag.clone(),
+            leaf_tag: self.leaf_tag.clone(),
+            branch_tag: self.branch_tag.clone(),
+            commit_split_index: self.commit_split_index,
+            commit_key: self.commit_key,
+            commit_count: self.commit_count,
+            hasher: PhantomData,
+        }
+    }
+}
+
+impl<HasherType, const KEY_LEN: usize, const HASH_LEN: usize> HashingScheme<KEY_LEN, HASH_LEN>
+    for TaggedScheme<HasherType>
+where
+    HasherType: Hasher<HASH_LEN, HashType = HasherType>,
+{
+    #[inline]
+    fn data_location(&self, key: &[u8; KEY_LEN], value: &[u8]) -> [u8; HASH_LEN] {
+        let mut data_hasher = HasherType::new(HASH_LEN);
+        data_hasher.update(&self.data_tag);
+        data_hasher.update(key);
+        data_hasher.update(value);
+        data_hasher.finalize()
+    }
+
+    #[inline]
+    fn leaf_location(&self, key: &[u8; KEY_LEN], data: &[u8; HASH_LEN]) -> [u8; HASH_LEN] {
+        let mut leaf_hasher = HasherType::new(HASH_LEN);
+        leaf_hasher.update(&self.leaf_tag);
+        leaf_hasher.update(key);
+        leaf_hasher.update(data);
+        leaf_hasher.finalize()
+    }
+
+    #[inline]
+    fn branch_location(
+        &self,
+        zero: &[u8; HASH_LEN],
+        one: &[u8; HASH_LEN],
+        split_index: u8,
+        key: &[u8; KEY_LEN],
+        count: u64,
+    ) -> [u8; HASH_LEN] {
+        let mut branch_hasher = HasherType::new(HASH_LEN);
+        branch_hasher.update(&self.branch_tag);
+        branch_hasher.update(zero);
+        branch_hasher.update(one);
+        if self.commit_split_index {
+            branch_hasher.update(&[split_index]);
+        }
+        if self.commit_key {
+            // Only the first `split_index` bits are shared by every key under the branch
+            let mut prefix = [0; KEY_LEN];
+            let full_bytes = (split_index >> 3) as usize;
+            prefix[..full_bytes].copy_from_slice(&key[..full_bytes]);
+            let remaining_bits = split_index % 8;
+            if remaining_bits > 0 {
+                prefix[full_bytes] = key[full_bytes] & (0xFF << (8 - remaining_bits));
+            }
+            branch_hasher.update(&prefix);
+        }
+        if self.commit_count {
+            branch_hasher.update(&count.to_be_bytes());
+        }
+        branch_hasher.finalize()
+    }
+
+    #[inline]
+    fn commits_position(&self) -> bool {
+        self.commit_split_index && self.commit_key
+    }
+}
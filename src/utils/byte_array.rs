@@ -0,0 +1,58 @@
+use std::fmt::{Formatter, Result as FmtResult};
+
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserializer, Serializer};
+
+/// Serializes a byte array of any length as a tuple, the same way serde serializes short arrays.
+#[inline]
+pub fn serialize<SerializerType, const LEN: usize>(
+    array: &[u8; LEN],
+    serializer: SerializerType,
+) -> Result<SerializerType::Ok, SerializerType::Error>
+where
+    SerializerType: Serializer,
+{
+    let mut tuple = serializer.serialize_tuple(LEN)?;
+    for byte in array {
+        tuple.serialize_element(byte)?;
+    }
+    tuple.end()
+}
+
+/// Deserializes a byte array of any length written by `serialize`.
+#[inline]
+pub fn deserialize<'de, DeserializerType, const LEN: usize>(
+    deserializer: DeserializerType,
+) -> Result<[u8; LEN], DeserializerType::Error>
+where
+    DeserializerType: Deserializer<'de>,
+{
+    deserializer.deserialize_tuple(LEN, ByteArrayVisitor)
+}
+
+/// Visits the bytes of an array of `LEN` bytes.
+struct ByteArrayVisitor<const LEN: usize>;
+
+impl<'de, const LEN: usize> Visitor<'de> for ByteArrayVisitor<LEN> {
+    type Value = [u8; LEN];
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+        write!(formatter, "an array of {} bytes", LEN)
+    }
+
+    #[inline]
+    fn visit_seq<SeqType>(self, mut seq: SeqType) -> Result<Self::Value, SeqType::Error>
+    where
+        SeqType: SeqAccess<'de>,
+    {
+        let mut array = [0; LEN];
+        for (i, byte) in array.iter_mut().enumerate() {
+            *byte = seq
+                .next_element()?
+                .ok_or_else(|| SeqType::Error::invalid_length(i, &self))?;
+        }
+        Ok(array)
+    }
+}
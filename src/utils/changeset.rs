@@ -0,0 +1,98 @@
+use crate::proof::encoding::{ProofReader, ProofWriter, CHANGESET_TAG};
+use crate::traits::{Decode, Encode, Exception};
+
+/// The changes that take a tree from one root to the next.  Applying the changes on top of
+/// `previous_root` gives back exactly `new_root`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Changeset<ValueType, const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
+    /// The root the changes are applied on top of, or `None` for an empty tree.
+    pub previous_root: Option<[u8; HASH_LEN]>,
+    /// The root produced by applying the changes.
+    pub new_root: [u8; HASH_LEN],
+    /// The changed keys in ascending order along with their new values, or `None` for removed
+    /// keys.
+    pub changes: Vec<([u8; KEY_LEN], Option<ValueType>)>,
+}
+
+impl<ValueType, const KEY_LEN: usize, const HASH_LEN: usize>
+    Changeset<ValueType, KEY_LEN, HASH_LEN>
+{
+    /// Creates a new `Changeset`.
+    #[inline]
+    pub const fn new(
+        previous_root: Option<[u8; HASH_LEN]>,
+        new_root: [u8; HASH_LEN],
+        changes: Vec<([u8; KEY_LEN], Option<ValueType>)>,
+    ) -> Self {
+        Self {
+            previous_root,
+            new_root,
+            changes,
+        }
+    }
+}
+
+impl<ValueType: Encode, const KEY_LEN: usize, const HASH_LEN: usize> Encode
+    for Changeset<ValueType, KEY_LEN, HASH_LEN>
+{
+    #[inline]
+    fn encode(&self) -> Result<Vec<u8>, Exception> {
+        let mut writer = ProofWriter::new(CHANGESET_TAG);
+        match &self.previous_root {
+            Some(previous_root) => {
+                writer.write_u8(1);
+                writer.write_location(previous_root);
+            }
+            None => writer.write_u8(0),
+        }
+        writer.write_location(&self.new_root);
+        writer.write_len(self.changes.len())?;
+        for (key, value) in &self.changes {
+            writer.write_key(key);
+            match value {
+                Some(v) => {
+                    writer.write_u8(1);
+                    writer.write_bytes(&v.encode()?)?;
+                }
+                None => writer.write_u8(0),
+            }
+        }
+        Ok(writer.finish())
+    }
+}
+
+impl<ValueType: Decode, const KEY_LEN: usize, const HASH_LEN: usize> Decode
+    for Changeset<ValueType, KEY_LEN, HASH_LEN>
+{
+    #[inline]
+    fn decode(buffer: &[u8]) -> Result<Self, Exception> {
+        let mut reader = ProofReader::new(buffer, CHANGESET_TAG)?;
+        let previous_root = match reader.read_u8()? {
+            0 => None,
+            1 => Some(reader.read_location()?),
+            _ => {
+                return Err(Exception::new(
+                    "Failed to decode changeset: Invalid root flag",
+                ))
+            }
+        };
+        let new_root = reader.read_location()?;
+        let len = reader.read_len()?;
+        let mut changes = Vec::new();
+        for _ in 0..len {
+            let key = reader.read_key()?;
+            let value = match reader.read_u8()? {
+                0 => None,
+                1 => Some(ValueType::decode(&reader.read_bytes()?)?),
+                _ => {
+                    return Err(Exception::new(
+                        "Failed to decode changeset: Invalid value flag",
+                    ))
+                }
+            };
+            changes.push((key, value));
+        }
+        reader.finish()?;
+        Ok(Self::new(previous_root, new_root, changes))
+    }
+}
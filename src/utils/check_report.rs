@@ -0,0 +1,48 @@
+/// The problems found by checking the nodes held under a set of live roots.  Each list is in
+/// ascending order of location.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckReport<const HASH_LEN: usize = 32> {
+    /// Nodes whose stored references differ from the references found, along with the stored and
+    /// the expected counts.
+    pub mismatched_references: Vec<([u8; HASH_LEN], u64, u64)>,
+    /// Nodes in the database that can not be reached from any live root.
+    pub unreachable: Vec<[u8; HASH_LEN]>,
+    /// Locations that are not in the database, along with the node pointing to them, or `None`
+    /// for a live root.
+    pub dangling: Vec<(Option<[u8; HASH_LEN]>, [u8; HASH_LEN])>,
+    /// Nodes of the wrong kind for where they are pointed to from, such as a leaf whose data
+    /// location holds another leaf.  Nodes beneath them are not checked.
+    pub corrupt: Vec<[u8; HASH_LEN]>,
+    /// Whether any mismatched references were rewritten with the expected counts.
+    pub repaired: bool,
+}
+
+impl<const HASH_LEN: usize> CheckReport<HASH_LEN> {
+    /// Creates a new, empty `CheckReport`.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            mismatched_references: Vec::new(),
+            unreachable: Vec::new(),
+            dangling: Vec::new(),
+            corrupt: Vec::new(),
+            repaired: false,
+        }
+    }
+
+    /// Checks if no problems were found.
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.mismatched_references.is_empty()
+            && self.unreachable.is_empty()
+            && self.dangling.is_empty()
+            && self.corrupt.is_empty()
+    }
+}
+
+impl<const HASH_LEN: usize> Default for CheckReport<HASH_LEN> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
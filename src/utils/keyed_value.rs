@@ -0,0 +1,59 @@
+use crate::traits::{Decode, Encode, Exception};
+
+/// A value stored along with the original key it was inserted under, so that the key can be
+/// recovered from the hash it is stored at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyedValue {
+    /// The original key.
+    pub key: Vec<u8>,
+    /// The encoded value.
+    pub value: Vec<u8>,
+}
+
+impl KeyedValue {
+    /// Creates a new `KeyedValue`.
+    #[inline]
+    pub const fn new(key: Vec<u8>, value: Vec<u8>) -> Self {
+        Self { key, value }
+    }
+}
+
+impl Encode for KeyedValue {
+    /// Encodes the key prefixed by its length as a big endian `u32`, followed by the value.
+    #[inline]
+    fn encode(&self) -> Result<Vec<u8>, Exception> {
+        if self.key.len() > u32::MAX as usize {
+            return Err(Exception::new(
+                "Failed to encode keyed value: Key length exceeds maximum",
+            ));
+        }
+        let mut buffer = Vec::with_capacity(4 + self.key.len() + self.value.len());
+        buffer.extend_from_slice(&(self.key.len() as u32).to_be_bytes());
+        buffer.extend_from_slice(&self.key);
+        buffer.extend_from_slice(&self.value);
+        Ok(buffer)
+    }
+}
+
+impl Decode for KeyedValue {
+    #[inline]
+    fn decode(buffer: &[u8]) -> Result<Self, Exception> {
+        if buffer.len() < 4 {
+            return Err(Exception::new(
+                "Failed to decode keyed value: Unexpected end of buffer",
+            ));
+        }
+        let mut len_bytes = [0; 4];
+        len_bytes.copy_from_slice(&buffer[..4]);
+        let key_end = 4 + u32::from_be_bytes(len_bytes) as usize;
+        if buffer.len() < key_end {
+            return Err(Exception::new(
+                "Failed to decode keyed value: Unexpected end of buffer",
+            ));
+        }
+        Ok(Self::new(
+            buffer[4..key_end].to_vec(),
+            buffer[key_end..].to_vec(),
+        ))
+    }
+}
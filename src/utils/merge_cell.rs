@@ -1,24 +1,24 @@
 use crate::utils::tree_ref::TreeRef;
 
 /// This is primarily for marking this collection as `Send` and `Sync`.
-pub struct MergeCell {
+pub struct MergeCell<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
     /// The split index to be used.
     pub split_index: u8,
     /// A raw pointer to the `TreeRef`.
-    pub tree_ref_pointer: *mut TreeRef,
+    pub tree_ref_pointer: *mut TreeRef<KEY_LEN, HASH_LEN>,
     /// A raw pointer to the adjacent `TreeRef`.
-    pub next_tree_ref_pointer: *mut TreeRef,
+    pub next_tree_ref_pointer: *mut TreeRef<KEY_LEN, HASH_LEN>,
     /// The index in the list of `tree_refs` of `tree_ref_pointer`.
     pub index: usize,
 }
 
-impl MergeCell {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> MergeCell<KEY_LEN, HASH_LEN> {
     /// Creates a new `MergeCell`.
     #[inline]
     pub const fn new(
         split_index: u8,
-        tree_ref_pointer: *mut TreeRef,
-        next_tree_ref_pointer: *mut TreeRef,
+        tree_ref_pointer: *mut TreeRef<KEY_LEN, HASH_LEN>,
+        next_tree_ref_pointer: *mut TreeRef<KEY_LEN, HASH_LEN>,
         index: usize,
     ) -> Self {
         Self {
@@ -31,7 +31,14 @@ impl MergeCell {
 
     /// Decomposes the structure into its constituent parts
     #[inline]
-    pub const fn deconstruct(self) -> (u8, *mut TreeRef, *mut TreeRef, usize) {
+    pub const fn deconstruct(
+        self,
+    ) -> (
+        u8,
+        *mut TreeRef<KEY_LEN, HASH_LEN>,
+        *mut TreeRef<KEY_LEN, HASH_LEN>,
+        usize,
+    ) {
         (
             self.split_index,
             self.tree_ref_pointer,
@@ -42,6 +49,6 @@ impl MergeCell {
 }
 
 #[cfg(feature = "use_rayon")]
-unsafe impl Send for MergeCell {}
+unsafe impl<const KEY_LEN: usize, const HASH_LEN: usize> Send for MergeCell<KEY_LEN, HASH_LEN> {}
 #[cfg(feature = "use_rayon")]
-unsafe impl Sync for MergeCell {}
+unsafe impl<const KEY_LEN: usize, const HASH_LEN: usize> Sync for MergeCell<KEY_LEN, HASH_LEN> {}
@@ -1,10 +1,25 @@
+/// Holds functions for serializing byte arrays of any length with serde
+#[cfg(feature = "use_serde")]
+pub mod byte_array;
+/// Holds the `Changeset` struct
+pub mod changeset;
+/// Holds the `CheckReport` struct
+pub mod check_report;
+/// Holds the `KeyedValue` struct
+pub mod keyed_value;
 #[cfg(feature = "use_rayon")]
 pub mod merge_cell;
 /// Holds the `TreeCell` struct
 pub mod tree_cell;
+/// Holds the `TreeDiff` struct
+pub mod tree_diff;
+/// Holds the `TreeIter` struct
+pub mod tree_iter;
 /// Holds the `TreeRef` struct
 pub mod tree_ref;
 #[cfg(feature = "use_rayon")]
 pub mod tree_ref_raw;
 /// Holds a collection of useful functions for tree operations
 pub mod tree_utils;
+/// Holds the `WriteBatch` struct
+pub mod write_batch;
@@ -1,10 +1,9 @@
-use crate::constants::KEY_LEN;
 use crate::traits::{Branch, Data, Leaf};
 
 /// Represents a position in the tree during tree traversal.
-pub struct TreeCell<'a, NodeType> {
+pub struct TreeCell<'a, NodeType, const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
     /// The location of the node being traversed.
-    pub location: [u8; KEY_LEN],
+    pub location: [u8; HASH_LEN],
     /// The keys traversing this part of the tree.
     pub keys: &'a [&'a [u8; KEY_LEN]],
     /// The node currently being traversed.
@@ -13,18 +12,20 @@ pub struct TreeCell<'a, NodeType> {
     pub depth: usize,
 }
 
-impl<'a, 'b, NodeType> TreeCell<'a, NodeType> {
+impl<'a, 'b, NodeType, const KEY_LEN: usize, const HASH_LEN: usize>
+    TreeCell<'a, NodeType, KEY_LEN, HASH_LEN>
+{
     /// Creates a new `TreeCell`.
     #[inline]
     pub fn new<BranchType, LeafType, DataType>(
-        location: [u8; KEY_LEN],
+        location: [u8; HASH_LEN],
         keys: &'a [&'a [u8; KEY_LEN]],
         node: NodeType,
         depth: usize,
     ) -> Self
     where
-        BranchType: Branch,
-        LeafType: Leaf,
+        BranchType: Branch<KEY_LEN, HASH_LEN>,
+        LeafType: Leaf<KEY_LEN, HASH_LEN>,
         DataType: Data,
     {
         Self {
@@ -0,0 +1,35 @@
+/// The keys that differ between two roots, each list in ascending order of keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeDiff<ValueType, KeyType = [u8; 32]> {
+    /// The keys and values held under the new root but not the old root.
+    pub added: Vec<(KeyType, ValueType)>,
+    /// The keys and values held under the old root but not the new root.
+    pub removed: Vec<(KeyType, ValueType)>,
+    /// The keys held under both roots with different values, along with the old and new values.
+    pub changed: Vec<(KeyType, ValueType, ValueType)>,
+}
+
+impl<ValueType, KeyType> TreeDiff<ValueType, KeyType> {
+    /// Creates a new, empty `TreeDiff`.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+
+    /// Checks if the two roots hold the same keys and values.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl<ValueType, KeyType> Default for TreeDiff<ValueType, KeyType> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,205 @@
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Branch, Data, Database, Decode, Exception, Leaf, Node, NodeVariant};
+use crate::utils::tree_utils::calc_child_bounds;
+
+/// An iterator over the keys and values held under a root, in ascending order of keys.  Nodes are
+/// loaded from the database as they are reached, and subtrees that cannot hold keys within the
+/// bounds of the iterator are skipped.
+pub struct TreeIter<
+    'a,
+    DatabaseType,
+    BranchType,
+    LeafType,
+    DataType,
+    NodeType,
+    ValueType,
+    const KEY_LEN: usize = 32,
+    const HASH_LEN: usize = 32,
+> {
+    /// The database holding the tree nodes.
+    db: &'a DatabaseType,
+    /// The locations still to visit along with their depths, with the next location on top.
+    stack: Vec<([u8; HASH_LEN], usize)>,
+    /// The maximum depth of the tree.
+    depth: usize,
+    /// The bounds on the keys to iterate over.
+    bounds: (Bound<[u8; KEY_LEN]>, Bound<[u8; KEY_LEN]>),
+    /// Marker for dealing with `BranchType`.
+    branch: PhantomData<*const BranchType>,
+    /// Marker for dealing with `LeafType`.
+    leaf: PhantomData<*const LeafType>,
+    /// Marker for dealing with `DataType`.
+    data: PhantomData<*const DataType>,
+    /// Marker for dealing with `NodeType`.
+    node: PhantomData<*const NodeType>,
+    /// Marker for dealing with `ValueType`.
+    value: PhantomData<*const ValueType>,
+}
+
+impl<
+        'a,
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        ValueType,
+        const KEY_LEN: usize,
+        const HASH_LEN: usize,
+    >
+    TreeIter<
+        'a,
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    >
+{
+    /// Creates a new `TreeIter` starting from `root` over the keys within `bounds`.  `depth` is the
+    /// maximum depth of the tree.
+    #[inline]
+    pub fn new(
+        db: &'a DatabaseType,
+        root: [u8; HASH_LEN],
+        depth: usize,
+        bounds: (Bound<[u8; KEY_LEN]>, Bound<[u8; KEY_LEN]>),
+    ) -> Self {
+        Self {
+            db,
+            stack: vec![(root, 0)],
+            depth,
+            bounds,
+            branch: PhantomData,
+            leaf: PhantomData,
+            data: PhantomData,
+            node: PhantomData,
+            value: PhantomData,
+        }
+    }
+
+    /// Checks if any key between `min_key` and `max_key` inclusive is within the bounds.
+    fn overlaps(&self, min_key: &[u8; KEY_LEN], max_key: &[u8; KEY_LEN]) -> bool {
+        let after_start = match &self.bounds.0 {
+            Bound::Included(start) => max_key >= start,
+            Bound::Excluded(start) => max_key > start,
+            Bound::Unbounded => true,
+        };
+        let before_end = match &self.bounds.1 {
+            Bound::Included(end) => min_key <= end,
+            Bound::Excluded(end) => min_key < end,
+            Bound::Unbounded => true,
+        };
+        after_start && before_end
+    }
+
+    /// Stops the iteration and returns the given error.
+    fn fail(&mut self, error: Exception) -> BinaryMerkleTreeResult<([u8; KEY_LEN], ValueType)> {
+        self.stack.clear();
+        Err(error)
+    }
+}
+
+impl<
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        ValueType,
+        const KEY_LEN: usize,
+        const HASH_LEN: usize,
+    > Iterator
+    for TreeIter<
+        '_,
+        DatabaseType,
+        BranchType,
+        LeafType,
+        DataType,
+        NodeType,
+        ValueType,
+        KEY_LEN,
+        HASH_LEN,
+    >
+where
+    DatabaseType: Database<HASH_LEN, NodeType = NodeType>,
+    BranchType: Branch<KEY_LEN, HASH_LEN>,
+    LeafType: Leaf<KEY_LEN, HASH_LEN>,
+    DataType: Data,
+    NodeType: Node<BranchType, LeafType, DataType>,
+    ValueType: Decode,
+{
+    type Item = BinaryMerkleTreeResult<([u8; KEY_LEN], ValueType)>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((location, depth)) = self.stack.pop() {
+            if depth > self.depth {
+                return Some(self.fail(Exception::new("Depth of merkle tree exceeded")));
+            }
+
+            let node = match self.db.get_node(&location) {
+                Ok(Some(n)) => n,
+                // A missing root holds no items
+                Ok(None) if depth == 0 => return None,
+                Ok(None) => {
+                    return Some(self.fail(Exception::new(
+                        "Corrupt merkle tree: Failed to get child node from DB",
+                    )))
+                }
+                Err(e) => return Some(self.fail(e)),
+            };
+
+            match node.get_variant() {
+                NodeVariant::Branch(branch) => {
+                    let split_index = branch.get_split_index();
+                    let (one_min, one_max) =
+                        calc_child_bounds(branch.get_key(), split_index, false);
+                    if self.overlaps(&one_min, &one_max) {
+                        self.stack.push((*branch.get_one(), depth + 1));
+                    }
+                    let (zero_min, zero_max) =
+                        calc_child_bounds(branch.get_key(), split_index, true);
+                    if self.overlaps(&zero_min, &zero_max) {
+                        self.stack.push((*branch.get_zero(), depth + 1));
+                    }
+                }
+                NodeVariant::Leaf(leaf) => {
+                    if !self.bounds.contains(leaf.get_key()) {
+                        continue;
+                    }
+                    let data_node = match self.db.get_node(leaf.get_data()) {
+                        Ok(Some(d)) => d,
+                        Ok(None) => {
+                            return Some(self.fail(Exception::new(
+                                "Corrupt merkle tree: Failed to get data node from DB",
+                            )))
+                        }
+                        Err(e) => return Some(self.fail(e)),
+                    };
+                    if let NodeVariant::Data(data) = data_node.get_variant() {
+                        return match ValueType::decode(data.get_value()) {
+                            Ok(value) => Some(Ok((*leaf.get_key(), value))),
+                            Err(e) => Some(self.fail(e)),
+                        };
+                    }
+                    return Some(self.fail(Exception::new(
+                        "Corrupt merkle tree: Found non data node after leaf",
+                    )));
+                }
+                NodeVariant::Data(_) => {
+                    return Some(self.fail(Exception::new(
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    )));
+                }
+            }
+        }
+        None
+    }
+}
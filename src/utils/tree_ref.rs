@@ -1,14 +1,12 @@
 use std::cmp::Ordering;
 
-use crate::constants::KEY_LEN;
-
 /// A reference to a node in the tree.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd)]
-pub struct TreeRef {
+pub struct TreeRef<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32> {
     /// The associated key with this `TreeRef`.
     pub key: [u8; KEY_LEN],
     /// The location of the `TreeRef` in the tree.
-    pub location: [u8; KEY_LEN],
+    pub location: [u8; HASH_LEN],
     /// The total number of elements underneath this `TreeRef`.  This represents the total number of nodes
     /// under this node in the tree.
     pub node_count: u64,
@@ -17,12 +15,12 @@ pub struct TreeRef {
     pub count: u32,
 }
 
-impl TreeRef {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> TreeRef<KEY_LEN, HASH_LEN> {
     /// Creates a new TreeRef.
     #[inline]
     pub const fn new(
         key: [u8; KEY_LEN],
-        location: [u8; KEY_LEN],
+        location: [u8; HASH_LEN],
         node_count: u64,
         count: u32,
     ) -> Self {
@@ -35,7 +33,7 @@ impl TreeRef {
     }
 }
 
-impl Ord for TreeRef {
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Ord for TreeRef<KEY_LEN, HASH_LEN> {
     #[inline]
     fn cmp(&self, other_ref: &Self) -> Ordering {
         self.key.cmp(&other_ref.key)
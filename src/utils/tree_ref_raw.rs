@@ -3,15 +3,17 @@ use std::ops::Deref;
 
 /// This is a wrapper around a raw pointer of a `TreeRef`.
 /// Used primarily to mark it as Send and Sync.
-pub struct TreeRefRaw(pub *mut TreeRef);
+pub struct TreeRefRaw<const KEY_LEN: usize = 32, const HASH_LEN: usize = 32>(
+    pub *mut TreeRef<KEY_LEN, HASH_LEN>,
+);
 
-impl Deref for TreeRefRaw {
-    type Target = *mut TreeRef;
+impl<const KEY_LEN: usize, const HASH_LEN: usize> Deref for TreeRefRaw<KEY_LEN, HASH_LEN> {
+    type Target = *mut TreeRef<KEY_LEN, HASH_LEN>;
     #[inline]
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-unsafe impl Send for TreeRefRaw {}
-unsafe impl Sync for TreeRefRaw {}
+unsafe impl<const KEY_LEN: usize, const HASH_LEN: usize> Send for TreeRefRaw<KEY_LEN, HASH_LEN> {}
+unsafe impl<const KEY_LEN: usize, const HASH_LEN: usize> Sync for TreeRefRaw<KEY_LEN, HASH_LEN> {}
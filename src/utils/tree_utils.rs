@@ -4,11 +4,11 @@ use std::collections::HashMap;
 #[cfg(feature = "use_hashbrown")]
 use hashbrown::HashMap;
 
-use crate::constants::{KEY_LEN, KEY_LEN_BITS, MULTIPLY_DE_BRUIJN_BIT_POSITION};
+use crate::constants::MULTIPLY_DE_BRUIJN_BIT_POSITION;
 
 /// This function checks if the given key should go down the zero branch at the given bit.
 #[inline]
-pub const fn choose_zero(key: &[u8; KEY_LEN], bit: u8) -> bool {
+pub const fn choose_zero<const KEY_LEN: usize>(key: &[u8; KEY_LEN], bit: u8) -> bool {
     let index = (bit >> 3) as usize;
     let shift = bit % 8;
     let extracted_bit = (key[index] >> (7 - shift)) & 1;
@@ -18,7 +18,7 @@ pub const fn choose_zero(key: &[u8; KEY_LEN], bit: u8) -> bool {
 /// This function splits the list of sorted pairs into two lists, one for going down the zero branch,
 /// and the other for going down the one branch.
 #[inline]
-pub fn split_pairs<'a>(
+pub fn split_pairs<'a, const KEY_LEN: usize>(
     sorted_pairs: &'a [&'a [u8; KEY_LEN]],
     bit: u8,
 ) -> (&'a [&'a [u8; KEY_LEN]], &'a [&'a [u8; KEY_LEN]]) {
@@ -51,7 +51,7 @@ pub fn split_pairs<'a>(
 
 /// This function checks to see if a section of keys need to go down this branch.
 #[inline]
-pub fn check_descendants<'a>(
+pub fn check_descendants<'a, const KEY_LEN: usize>(
     keys: &'a [&'a [u8; KEY_LEN]],
     branch_split_index: u8,
     branch_key: &[u8; KEY_LEN],
@@ -93,7 +93,10 @@ pub fn check_descendants<'a>(
 /// This function calculates the minimum index upon which the given keys diverge.  It also includes
 /// the given branch key when calculating the minimum split index.
 #[inline]
-pub fn calc_min_split_index(keys: &[&[u8; KEY_LEN]], branch_key: &[u8; KEY_LEN]) -> u8 {
+pub fn calc_min_split_index<const KEY_LEN: usize>(
+    keys: &[&[u8; KEY_LEN]],
+    branch_key: &[u8; KEY_LEN],
+) -> u8 {
     assert!(!keys.is_empty());
     let mut min_key = *keys.iter().min().expect("Failed to get min key");
     let mut max_key = *keys.iter().max().expect("Failed to get max key");
@@ -104,7 +107,7 @@ pub fn calc_min_split_index(keys: &[&[u8; KEY_LEN]], branch_key: &[u8; KEY_LEN])
         max_key = branch_key;
     }
 
-    let mut split_bit = KEY_LEN_BITS;
+    let mut split_bit = (KEY_LEN * 8 - 1) as u8;
     for (i, &min_key_byte) in min_key.iter().enumerate() {
         if min_key_byte == max_key[i] {
             continue;
@@ -116,10 +119,81 @@ pub fn calc_min_split_index(keys: &[&[u8; KEY_LEN]], branch_key: &[u8; KEY_LEN])
     split_bit
 }
 
+/// This function finds the index of the first bit on which the two keys differ.  Returns `None`
+/// if the keys are the same.
+#[inline]
+pub fn find_split_index<const KEY_LEN: usize>(
+    left_key: &[u8; KEY_LEN],
+    right_key: &[u8; KEY_LEN],
+) -> Option<u8> {
+    for (i, &left_byte) in left_key.iter().enumerate() {
+        if left_byte == right_key[i] {
+            continue;
+        }
+        let xor_key = left_byte ^ right_key[i];
+        return Some((i << 3) as u8 + (7 - fast_log_2(xor_key)));
+    }
+    None
+}
+
+/// This function checks if the given key shares all bits before `branch_split_index` with the
+/// branch key, and so would be found under that branch.
+#[inline]
+pub fn is_descendant<const KEY_LEN: usize>(
+    key: &[u8; KEY_LEN],
+    branch_split_index: u8,
+    branch_key: &[u8; KEY_LEN],
+) -> bool {
+    find_split_index(key, branch_key).map_or(true, |index| index >= branch_split_index)
+}
+
+/// This function calculates the smallest and largest keys that share the first `prefix_bits` bits
+/// of `prefix`.
+#[inline]
+pub fn calc_prefix_bounds<const KEY_LEN: usize>(
+    prefix: &[u8; KEY_LEN],
+    prefix_bits: usize,
+) -> ([u8; KEY_LEN], [u8; KEY_LEN]) {
+    let mut min_key = [0x00; KEY_LEN];
+    let mut max_key = [0xFF; KEY_LEN];
+
+    let full_bytes = (prefix_bits >> 3).min(KEY_LEN);
+    min_key[..full_bytes].copy_from_slice(&prefix[..full_bytes]);
+    max_key[..full_bytes].copy_from_slice(&prefix[..full_bytes]);
+
+    let remaining_bits = prefix_bits % 8;
+    if full_bytes < KEY_LEN && remaining_bits > 0 {
+        let prefix_mask = !(0xFF >> remaining_bits);
+        min_key[full_bytes] = prefix[full_bytes] & prefix_mask;
+        max_key[full_bytes] = prefix[full_bytes] | !prefix_mask;
+    }
+    (min_key, max_key)
+}
+
+/// This function calculates the smallest and largest keys that could be found down the zero or
+/// one side of a branch with the given split index and key.
+#[inline]
+pub fn calc_child_bounds<const KEY_LEN: usize>(
+    branch_key: &[u8; KEY_LEN],
+    branch_split_index: u8,
+    zero: bool,
+) -> ([u8; KEY_LEN], [u8; KEY_LEN]) {
+    let split_byte = (branch_split_index >> 3) as usize;
+    let split_bit = 0x80 >> (branch_split_index % 8);
+
+    let mut child_prefix = *branch_key;
+    if zero {
+        child_prefix[split_byte] &= !split_bit;
+    } else {
+        child_prefix[split_byte] |= split_bit;
+    }
+    calc_prefix_bounds(&child_prefix, branch_split_index as usize + 1)
+}
+
 /// This function initializes a hashmap to have entries for each provided key.  Values are initialized
 /// to `None`.
 #[inline]
-pub fn generate_leaf_map<'a, ValueType>(
+pub fn generate_leaf_map<'a, ValueType, const KEY_LEN: usize>(
     keys: &[&'a [u8; KEY_LEN]],
 ) -> HashMap<&'a [u8; KEY_LEN], Option<ValueType>> {
     let mut leaf_map = HashMap::new();
@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+/// A set of changes to apply to a tree in a single pass.  Later changes to a key replace earlier
+/// changes to the same key.
+#[derive(Clone, Debug)]
+pub struct WriteBatch<ValueType, const KEY_LEN: usize = 32> {
+    /// The new value for each changed key, or `None` if the key is deleted.
+    changes: BTreeMap<[u8; KEY_LEN], Option<ValueType>>,
+}
+
+impl<ValueType, const KEY_LEN: usize> WriteBatch<ValueType, KEY_LEN> {
+    /// Creates a new, empty `WriteBatch`.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            changes: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the value for `key`, inserting it or updating an existing value.
+    #[inline]
+    pub fn put(&mut self, key: [u8; KEY_LEN], value: ValueType) {
+        self.changes.insert(key, Some(value));
+    }
+
+    /// Deletes `key` from the tree.
+    #[inline]
+    pub fn delete(&mut self, key: [u8; KEY_LEN]) {
+        self.changes.insert(key, None);
+    }
+
+    /// Gets the number of keys changed by the batch.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Checks if the batch has no changes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Gets the changed keys in ascending order along with their new values, or `None` for deleted
+    /// keys.
+    #[inline]
+    pub fn changes(&self) -> &BTreeMap<[u8; KEY_LEN], Option<ValueType>> {
+        &self.changes
+    }
+}
+
+impl<ValueType, const KEY_LEN: usize> Default for WriteBatch<ValueType, KEY_LEN> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
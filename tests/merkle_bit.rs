@@ -1,18 +1,43 @@
 #[cfg(test)]
 pub mod integration_tests {
+    use std::collections::BTreeMap;
     #[cfg(any(feature = "use_serialization"))]
     use std::error::Error;
     use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
 
     use rand::rngs::StdRng;
     use rand::{Rng, SeedableRng};
 
-    use starling::constants::KEY_LEN;
+    use starling::constants::{HASH_LEN, KEY_LEN};
     #[cfg(not(any(feature = "use_rocksdb")))]
     use starling::hash_tree::HashTree;
-    use starling::merkle_bit::BinaryMerkleTreeResult;
+    use starling::hashed_key_tree::HashedKeyTree;
+    use starling::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT};
+    use starling::proof::absence_proof::{AbsenceProof, PathEnd};
+    use starling::proof::inclusion_proof::InclusionProof;
+    use starling::proof::multi_proof::{MultiProof, MultiProofNode};
+    use starling::proof::proof_branch::ProofBranch;
+    use starling::proof::range_proof::RangeProof;
+    use starling::proof::verify::{
+        verify_absence_proof, verify_multiproof, verify_multiproof_with_scheme, verify_proof,
+        verify_proof_with_scheme, verify_range_proof, verify_range_proof_with_scheme,
+    };
     #[cfg(feature = "use_rocksdb")]
     use starling::rocks_tree::RocksTree;
+    use starling::traits::{Database, Decode, Encode, Exception};
+    use starling::tree::tree_branch::TreeBranch;
+    use starling::tree::tree_data::TreeData;
+    use starling::tree::tree_leaf::TreeLeaf;
+    use starling::tree::tree_node::TreeNode;
+    #[cfg(feature = "use_rocksdb")]
+    use starling::tree_db::rocksdb::RocksDB;
+    use starling::tree_db::HashTreeDB;
+    use starling::tree_hasher::tagged_scheme::TaggedScheme;
+    use starling::tree_hasher::TreeHasher;
+    use starling::utils::changeset::Changeset;
+    use starling::utils::write_batch::WriteBatch;
 
     #[cfg(feature = "use_rocksdb")]
     type Tree = RocksTree<Vec<u8>>;
@@ -20,6 +45,220 @@ pub mod integration_tests {
     #[cfg(not(any(feature = "use_rocksdb")))]
     type Tree = HashTree<Vec<u8>>;
 
+    #[cfg(feature = "use_rocksdb")]
+    type KeyedTree = HashedKeyTree<RocksDB, Vec<u8>>;
+
+    #[cfg(not(any(feature = "use_rocksdb")))]
+    type KeyedTree = HashedKeyTree<HashTreeDB, Vec<u8>>;
+
+    type CountingTree =
+        MerkleBIT<CountingDB, TreeBranch, TreeLeaf, TreeData, TreeNode, TreeHasher, Vec<u8>>;
+
+    /// A database that counts the number of reads and batch writes made to it.  While
+    /// `fail_removals` is set, each removal is queued and then reported as failed.
+    struct CountingDB {
+        db: HashTreeDB,
+        reads: Arc<AtomicUsize>,
+        batch_writes: Arc<AtomicUsize>,
+        fail_removals: Arc<AtomicBool>,
+    }
+
+    impl CountingDB {
+        fn new(reads: Arc<AtomicUsize>, batch_writes: Arc<AtomicUsize>) -> Result<Self, Exception> {
+            Ok(Self {
+                db: HashTreeDB::open(&PathBuf::new())?,
+                reads,
+                batch_writes,
+                fail_removals: Arc::new(AtomicBool::new(false)),
+            })
+        }
+
+        fn with_failing_removals(mut self, fail_removals: Arc<AtomicBool>) -> Self {
+            self.fail_removals = fail_removals;
+            self
+        }
+    }
+
+    impl Database<HASH_LEN> for CountingDB {
+        type NodeType = TreeNode;
+        type EntryType = ([u8; HASH_LEN], Vec<u8>);
+
+        fn open(_path: &PathBuf) -> Result<Self, Exception> {
+            Self::new(Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)))
+        }
+
+        fn get_node(&self, key: &[u8; HASH_LEN]) -> Result<Option<Self::NodeType>, Exception> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            self.db.get_node(key)
+        }
+
+        fn locations(&self) -> Result<Vec<[u8; HASH_LEN]>, Exception> {
+            self.db.locations()
+        }
+
+        fn insert(&mut self, key: [u8; HASH_LEN], node: Self::NodeType) -> Result<(), Exception> {
+            self.db.insert(key, node)
+        }
+
+        fn remove(&mut self, key: &[u8; HASH_LEN]) -> Result<(), Exception> {
+            self.db.remove(key)?;
+            if self.fail_removals.load(Ordering::SeqCst) {
+                return Err(Exception::new("Injected failure"));
+            }
+            Ok(())
+        }
+
+        fn batch_write(&mut self) -> Result<(), Exception> {
+            self.batch_writes.fetch_add(1, Ordering::SeqCst);
+            self.db.batch_write()
+        }
+
+        fn rollback(&mut self) -> Result<(), Exception> {
+            self.db.rollback()
+        }
+    }
+
+    type FailingTree =
+        MerkleBIT<FailingDB, TreeBranch, TreeLeaf, TreeData, TreeNode, TreeHasher, Vec<u8>>;
+
+    /// The nodes that have been committed to a `FailingDB`.
+    type Store = Arc<Mutex<BTreeMap<[u8; HASH_LEN], TreeNode>>>;
+
+    /// A database that queues changes until `batch_write` and then commits them to a shared store
+    /// all at once.  Once it has been written to a given number of times, every further write fails,
+    /// as if the process had crashed.  The number of writes left is shared, so a test can change it
+    /// between calls to the same tree.
+    struct FailingDB {
+        committed: Store,
+        pending: BTreeMap<[u8; HASH_LEN], Option<TreeNode>>,
+        writes_left: Arc<AtomicUsize>,
+    }
+
+    impl FailingDB {
+        fn new(committed: Store, writes_left: usize) -> Self {
+            Self::with_writes_left(committed, Arc::new(AtomicUsize::new(writes_left)))
+        }
+
+        fn with_writes_left(committed: Store, writes_left: Arc<AtomicUsize>) -> Self {
+            Self {
+                committed,
+                pending: BTreeMap::new(),
+                writes_left,
+            }
+        }
+
+        fn count_write(&mut self) -> Result<(), Exception> {
+            let writes_left = self.writes_left.load(Ordering::SeqCst);
+            if writes_left == 0 {
+                return Err(Exception::new("Injected failure"));
+            }
+            self.writes_left.store(writes_left - 1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    impl Database<HASH_LEN> for FailingDB {
+        type NodeType = TreeNode;
+        type EntryType = ([u8; HASH_LEN], Vec<u8>);
+
+        fn open(_path: &PathBuf) -> Result<Self, Exception> {
+            Ok(Self::new(Arc::new(Mutex::new(BTreeMap::new())), usize::MAX))
+        }
+
+        fn get_node(&self, key: &[u8; HASH_LEN]) -> Result<Option<Self::NodeType>, Exception> {
+            if let Some(node) = self.pending.get(key) {
+                return Ok(node.clone());
+            }
+            let committed = self
+                .committed
+                .lock()
+                .map_err(|_| Exception::new("Store is poisoned"))?;
+            Ok(committed.get(key).cloned())
+        }
+
+        fn locations(&self) -> Result<Vec<[u8; HASH_LEN]>, Exception> {
+            let committed = self
+                .committed
+                .lock()
+                .map_err(|_| Exception::new("Store is poisoned"))?;
+            Ok(committed.keys().copied().collect())
+        }
+
+        fn insert(&mut self, key: [u8; HASH_LEN], node: Self::NodeType) -> Result<(), Exception> {
+            self.count_write()?;
+            self.pending.insert(key, Some(node));
+            Ok(())
+        }
+
+        fn remove(&mut self, key: &[u8; HASH_LEN]) -> Result<(), Exception> {
+            self.count_write()?;
+            self.pending.insert(*key, None);
+            Ok(())
+        }
+
+        fn batch_write(&mut self) -> Result<(), Exception> {
+            self.count_write()?;
+            let mut committed = self
+                .committed
+                .lock()
+                .map_err(|_| Exception::new("Store is poisoned"))?;
+            for (key, node) in std::mem::take(&mut self.pending) {
+                match node {
+                    Some(n) => committed.insert(key, n),
+                    None => committed.remove(&key),
+                };
+            }
+            Ok(())
+        }
+
+        fn rollback(&mut self) -> Result<(), Exception> {
+            self.pending.clear();
+            Ok(())
+        }
+    }
+
+    /// Gets a copy of the nodes committed to `store`.
+    fn snapshot(store: &Store) -> BTreeMap<[u8; HASH_LEN], TreeNode> {
+        store.lock().expect("Store is poisoned").clone()
+    }
+
+    /// Runs `mutate` on `bmt`, failing it after every possible number of writes until it succeeds.
+    /// Every failed call must leave `store` unchanged, and the call that succeeds must leave it
+    /// exactly as the same mutation does on a fresh tree, so nothing staged by a failed call is
+    /// written with a later one.
+    fn fail_until_done<T, F>(
+        bmt: &mut FailingTree,
+        store: &Store,
+        writes_left: &AtomicUsize,
+        mut mutate: F,
+    ) -> BinaryMerkleTreeResult<T>
+    where
+        T: PartialEq + std::fmt::Debug,
+        F: FnMut(&mut FailingTree) -> BinaryMerkleTreeResult<T>,
+    {
+        let before = snapshot(store);
+        let expected_store: Store = Arc::new(Mutex::new(before.clone()));
+        let mut fresh =
+            FailingTree::from_db(FailingDB::new(Arc::clone(&expected_store), usize::MAX), 160)?;
+        let expected = mutate(&mut fresh)?;
+        let expected_store = snapshot(&expected_store);
+
+        let mut limit = 0;
+        let result = loop {
+            writes_left.store(limit, Ordering::SeqCst);
+            match mutate(bmt) {
+                Ok(r) => break r,
+                Err(_) => assert_eq!(snapshot(store), before),
+            }
+            limit += 1;
+        };
+        writes_left.store(usize::MAX, Ordering::SeqCst);
+        assert!(limit > 0);
+        assert_eq!(result, expected);
+        assert_eq!(snapshot(store), expected_store);
+        Ok(result)
+    }
+
     #[test]
     #[cfg(feature = "use_serialization")]
     fn it_works_with_a_real_database() -> BinaryMerkleTreeResult<()> {
@@ -1210,37 +1449,2952 @@ pub mod integration_tests {
         Ok(())
     }
 
-    fn generate_path(seed: [u8; KEY_LEN]) -> PathBuf {
+    #[test]
+    fn it_gets_a_proof_for_an_item_in_a_simple_tree() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x38u8; KEY_LEN];
+        let path = generate_path(seed);
+        let key = [0xAAu8; KEY_LEN];
+        let value = vec![0xFFu8];
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut [&key], &mut [&value])?;
+        let proof = bmt.get_proof(&root, &key)?.expect("Failed to get proof");
+        assert!(proof.branches.is_empty());
+        assert_eq!(proof.key, key);
+        assert_eq!(proof.value, value);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_gets_proofs_for_items_in_a_tree() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x39u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let num_leaves = 16;
+        let mut keys = Vec::with_capacity(num_leaves);
+        let mut values: Vec<Vec<u8>> = Vec::with_capacity(num_leaves);
+        for i in 0..num_leaves {
+            keys.push([(i as u8) << 4; KEY_LEN]);
+            values.push(vec![i as u8; KEY_LEN]);
+        }
+
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        for (key, value) in keys.iter().zip(values.iter()) {
+            let proof = bmt.get_proof(&root, key)?.expect("Failed to get proof");
+            assert_eq!(proof.branches.len(), 4);
+            assert_eq!(&proof.key, key);
+            assert_eq!(&proof.value, value);
+            for (i, branch) in proof.branches.iter().enumerate() {
+                assert_eq!(branch.split_index, i as u8);
+                assert_eq!(branch.count, (num_leaves >> i) as u64);
+            }
+        }
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_does_not_get_a_proof_for_a_nonexistent_item() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x3Au8; KEY_LEN];
+        let path = generate_path(seed);
+        let key_values = vec![[0x00u8; KEY_LEN], [0x10u8; KEY_LEN], [0x20u8; KEY_LEN]];
+        let mut keys = key_values.iter().collect::<Vec<_>>();
+        let values = vec![vec![0x00u8], vec![0x01u8], vec![0x02u8]];
+        let mut data = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &mut data)?;
+
+        let nonexistent_key = [0x11u8; KEY_LEN];
+        assert_eq!(bmt.get_proof(&root, &nonexistent_key)?, None);
+        let nonexistent_root = [0x01u8; KEY_LEN];
+        assert_eq!(bmt.get_proof(&nonexistent_root, &key_values[0])?, None);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_verifies_proofs_for_items_in_a_tree() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x3Bu8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x5Cu8; KEY_LEN];
         let mut rng: StdRng = SeedableRng::from_seed(seed);
-        let suffix = rng.gen_range(1000, 10000);
-        let path_string = format!("Test_DB_{}", suffix);
-        PathBuf::from(path_string)
+        let (keys, values) = prepare_inserts(64, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        for (key, value) in keys.iter().zip(values.iter()) {
+            let proof = bmt.get_proof(&root, key)?.expect("Failed to get proof");
+            assert!(verify_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+                &root, key, value, &proof
+            )?);
+        }
+        tear_down(&path);
+        Ok(())
     }
 
-    fn tear_down(_path: &PathBuf) {
-        #[cfg(feature = "use_rocksdb")]
-        use std::fs::remove_dir_all;
+    #[test]
+    fn it_rejects_invalid_proofs() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x3Cu8; KEY_LEN];
+        let path = generate_path(seed);
 
-        #[cfg(feature = "use_rocksdb")]
-        remove_dir_all(&_path).unwrap();
+        let seed = [0x5Du8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(16, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+        let proof = bmt
+            .get_proof(&root, &keys[0])?
+            .expect("Failed to get proof");
+
+        let wrong_value = vec![0x00u8];
+        assert!(!verify_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root,
+            &keys[0],
+            &wrong_value,
+            &proof
+        )?);
+        assert!(!verify_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root, &keys[1], &values[0], &proof
+        )?);
+        let wrong_root = [0x00u8; KEY_LEN];
+        assert!(!verify_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &wrong_root,
+            &keys[0],
+            &values[0],
+            &proof
+        )?);
+
+        let mut tampered_proof = proof.clone();
+        tampered_proof.branches[0].sibling[0] ^= 0x01;
+        assert!(!verify_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root,
+            &keys[0],
+            &values[0],
+            &tampered_proof
+        )?);
+        tear_down(&path);
+        Ok(())
     }
 
-    fn prepare_inserts(num_entries: usize, rng: &mut StdRng) -> (Vec<[u8; KEY_LEN]>, Vec<Vec<u8>>) {
-        let mut keys = Vec::with_capacity(num_entries);
-        let mut data = Vec::with_capacity(num_entries);
-        for _ in 0..num_entries {
-            let mut key_value = [0u8; KEY_LEN];
-            rng.fill(&mut key_value);
-            keys.push(key_value);
+    #[test]
+    fn it_proves_an_item_is_absent_at_a_leaf() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x3Du8; KEY_LEN];
+        let path = generate_path(seed);
+        let key_values = vec![[0x00u8; KEY_LEN], [0x10u8; KEY_LEN], [0x20u8; KEY_LEN]];
+        let mut keys = key_values.iter().collect::<Vec<_>>();
+        let values = vec![vec![0x00u8], vec![0x01u8], vec![0x02u8]];
+        let mut data = values.iter().collect::<Vec<_>>();
 
-            let mut data_value = [0u8; KEY_LEN];
-            rng.fill(data_value.as_mut());
-            data.push(data_value.to_vec());
+        let scheme = TaggedScheme::<TreeHasher>::committed();
+        let mut bmt = open_with_scheme(&path, scheme.clone())?;
+        let root = bmt.insert(None, &mut keys, &mut data)?;
+
+        let absent_key = [0x11u8; KEY_LEN];
+        let proof = bmt
+            .get_absence_proof(&root, &absent_key)?
+            .expect("Failed to get absence proof");
+        assert_eq!(proof.branches.len(), 2);
+        match proof.end {
+            PathEnd::Leaf { key, .. } => assert_eq!(key, key_values[1]),
+            PathEnd::Branch { .. } => panic!("Expected the path to end at a leaf"),
+        }
+        assert!(verify_absence_proof(&scheme, &root, &absent_key, &proof)?);
+        assert!(!verify_absence_proof(
+            &scheme,
+            &root,
+            &key_values[1],
+            &proof
+        )?);
+
+        assert_eq!(bmt.get_absence_proof(&root, &key_values[1])?, None);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_proves_an_item_is_absent_at_a_branch() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x3Eu8; KEY_LEN];
+        let path = generate_path(seed);
+        let key_values = vec![[0x00u8; KEY_LEN], [0x01u8; KEY_LEN]];
+        let mut keys = key_values.iter().collect::<Vec<_>>();
+        let values = vec![vec![0x00u8], vec![0x01u8]];
+        let mut data = values.iter().collect::<Vec<_>>();
+
+        let scheme = TaggedScheme::<TreeHasher>::committed();
+        let mut bmt = open_with_scheme(&path, scheme.clone())?;
+        let root = bmt.insert(None, &mut keys, &mut data)?;
+
+        let absent_key = [0x80u8; KEY_LEN];
+        let proof = bmt
+            .get_absence_proof(&root, &absent_key)?
+            .expect("Failed to get absence proof");
+        assert!(proof.branches.is_empty());
+        match proof.end {
+            PathEnd::Branch { split_index, .. } => assert_eq!(split_index, 7),
+            PathEnd::Leaf { .. } => panic!("Expected the path to end at a branch"),
+        }
+        assert!(verify_absence_proof(&scheme, &root, &absent_key, &proof)?);
+        assert!(!verify_absence_proof(
+            &scheme,
+            &root,
+            &key_values[0],
+            &proof
+        )?);
+        let wrong_root = [0x00u8; KEY_LEN];
+        assert!(!verify_absence_proof(
+            &scheme,
+            &wrong_root,
+            &absent_key,
+            &proof
+        )?);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_verifies_absence_proofs_for_random_keys() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x3Fu8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x5Eu8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(64, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let scheme = TaggedScheme::<TreeHasher>::committed();
+        let mut bmt = open_with_scheme(&path, scheme.clone())?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let (absent_keys, _) = prepare_inserts(64, &mut rng);
+        for key in absent_keys.iter() {
+            let proof = bmt
+                .get_absence_proof(&root, key)?
+                .expect("Failed to get absence proof");
+            assert!(verify_absence_proof(&scheme, &root, key, &proof)?);
+        }
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_absence_proofs_with_a_moved_branch() -> BinaryMerkleTreeResult<()> {
+        use starling::traits::HashingScheme;
+
+        let key_values = vec![[0x00u8; KEY_LEN], [0x80u8; KEY_LEN]];
+        let mut keys = key_values.iter().collect::<Vec<_>>();
+        let values = vec![vec![0x00u8], vec![0x01u8]];
+        let mut data = values.iter().collect::<Vec<_>>();
+
+        // Claims the root splits at index 4 under a key that differs from the present key there
+        let forge = |scheme: &TaggedScheme<TreeHasher>| -> BinaryMerkleTreeResult<AbsenceProof> {
+            let mut children = vec![];
+            for (key, value) in key_values.iter().zip(values.iter()) {
+                let data = scheme.data_location(key, &value.encode()?);
+                children.push(scheme.leaf_location(key, &data));
+            }
+            let end = PathEnd::Branch {
+                zero: children[0],
+                one: children[1],
+                split_index: 4,
+                key: [0x00u8; KEY_LEN],
+                count: 2,
+            };
+            Ok(AbsenceProof::new(vec![], end))
+        };
+
+        // The default scheme does not bind the split index, so the forged branch matches the root
+        let path = generate_path([0x7Au8; KEY_LEN]);
+        let scheme = TaggedScheme::<TreeHasher>::default();
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &mut data)?;
+        let forged = forge(&scheme)?;
+        if let PathEnd::Branch {
+            zero, one, count, ..
+        } = forged.end
+        {
+            assert_eq!(
+                scheme.branch_location(&zero, &one, 0, &key_values[0], count),
+                root
+            );
+        }
+        assert!(verify_absence_proof(&scheme, &root, &key_values[1], &forged).is_err());
+
+        // Nor does the tree build absence proofs that could never be verified
+        let absent_key = [0x40u8; KEY_LEN];
+        assert!(bmt.get_absence_proof(&root, &absent_key).is_err());
+        tear_down(&path);
+
+        let path = generate_path([0x7Bu8; KEY_LEN]);
+        let scheme = TaggedScheme::<TreeHasher>::committed();
+        let mut bmt = open_with_scheme(&path, scheme.clone())?;
+        let root = bmt.insert(None, &mut keys, &mut data)?;
+        let forged = forge(&scheme)?;
+        assert!(!verify_absence_proof(
+            &scheme,
+            &root,
+            &key_values[1],
+            &forged
+        )?);
+
+        let absent_key = [0x40u8; KEY_LEN];
+        let proof = bmt
+            .get_absence_proof(&root, &absent_key)?
+            .expect("Failed to get absence proof");
+        assert!(verify_absence_proof(&scheme, &root, &absent_key, &proof)?);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_gets_a_multiproof_sharing_path_nodes() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x40u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut key_values = vec![];
+        let mut values = vec![];
+        for i in 0..16 {
+            key_values.push([(i << 4) as u8; KEY_LEN]);
+            values.push(vec![i as u8]);
+        }
+        let mut keys = key_values.iter().collect::<Vec<_>>();
+        let mut data = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &mut data)?;
+
+        let mut proof_keys = vec![&key_values[0], &key_values[1]];
+        let proof = bmt
+            .get_multiproof(&root, &mut proof_keys)?
+            .expect("Failed to get multiproof");
+
+        // Both keys share the top three branches, so only one sibling is needed at each of them
+        let branch_count = proof
+            .nodes
+            .iter()
+            .filter(|node| match node {
+                MultiProofNode::Branch { .. } => true,
+                _ => false,
+            })
+            .count();
+        let hash_count = proof
+            .nodes
+            .iter()
+            .filter(|node| match node {
+                MultiProofNode::Hash(_) => true,
+                _ => false,
+            })
+            .count();
+        assert_eq!(branch_count, 4);
+        assert_eq!(hash_count, 3);
+        assert_eq!(proof.nodes.len(), 9);
+
+        let claims = vec![
+            (&key_values[0], Some(&values[0])),
+            (&key_values[1], Some(&values[1])),
+        ];
+        assert!(verify_multiproof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root, &claims, &proof
+        )?);
+
+        let mut empty_keys: Vec<&[u8; KEY_LEN]> = vec![];
+        let proof = bmt
+            .get_multiproof(&root, &mut empty_keys)?
+            .expect("Failed to get multiproof");
+        assert_eq!(proof.nodes, vec![MultiProofNode::Hash(root)]);
+        let no_claims: Vec<(&[u8; KEY_LEN], Option<&Vec<u8>>)> = vec![];
+        assert!(verify_multiproof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root, &no_claims, &proof
+        )?);
+
+        let missing_root = [0xFFu8; KEY_LEN];
+        assert_eq!(bmt.get_multiproof(&missing_root, &mut proof_keys)?, None);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_verifies_multiproofs_for_present_and_absent_keys() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x41u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x5Fu8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(256, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let scheme = TaggedScheme::<TreeHasher>::committed();
+        let mut bmt = open_with_scheme(&path, scheme.clone())?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let (absent_keys, _) = prepare_inserts(32, &mut rng);
+        let mut proof_keys = keys.iter().step_by(4).collect::<Vec<_>>();
+        proof_keys.extend(absent_keys.iter());
+        let proof = bmt
+            .get_multiproof(&root, &mut proof_keys)?
+            .expect("Failed to get multiproof");
+
+        let mut claims = keys
+            .iter()
+            .zip(values.iter())
+            .step_by(4)
+            .map(|(key, value)| (key, Some(value)))
+            .collect::<Vec<_>>();
+        claims.extend(absent_keys.iter().map(|key| (key, None)));
+        assert!(verify_multiproof_with_scheme(
+            &scheme, &root, &claims, &proof
+        )?);
+
+        let mut individual_size = 0;
+        for key in keys.iter().step_by(4) {
+            let single = bmt.get_proof(&root, key)?.expect("Failed to get proof");
+            individual_size += single.branches.len() + 1;
+        }
+        for key in absent_keys.iter() {
+            let single = bmt
+                .get_absence_proof(&root, key)?
+                .expect("Failed to get absence proof");
+            individual_size += single.branches.len() + 1;
+        }
+        assert!(proof.nodes.len() < individual_size);
+
+        // A wrong value for a present key
+        let wrong_value = vec![0xFFu8; 32];
+        let mut wrong_claims = claims.clone();
+        wrong_claims[0].1 = Some(&wrong_value);
+        assert!(!verify_multiproof_with_scheme(
+            &scheme,
+            &root,
+            &wrong_claims,
+            &proof
+        )?);
+
+        // A present key claimed as absent
+        let mut wrong_claims = claims.clone();
+        wrong_claims[0].1 = None;
+        assert!(!verify_multiproof_with_scheme(
+            &scheme,
+            &root,
+            &wrong_claims,
+            &proof
+        )?);
+
+        // An absent key claimed as present
+        let mut wrong_claims = claims.clone();
+        let last = wrong_claims.len() - 1;
+        wrong_claims[last].1 = Some(&values[0]);
+        assert!(!verify_multiproof_with_scheme(
+            &scheme,
+            &root,
+            &wrong_claims,
+            &proof
+        )?);
+
+        // A key the proof was not generated for
+        let mut wrong_claims = claims.clone();
+        wrong_claims.push((&keys[1], Some(&values[1])));
+        assert!(!verify_multiproof_with_scheme(
+            &scheme,
+            &root,
+            &wrong_claims,
+            &proof
+        )?);
+
+        let wrong_root = [0x00u8; KEY_LEN];
+        assert!(!verify_multiproof_with_scheme(
+            &scheme,
+            &wrong_root,
+            &claims,
+            &proof
+        )?);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_multiproofs_with_a_moved_branch() -> BinaryMerkleTreeResult<()> {
+        use starling::traits::HashingScheme;
+
+        let key_values = vec![[0x00u8; KEY_LEN], [0x80u8; KEY_LEN]];
+        let mut keys = key_values.iter().collect::<Vec<_>>();
+        let values = vec![vec![0x00u8], vec![0x01u8]];
+        let mut data = values.iter().collect::<Vec<_>>();
+
+        // Claims the root splits at index 4 under a key that differs from the present key there
+        let forge = |scheme: &TaggedScheme<TreeHasher>| -> BinaryMerkleTreeResult<MultiProof> {
+            let mut nodes = vec![MultiProofNode::Branch {
+                split_index: 4,
+                key: [0x00u8; KEY_LEN],
+                count: 2,
+            }];
+            for (key, value) in key_values.iter().zip(values.iter()) {
+                let data = scheme.data_location(key, &value.encode()?);
+                nodes.push(MultiProofNode::Hash(scheme.leaf_location(key, &data)));
+            }
+            Ok(MultiProof::new(nodes))
+        };
+        let claims: Vec<(&[u8; KEY_LEN], Option<&Vec<u8>>)> = vec![(&key_values[1], None)];
+
+        // The default scheme does not bind the split index, so the forged branch matches the root
+        let path = generate_path([0x7Cu8; KEY_LEN]);
+        let scheme = TaggedScheme::<TreeHasher>::default();
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &mut data)?;
+        let forged = forge(&scheme)?;
+        assert!(
+            verify_multiproof::<TreeHasher, _, KEY_LEN, HASH_LEN>(&root, &claims, &forged).is_err()
+        );
+        assert!(verify_multiproof_with_scheme(&scheme, &root, &claims, &forged).is_err());
+
+        // Claims of presence are still accepted
+        let present_claims = vec![(&key_values[1], Some(&values[1]))];
+        let proof = bmt
+            .get_multiproof(&root, &mut vec![&key_values[1]])?
+            .expect("Failed to get multiproof");
+        assert!(verify_multiproof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root,
+            &present_claims,
+            &proof
+        )?);
+
+        // Proofs of absence are not built, as they could never be verified
+        let absent_key = [0x40u8; KEY_LEN];
+        assert!(bmt
+            .get_multiproof(&root, &mut [&key_values[1], &absent_key])
+            .is_err());
+        tear_down(&path);
+
+        let path = generate_path([0x7Du8; KEY_LEN]);
+        let scheme = TaggedScheme::<TreeHasher>::committed();
+        let mut bmt = open_with_scheme(&path, scheme.clone())?;
+        let root = bmt.insert(None, &mut keys, &mut data)?;
+        let forged = forge(&scheme)?;
+        assert!(!verify_multiproof_with_scheme(
+            &scheme, &root, &claims, &forged
+        )?);
+
+        let absent_key = [0x40u8; KEY_LEN];
+        let proof = bmt
+            .get_multiproof(&root, &mut [&key_values[1], &absent_key])?
+            .expect("Failed to get multiproof");
+        let mixed_claims = vec![(&key_values[1], Some(&values[1])), (&absent_key, None)];
+        assert!(verify_multiproof_with_scheme(
+            &scheme,
+            &root,
+            &mixed_claims,
+            &proof
+        )?);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_gets_a_range_proof_with_boundary_keys() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x42u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut key_values = vec![];
+        let mut values = vec![];
+        for i in 0..16 {
+            key_values.push([(i << 4) as u8; KEY_LEN]);
+            values.push(vec![i as u8]);
         }
+        let mut keys = key_values.iter().collect::<Vec<_>>();
+        let mut data = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &mut data)?;
+
+        let start = [0x31u8; KEY_LEN];
+        let end = [0x70u8; KEY_LEN];
+        let (entries, proof) = bmt
+            .get_range_proof(&root, &start, &end)?
+            .expect("Failed to get range proof");
+        let expected = (4..8)
+            .map(|i| (key_values[i], values[i].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, expected);
+
+        let leaf_keys = proof
+            .nodes
+            .iter()
+            .filter_map(|node| match node {
+                MultiProofNode::Leaf { key, .. } => Some(*key),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let expected_leaves = (3..9).map(|i| key_values[i]).collect::<Vec<_>>();
+        assert_eq!(leaf_keys, expected_leaves);
+
+        let entry_refs = entries.iter().map(|(k, v)| (k, v)).collect::<Vec<_>>();
+        assert!(verify_range_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root,
+            &start,
+            &end,
+            &entry_refs,
+            &proof
+        )?);
+
+        // Leaving out a key in the interval
+        assert!(!verify_range_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root,
+            &start,
+            &end,
+            &entry_refs[1..],
+            &proof
+        )?);
+
+        // Widening the interval past a boundary key
+        let wider_end = [0x80u8; KEY_LEN];
+        assert!(!verify_range_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root,
+            &start,
+            &wider_end,
+            &entry_refs,
+            &proof
+        )?);
+
+        // A wrong value for a key in the interval
+        let wrong_value = vec![0xFFu8];
+        let mut wrong_entries = entry_refs.clone();
+        wrong_entries[0].1 = &wrong_value;
+        assert!(!verify_range_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root,
+            &start,
+            &end,
+            &wrong_entries,
+            &proof
+        )?);
+
+        let missing_root = [0xFFu8; KEY_LEN];
+        assert_eq!(bmt.get_range_proof(&missing_root, &start, &end)?, None);
+        assert!(bmt.get_range_proof(&root, &end, &start).is_err());
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_verifies_range_proofs_for_random_intervals() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x43u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x60u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(256, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
 
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
         keys.sort();
 
-        (keys, data)
+        let (bounds, _) = prepare_inserts(32, &mut rng);
+        for pair in bounds.chunks(2) {
+            let (start, end) = if pair[0] < pair[1] {
+                (pair[0], pair[1])
+            } else {
+                (pair[1], pair[0])
+            };
+            let (entries, proof) = bmt
+                .get_range_proof(&root, &start, &end)?
+                .expect("Failed to get range proof");
+            let expected = keys
+                .iter()
+                .filter(|&key| *key >= start && *key <= end)
+                .collect::<Vec<_>>();
+            assert_eq!(entries.iter().map(|(k, _)| k).collect::<Vec<_>>(), expected);
+
+            let entry_refs = entries.iter().map(|(k, v)| (k, v)).collect::<Vec<_>>();
+            assert!(verify_range_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+                &root,
+                &start,
+                &end,
+                &entry_refs,
+                &proof
+            )?);
+        }
+
+        // Intervals before the first key and after the last key
+        let start = [0x00u8; KEY_LEN];
+        let end = [0x00u8; KEY_LEN];
+        let (entries, proof) = bmt
+            .get_range_proof(&root, &start, &end)?
+            .expect("Failed to get range proof");
+        assert!(entries.is_empty());
+        assert!(
+            verify_range_proof::<TreeHasher, Vec<u8>, KEY_LEN, HASH_LEN>(
+                &root,
+                &start,
+                &end,
+                &[],
+                &proof
+            )?
+        );
+        let start = [0xFFu8; KEY_LEN];
+        let end = [0xFFu8; KEY_LEN];
+        let (entries, proof) = bmt
+            .get_range_proof(&root, &start, &end)?
+            .expect("Failed to get range proof");
+        assert!(entries.is_empty());
+        assert!(
+            verify_range_proof::<TreeHasher, Vec<u8>, KEY_LEN, HASH_LEN>(
+                &root,
+                &start,
+                &end,
+                &[],
+                &proof
+            )?
+        );
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_deeply_nested_range_proofs() -> BinaryMerkleTreeResult<()> {
+        // Branches that do not move down the tree would otherwise be followed without limit
+        let mut nodes = vec![
+            MultiProofNode::Branch {
+                split_index: 0,
+                key: [0x00u8; KEY_LEN],
+                count: 2,
+            };
+            200_000
+        ];
+        nodes.push(MultiProofNode::Hash([0x00u8; HASH_LEN]));
+        let proof = RangeProof::decode(&RangeProof::new(nodes).encode()?)?;
+
+        let root = [0x00u8; HASH_LEN];
+        let start = [0x00u8; KEY_LEN];
+        let end = [0xFFu8; KEY_LEN];
+        assert!(
+            !verify_range_proof::<TreeHasher, Vec<u8>, KEY_LEN, HASH_LEN>(
+                &root,
+                &start,
+                &end,
+                &[],
+                &proof
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_the_proof_encoding_vectors() -> BinaryMerkleTreeResult<()> {
+        let vectors = include_str!("vectors/proof_encoding.txt")
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let mut parts = line.split_whitespace();
+                let name = parts.next().expect("Missing vector name");
+                let encoded = decode_hex(parts.next().expect("Missing vector encoding"));
+                (name, encoded)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vectors.len(), 5);
+
+        let branches = vec![
+            ProofBranch::new([0x11u8; KEY_LEN], 3, [0x22u8; KEY_LEN], 5),
+            ProofBranch::new([0x33u8; KEY_LEN], 9, [0x44u8; KEY_LEN], 2),
+        ];
+        let nodes = vec![
+            MultiProofNode::Branch {
+                split_index: 0,
+                key: [0x01u8; KEY_LEN],
+                count: 3,
+            },
+            MultiProofNode::Leaf {
+                key: [0x02u8; KEY_LEN],
+                data: [0x03u8; KEY_LEN],
+            },
+            MultiProofNode::Hash([0x04u8; KEY_LEN]),
+        ];
+
+        for (name, encoded) in vectors {
+            match name {
+                "inclusion" => {
+                    let proof = InclusionProof::new(
+                        branches.clone(),
+                        [0x55u8; KEY_LEN],
+                        [0x66u8; KEY_LEN],
+                        vec![0xDE, 0xAD, 0xBE, 0xEF],
+                    );
+                    assert_eq!(proof.encode()?, encoded);
+                    assert_eq!(InclusionProof::decode(&encoded)?, proof);
+                }
+                "absence_leaf" => {
+                    let end = PathEnd::Leaf {
+                        key: [0x77u8; KEY_LEN],
+                        data: [0x88u8; KEY_LEN],
+                    };
+                    let proof = AbsenceProof::new(branches[..1].to_vec(), end);
+                    assert_eq!(proof.encode()?, encoded);
+                    assert_eq!(AbsenceProof::decode(&encoded)?, proof);
+                }
+                "absence_branch" => {
+                    let end = PathEnd::Branch {
+                        zero: [0x99u8; KEY_LEN],
+                        one: [0xAAu8; KEY_LEN],
+                        split_index: 7,
+                        key: [0xBBu8; KEY_LEN],
+                        count: 3,
+                    };
+                    let proof = AbsenceProof::new(vec![], end);
+                    assert_eq!(proof.encode()?, encoded);
+                    assert_eq!(AbsenceProof::decode(&encoded)?, proof);
+                }
+                "multi" => {
+                    let proof = MultiProof::new(nodes.clone());
+                    assert_eq!(proof.encode()?, encoded);
+                    assert_eq!(MultiProof::decode(&encoded)?, proof);
+                }
+                "range" => {
+                    let proof = RangeProof::new(nodes.clone());
+                    assert_eq!(proof.encode()?, encoded);
+                    assert_eq!(RangeProof::decode(&encoded)?, proof);
+                }
+                _ => panic!("Unknown vector {}", name),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_decodes_version_1_proof_encodings() -> BinaryMerkleTreeResult<()> {
+        let read_vectors = |vectors: &'static str| {
+            vectors
+                .lines()
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| {
+                    let mut parts = line.split_whitespace();
+                    let name = parts.next().expect("Missing vector name");
+                    let encoded = decode_hex(parts.next().expect("Missing vector encoding"));
+                    (name, encoded)
+                })
+                .collect::<Vec<_>>()
+        };
+        let legacy = read_vectors(include_str!("vectors/proof_encoding_v1.txt"));
+        let current = read_vectors(include_str!("vectors/proof_encoding.txt"));
+        assert_eq!(legacy.len(), 5);
+
+        for ((name, legacy), (current_name, current)) in legacy.iter().zip(current.iter()) {
+            assert_eq!(name, current_name);
+            match *name {
+                "inclusion" => assert_eq!(
+                    <InclusionProof>::decode(legacy)?,
+                    <InclusionProof>::decode(current)?
+                ),
+                "absence_leaf" | "absence_branch" => assert_eq!(
+                    <AbsenceProof>::decode(legacy)?,
+                    <AbsenceProof>::decode(current)?
+                ),
+                "multi" => assert_eq!(
+                    <MultiProof>::decode(legacy)?,
+                    <MultiProof>::decode(current)?
+                ),
+                "range" => assert_eq!(
+                    <RangeProof>::decode(legacy)?,
+                    <RangeProof>::decode(current)?
+                ),
+                _ => panic!("Unknown vector {}", name),
+            }
+        }
+
+        let multi = &legacy[3].1;
+        assert!(MultiProof::<20, 32>::decode(multi).is_err());
+        let mut narrow = multi.clone();
+        narrow[2] = 20;
+        assert!(MultiProof::<20, 32>::decode(&narrow).is_err());
+        assert!(MultiProof::<32, 20>::decode(multi).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_encodes_and_decodes_proofs_from_a_tree() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x44u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x61u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(64, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let scheme = TaggedScheme::<TreeHasher>::committed();
+        let mut bmt = open_with_scheme(&path, scheme.clone())?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let proof = bmt
+            .get_proof(&root, &keys[0])?
+            .expect("Failed to get proof");
+        let decoded = InclusionProof::decode(&proof.encode()?)?;
+        assert_eq!(decoded, proof);
+        assert!(verify_proof_with_scheme(
+            &scheme, &root, &keys[0], &values[0], &decoded
+        )?);
+
+        let absent_key = [0x00u8; KEY_LEN];
+        let proof = bmt
+            .get_absence_proof(&root, &absent_key)?
+            .expect("Failed to get absence proof");
+        let decoded = AbsenceProof::decode(&proof.encode()?)?;
+        assert_eq!(decoded, proof);
+        assert!(verify_absence_proof(&scheme, &root, &absent_key, &decoded)?);
+
+        let mut proof_keys = keys.iter().take(8).collect::<Vec<_>>();
+        let proof = bmt
+            .get_multiproof(&root, &mut proof_keys)?
+            .expect("Failed to get multiproof");
+        let decoded = MultiProof::decode(&proof.encode()?)?;
+        assert_eq!(decoded, proof);
+        let claims = keys
+            .iter()
+            .zip(values.iter())
+            .take(8)
+            .map(|(key, value)| (key, Some(value)))
+            .collect::<Vec<_>>();
+        assert!(verify_multiproof_with_scheme(
+            &scheme, &root, &claims, &decoded
+        )?);
+
+        let start = [0x40u8; KEY_LEN];
+        let end = [0x80u8; KEY_LEN];
+        let (entries, proof) = bmt
+            .get_range_proof(&root, &start, &end)?
+            .expect("Failed to get range proof");
+        let decoded = RangeProof::decode(&proof.encode()?)?;
+        assert_eq!(decoded, proof);
+        let entry_refs = entries.iter().map(|(k, v)| (k, v)).collect::<Vec<_>>();
+        assert!(verify_range_proof_with_scheme(
+            &scheme,
+            &root,
+            &start,
+            &end,
+            &entry_refs,
+            &decoded
+        )?);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_malformed_proof_encodings() -> BinaryMerkleTreeResult<()> {
+        let proof: MultiProof = MultiProof::new(vec![MultiProofNode::Hash([0x01u8; HASH_LEN])]);
+        let encoded = proof.encode()?;
+        assert_eq!(<MultiProof>::decode(&encoded)?, proof);
+
+        let mut wrong_version = encoded.clone();
+        wrong_version[0] = 0xFF;
+        assert!(<MultiProof>::decode(&wrong_version).is_err());
+
+        assert!(<RangeProof>::decode(&encoded).is_err());
+
+        let mut wrong_key_length = encoded.clone();
+        wrong_key_length[2] = 20;
+        assert!(<MultiProof>::decode(&wrong_key_length).is_err());
+
+        let mut wrong_hash_length = encoded.clone();
+        wrong_hash_length[3] = 64;
+        assert!(<MultiProof>::decode(&wrong_hash_length).is_err());
+
+        let mut unknown_node = encoded.clone();
+        unknown_node[8] = 0xFF;
+        assert!(<MultiProof>::decode(&unknown_node).is_err());
+
+        assert!(<MultiProof>::decode(&encoded[..encoded.len() - 1]).is_err());
+        assert!(<MultiProof>::decode(&[]).is_err());
+
+        let mut trailing = encoded.clone();
+        trailing.push(0x00);
+        assert!(<MultiProof>::decode(&trailing).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_deletes_an_item_from_a_tree() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x45u8; KEY_LEN];
+        let path = generate_path(seed);
+        let key_values = vec![[0x00u8; KEY_LEN], [0x10u8; KEY_LEN], [0x20u8; KEY_LEN]];
+        let mut keys = key_values.iter().collect::<Vec<_>>();
+        let values = vec![vec![0x00u8], vec![0x01u8], vec![0x02u8]];
+        let mut data = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &mut data)?;
+
+        let new_root = bmt
+            .delete(&root, &mut [&key_values[1]])?
+            .expect("Tree should not be empty");
+        let expected_root = bmt.insert(
+            None,
+            &mut [&key_values[0], &key_values[2]],
+            &mut [&values[0], &values[2]],
+        )?;
+        assert_eq!(new_root, expected_root);
+
+        let items = bmt.get(&new_root, &mut keys)?;
+        assert_eq!(items[&key_values[0]], Some(values[0].clone()));
+        assert_eq!(items[&key_values[1]], None);
+        assert_eq!(items[&key_values[2]], Some(values[2].clone()));
+
+        // The previous root is unaffected
+        let items = bmt.get(&root, &mut keys)?;
+        assert_eq!(items[&key_values[1]], Some(values[1].clone()));
+
+        // Deleting a key that is not in the tree leaves the tree unchanged
+        let absent_key = [0x30u8; KEY_LEN];
+        let same_root = bmt.delete(&new_root, &mut [&absent_key])?;
+        assert_eq!(same_root, Some(new_root));
+
+        let empty_root = bmt.delete(&new_root, &mut [&key_values[0], &key_values[2]])?;
+        assert_eq!(empty_root, None);
+
+        let single_root = bmt
+            .delete(&new_root, &mut [&key_values[2]])?
+            .expect("Tree should not be empty");
+        let expected_root = bmt.insert(None, &mut [&key_values[0]], &mut [&values[0]])?;
+        assert_eq!(single_root, expected_root);
+        assert_eq!(bmt.delete(&single_root, &mut [&key_values[0]])?, None);
+
+        assert!(bmt.delete(&root, &mut []).is_err());
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_deletes_items_to_match_a_fresh_insert() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x46u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x62u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(256, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let mut delete_keys = keys.iter().step_by(3).collect::<Vec<_>>();
+        let new_root = bmt
+            .delete(&root, &mut delete_keys)?
+            .expect("Tree should not be empty");
+
+        let mut remaining_keys = vec![];
+        let mut remaining_values = vec![];
+        for (i, (key, value)) in keys.iter().zip(values.iter()).enumerate() {
+            if i % 3 != 0 {
+                remaining_keys.push(key);
+                remaining_values.push(value);
+            }
+        }
+
+        let fresh_path = generate_path([0x47u8; KEY_LEN]);
+        let mut fresh_bmt = Tree::open(&fresh_path, 160)?;
+        let fresh_root = fresh_bmt.insert(None, &mut remaining_keys, &mut remaining_values)?;
+        assert_eq!(new_root, fresh_root);
+
+        // Removing the old root keeps every node of the new root
+        bmt.remove(&root)?;
+        let mut all_keys = keys.iter().collect::<Vec<_>>();
+        let items = bmt.get(&new_root, &mut all_keys)?;
+        for (i, (key, value)) in keys.iter().zip(values.iter()).enumerate() {
+            if i % 3 == 0 {
+                assert_eq!(items[key], None);
+            } else {
+                assert_eq!(items[key], Some(value.clone()));
+            }
+        }
+        tear_down(&path);
+        tear_down(&fresh_path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_applies_a_write_batch_of_upserts_and_deletes() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x48u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x63u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(64, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let (new_keys, new_values) = prepare_inserts(8, &mut rng);
+        let mut expected = keys
+            .iter()
+            .cloned()
+            .zip(values.iter().cloned())
+            .collect::<Vec<_>>();
+
+        let mut batch = WriteBatch::new();
+        for i in 0..8 {
+            // Updates
+            let updated_value = vec![0xFFu8, i as u8];
+            batch.put(keys[i], updated_value.clone());
+            expected[i].1 = updated_value;
+            // Deletes
+            batch.delete(keys[i + 8]);
+            // Inserts
+            batch.put(new_keys[i], new_values[i].clone());
+            expected.push((new_keys[i], new_values[i].clone()));
+        }
+        // The last change to a key wins
+        let short_lived_key = [0x00u8; KEY_LEN];
+        batch.put(short_lived_key, vec![0x00u8]);
+        batch.delete(short_lived_key);
+        batch.put(keys[16], vec![0x00u8]);
+        batch.put(keys[16], vec![0x01u8]);
+        expected[16].1 = vec![0x01u8];
+        expected.drain(8..16);
+        assert_eq!(batch.len(), 26);
+
+        let new_root = bmt
+            .apply(Some(&root), &batch)?
+            .expect("Tree should not be empty");
+
+        let fresh_path = generate_path([0x49u8; KEY_LEN]);
+        let mut fresh_bmt = Tree::open(&fresh_path, 160)?;
+        let mut fresh_keys = expected.iter().map(|(k, _)| k).collect::<Vec<_>>();
+        let mut fresh_values = expected.iter().map(|(_, v)| v).collect::<Vec<_>>();
+        let fresh_root = fresh_bmt.insert(None, &mut fresh_keys, &mut fresh_values)?;
+        assert_eq!(new_root, fresh_root);
+
+        let mut get_keys = keys.iter().chain(new_keys.iter()).collect::<Vec<_>>();
+        get_keys.push(&short_lived_key);
+        let items = bmt.get(&new_root, &mut get_keys)?;
+        for (key, value) in expected.iter() {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        for key in keys[8..16].iter() {
+            assert_eq!(items[key], None);
+        }
+        assert_eq!(items[&short_lived_key], None);
+
+        // The previous root is unaffected
+        let items = bmt.get(&root, &mut get_keys)?;
+        for (key, value) in keys.iter().zip(values.iter()) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        tear_down(&path);
+        tear_down(&fresh_path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_applies_a_write_batch_without_a_previous_root() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x4Au8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut bmt = Tree::open(&path, 160)?;
+
+        let mut batch = WriteBatch::new();
+        assert!(bmt.apply(None, &batch).is_err());
+
+        batch.delete([0x01u8; KEY_LEN]);
+        assert_eq!(bmt.apply(None, &batch)?, None);
+
+        batch.put([0x02u8; KEY_LEN], vec![0x02u8]);
+        let root = bmt.apply(None, &batch)?.expect("Tree should not be empty");
+        let expected_root = bmt.insert(None, &mut [&[0x02u8; KEY_LEN]], &mut [&vec![0x02u8]])?;
+        assert_eq!(root, expected_root);
+
+        let mut batch = WriteBatch::new();
+        batch.delete([0x02u8; KEY_LEN]);
+        assert_eq!(bmt.apply(Some(&root), &batch)?, None);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_applies_a_write_batch_with_a_single_batch_write() -> BinaryMerkleTreeResult<()> {
+        let batch_writes = Arc::new(AtomicUsize::new(0));
+        let db = CountingDB::new(Arc::new(AtomicUsize::new(0)), Arc::clone(&batch_writes))?;
+        let mut bmt = CountingTree::from_db(db, 160)?;
+
+        let seed = [0x64u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(64, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+        assert_eq!(batch_writes.load(Ordering::SeqCst), 1);
+
+        let mut batch = WriteBatch::new();
+        for i in 0..16 {
+            if i % 2 == 0 {
+                batch.put(keys[i], vec![i as u8]);
+            } else {
+                batch.delete(keys[i]);
+            }
+        }
+        bmt.apply(Some(&root), &batch)?;
+        assert_eq!(batch_writes.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn it_iterates_over_items_in_order() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x4Bu8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x65u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(256, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let mut expected = keys
+            .iter()
+            .cloned()
+            .zip(values.iter().cloned())
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        let items = bmt
+            .iter(&root)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected);
+
+        // Iteration is lazy and can stop early
+        let first_items = bmt
+            .iter(&root)
+            .take(4)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(first_items, expected[..4].to_vec());
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_iterates_over_a_single_item_and_a_missing_root() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x4Cu8; KEY_LEN];
+        let path = generate_path(seed);
+        let key = [0xAAu8; KEY_LEN];
+        let value = vec![0xBBu8];
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut [&key], &mut [&value])?;
+
+        let items = bmt
+            .iter(&root)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, vec![(key, value)]);
+
+        let missing_root = [0x00u8; KEY_LEN];
+        assert_eq!(bmt.iter(&missing_root).count(), 0);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_iterates_over_a_range_of_items() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x4Du8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x66u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(256, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let mut expected = keys
+            .iter()
+            .cloned()
+            .zip(values.iter().cloned())
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        let start = expected[40].0;
+        let end = expected[80].0;
+
+        let items = bmt
+            .range(&root, start..end)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected[40..80].to_vec());
+
+        let items = bmt
+            .range(&root, start..=end)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected[40..=80].to_vec());
+
+        let items = bmt
+            .range(&root, ..end)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected[..80].to_vec());
+
+        let items = bmt
+            .range(&root, start..)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected[40..].to_vec());
+
+        let items = bmt
+            .range(&root, ..)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected);
+
+        // Bounds that fall between keys
+        let mut between = expected[40].0;
+        between[KEY_LEN - 1] = between[KEY_LEN - 1].wrapping_add(1);
+        let items = bmt
+            .range(&root, between..end)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected[41..80].to_vec());
+
+        assert_eq!(bmt.range(&root, end..start).count(), 0);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_iterates_over_items_with_a_prefix() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x4Eu8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x67u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(256, &mut rng);
+        // Place the keys into four namespaces
+        for (i, key) in keys.iter_mut().enumerate() {
+            key[0] = (i % 4) as u8;
+        }
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let mut prefix = [0x00u8; KEY_LEN];
+        prefix[0] = 0x02;
+        let mut expected = keys
+            .iter()
+            .cloned()
+            .zip(values.iter().cloned())
+            .filter(|(key, _)| key[0] == 0x02)
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(expected.len(), 64);
+
+        let items = bmt
+            .prefix(&root, &prefix, 8)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected);
+
+        // A prefix that ends partway through a byte covers namespaces 0x02 and 0x03
+        let items = bmt
+            .prefix(&root, &prefix, 7)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items.len(), 128);
+        assert!(items
+            .iter()
+            .all(|(key, _)| key[0] == 0x02 || key[0] == 0x03));
+
+        assert_eq!(bmt.prefix(&root, &prefix, 0).count(), 256);
+
+        prefix[0] = 0x04;
+        assert_eq!(bmt.prefix(&root, &prefix, 8).count(), 0);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_prunes_subtrees_outside_of_a_prefix() -> BinaryMerkleTreeResult<()> {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let db = CountingDB::new(Arc::clone(&reads), Arc::new(AtomicUsize::new(0)))?;
+        let mut bmt = CountingTree::from_db(db, 160)?;
+
+        let seed = [0x68u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(256, &mut rng);
+        keys[0][0] = 0x00;
+        for key in keys.iter_mut().skip(1) {
+            key[0] |= 0x80;
+        }
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        reads.store(0, Ordering::SeqCst);
+        let items = bmt
+            .prefix(&root, &[0x00u8; KEY_LEN], 1)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, vec![(keys[0], values[0].clone())]);
+        // The root, the leaf, and its data node
+        assert_eq!(reads.load(Ordering::SeqCst), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn it_gets_the_length_nth_item_and_rank_of_keys() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x4Fu8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x69u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(300, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        assert_eq!(bmt.len(&[0x00u8; KEY_LEN])?, 0);
+        assert_eq!(bmt.nth(&[0x00u8; KEY_LEN], 0)?, None);
+        assert_eq!(bmt.rank(&[0x00u8; KEY_LEN], &keys[0])?, 0);
+
+        let mut first_keys = keys[..200].iter().collect::<Vec<_>>();
+        let mut first_values = values[..200].iter().collect::<Vec<_>>();
+        let first_root = bmt.insert(None, &mut first_keys, &mut first_values)?;
+        let mut second_keys = keys[150..].iter().collect::<Vec<_>>();
+        let mut second_values = values[150..].iter().collect::<Vec<_>>();
+        let second_root = bmt.insert(Some(&first_root), &mut second_keys, &mut second_values)?;
+        let mut removed_keys = keys[..20].iter().collect::<Vec<_>>();
+        let root = bmt
+            .delete(&second_root, &mut removed_keys)?
+            .expect("Tree should not be empty");
+
+        let mut expected = keys[20..]
+            .iter()
+            .cloned()
+            .zip(values[20..].iter().cloned())
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        assert_eq!(bmt.len(&first_root)?, 200);
+        assert_eq!(bmt.len(&root)?, expected.len() as u64);
+        for (i, item) in expected.iter().enumerate() {
+            assert_eq!(bmt.nth(&root, i as u64)?.as_ref(), Some(item));
+            assert_eq!(bmt.rank(&root, &item.0)?, i as u64);
+
+            // A key just above an existing key sorts after it
+            let mut next_key = item.0;
+            next_key[KEY_LEN - 1] = next_key[KEY_LEN - 1].wrapping_add(1);
+            if next_key[KEY_LEN - 1] != 0 && !expected.iter().any(|(key, _)| *key == next_key) {
+                assert_eq!(bmt.rank(&root, &next_key)?, i as u64 + 1);
+            }
+        }
+        assert_eq!(bmt.nth(&root, expected.len() as u64)?, None);
+        assert_eq!(bmt.rank(&root, &[0x00u8; KEY_LEN])?, 0);
+        assert_eq!(bmt.rank(&root, &[0xFFu8; KEY_LEN])?, expected.len() as u64);
+
+        // Deleted keys are still ranked by where they would sort
+        for key in &keys[..20] {
+            let position = expected.iter().filter(|(k, _)| k < key).count();
+            assert_eq!(bmt.rank(&root, key)?, position as u64);
+        }
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_diffs_two_roots() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x50u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x6Au8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(200, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let old_root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let (new_keys, new_values) = prepare_inserts(20, &mut rng);
+        let mut batch = WriteBatch::new();
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+        let mut changed = Vec::new();
+        for i in 0..20 {
+            batch.put(new_keys[i], new_values[i].clone());
+            added.push((new_keys[i], new_values[i].clone()));
+        }
+        for i in 0..15 {
+            batch.delete(keys[i * 13]);
+            removed.push((keys[i * 13], values[i * 13].clone()));
+        }
+        for i in 0..10 {
+            let new_value = vec![0xFFu8, i as u8];
+            batch.put(keys[i * 13 + 1], new_value.clone());
+            changed.push((keys[i * 13 + 1], values[i * 13 + 1].clone(), new_value));
+        }
+        // Writing the same value does not change a key
+        batch.put(keys[2], values[2].clone());
+        let new_root = bmt
+            .apply(Some(&old_root), &batch)?
+            .expect("Tree should not be empty");
+        added.sort();
+        removed.sort();
+        changed.sort();
+
+        let diff = bmt.diff(&old_root, &new_root)?;
+        assert_eq!(diff.added, added);
+        assert_eq!(diff.removed, removed);
+        assert_eq!(diff.changed, changed);
+
+        // Diffing the other way swaps the sides
+        let diff = bmt.diff(&new_root, &old_root)?;
+        assert_eq!(diff.added, removed);
+        assert_eq!(diff.removed, added);
+        let swapped = changed
+            .iter()
+            .map(|(key, old_value, new_value)| (*key, new_value.clone(), old_value.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(diff.changed, swapped);
+
+        assert!(bmt.diff(&old_root, &old_root)?.is_empty());
+
+        let missing_root = [0x00u8; HASH_LEN];
+        assert!(bmt.diff(&missing_root, &old_root).is_err());
+        assert!(bmt.diff(&old_root, &missing_root).is_err());
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_diffs_two_roots_without_visiting_shared_subtrees() -> BinaryMerkleTreeResult<()> {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let db = CountingDB::new(Arc::clone(&reads), Arc::new(AtomicUsize::new(0)))?;
+        let mut bmt = CountingTree::from_db(db, 160)?;
+
+        let seed = [0x6Bu8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(1024, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+        let old_root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let new_value = vec![0xFFu8];
+        let new_root = bmt.insert(Some(&old_root), &mut [&keys[512]], &mut [&new_value])?;
+
+        reads.store(0, Ordering::SeqCst);
+        let diff = bmt.diff(&old_root, &new_root)?;
+        assert_eq!(
+            diff.changed,
+            vec![(keys[512], values[512].clone(), new_value)]
+        );
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        // Only the nodes on the path to the changed key are read from each side
+        assert!(reads.load(Ordering::SeqCst) < 64);
+        Ok(())
+    }
+
+    #[test]
+    fn it_replays_changesets_into_a_new_tree() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x51u8; KEY_LEN];
+        let path = generate_path(seed);
+        let seed = [0x52u8; KEY_LEN];
+        let replay_path = generate_path(seed);
+
+        let seed = [0x6Cu8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(100, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let first_root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let (new_keys, new_values) = prepare_inserts(10, &mut rng);
+        let mut batch = WriteBatch::new();
+        for i in 0..10 {
+            batch.put(new_keys[i], new_values[i].clone());
+            batch.put(keys[i * 3], vec![0xFFu8, i as u8]);
+            batch.delete(keys[i * 3 + 1]);
+        }
+        let second_root = bmt
+            .apply(Some(&first_root), &batch)?
+            .expect("Tree should not be empty");
+
+        let mut removed_keys = keys[50..].iter().collect::<Vec<_>>();
+        let third_root = bmt
+            .delete(&second_root, &mut removed_keys)?
+            .expect("Tree should not be empty");
+
+        let changesets = vec![
+            bmt.export_changeset(None, &first_root)?,
+            bmt.export_changeset(Some(&first_root), &second_root)?,
+            bmt.export_changeset(Some(&second_root), &third_root)?,
+        ];
+        assert_eq!(changesets[0].changes.len(), 100);
+        assert_eq!(changesets[1].changes.len(), 30);
+        assert_eq!(changesets[2].changes.len(), 50);
+
+        let mut replay = Tree::open(&replay_path, 160)?;
+        for changeset in changesets {
+            let expected_root = changeset.new_root;
+            let encoded = changeset.encode()?;
+            let decoded = Changeset::<Vec<u8>>::decode(&encoded)?;
+            assert_eq!(decoded, changeset);
+            assert_eq!(replay.apply_changeset(decoded)?, expected_root);
+        }
+
+        let expected = bmt
+            .iter(&third_root)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        let items = replay
+            .iter(&third_root)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected);
+        tear_down(&path);
+        tear_down(&replay_path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_a_changeset_that_does_not_produce_its_root() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x53u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x6Du8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(32, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let first_root = bmt.insert(None, &mut insert_keys[..16], &mut insert_values[..16])?;
+        let second_root = bmt.insert(
+            Some(&first_root),
+            &mut insert_keys[16..],
+            &mut insert_values[16..],
+        )?;
+
+        let unchanged = bmt.export_changeset(Some(&second_root), &second_root)?;
+        assert!(unchanged.changes.is_empty());
+        assert_eq!(bmt.apply_changeset(unchanged)?, second_root);
+        // The replayed root is held separately from the one it was replayed on
+        assert!(bmt
+            .check(&[first_root, second_root, second_root], false)?
+            .is_clean());
+
+        // A changeset without changes still needs its root to be present
+        let missing_root = [0x00u8; HASH_LEN];
+        let missing = Changeset::<Vec<u8>>::new(Some(missing_root), missing_root, vec![]);
+        assert!(bmt.apply_changeset(missing).is_err());
+
+        let mut changeset = bmt.export_changeset(Some(&first_root), &second_root)?;
+        changeset.changes[0].1 = Some(vec![0x00u8]);
+        assert!(bmt.apply_changeset(changeset).is_err());
+
+        let mut changeset = bmt.export_changeset(Some(&first_root), &second_root)?;
+        changeset.previous_root = None;
+        assert!(bmt.apply_changeset(changeset).is_err());
+
+        let encoded = bmt
+            .export_changeset(Some(&first_root), &second_root)?
+            .encode()?;
+        assert!(Changeset::<Vec<u8>>::decode(&encoded[..encoded.len() - 1]).is_err());
+        assert!(<MultiProof>::decode(&encoded).is_err());
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_merges_divergent_roots() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x54u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x6Eu8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(100, &mut rng);
+        let (new_keys, new_values) = prepare_inserts(12, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let base = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        let mut expected = keys
+            .iter()
+            .cloned()
+            .zip(values.iter().cloned())
+            .collect::<BTreeMap<_, _>>();
+
+        let mut our_batch = WriteBatch::new();
+        let mut their_batch = WriteBatch::new();
+        for i in 0..5 {
+            // Changes made on only one side
+            our_batch.put(keys[i], vec![0x01u8, i as u8]);
+            expected.insert(keys[i], vec![0x01u8, i as u8]);
+            their_batch.put(keys[i + 10], vec![0x02u8, i as u8]);
+            expected.insert(keys[i + 10], vec![0x02u8, i as u8]);
+            our_batch.delete(keys[i + 20]);
+            expected.remove(&keys[i + 20]);
+            their_batch.delete(keys[i + 30]);
+            expected.remove(&keys[i + 30]);
+            our_batch.put(new_keys[i], new_values[i].clone());
+            expected.insert(new_keys[i], new_values[i].clone());
+            their_batch.put(new_keys[i + 5], new_values[i + 5].clone());
+            expected.insert(new_keys[i + 5], new_values[i + 5].clone());
+        }
+        // The same change on both sides is not a conflict
+        our_batch.put(keys[40], vec![0x03u8]);
+        their_batch.put(keys[40], vec![0x03u8]);
+        expected.insert(keys[40], vec![0x03u8]);
+        our_batch.delete(keys[41]);
+        their_batch.delete(keys[41]);
+        expected.remove(&keys[41]);
+        // Conflicting changes
+        our_batch.put(keys[42], vec![0x04u8]);
+        their_batch.put(keys[42], vec![0x05u8]);
+        expected.insert(keys[42], vec![0x04u8, 0x05u8]);
+        our_batch.delete(keys[43]);
+        their_batch.put(keys[43], vec![0x06u8]);
+        expected.insert(keys[43], vec![0x06u8]);
+        our_batch.put(new_keys[10], vec![0x07u8]);
+        their_batch.put(new_keys[10], vec![0x08u8]);
+        expected.remove(&new_keys[10]);
+
+        let ours = bmt
+            .apply(Some(&base), &our_batch)?
+            .expect("Tree should not be empty");
+        let theirs = bmt
+            .apply(Some(&base), &their_batch)?
+            .expect("Tree should not be empty");
+
+        let mut conflicts = Vec::new();
+        let merged = bmt
+            .merge(
+                &base,
+                &ours,
+                &theirs,
+                |key, base_value, our_value, their_value| {
+                    conflicts.push((
+                        *key,
+                        base_value.cloned(),
+                        our_value.cloned(),
+                        their_value.cloned(),
+                    ));
+                    match (our_value, their_value) {
+                        (Some(o), Some(t)) if base_value.is_some() => {
+                            Ok(Some(o.iter().chain(t.iter()).cloned().collect()))
+                        }
+                        (None, Some(t)) => Ok(Some(t.clone())),
+                        _ => Ok(None),
+                    }
+                },
+            )?
+            .expect("Tree should not be empty");
+
+        conflicts.sort();
+        let mut expected_conflicts = vec![
+            (
+                keys[42],
+                Some(values[42].clone()),
+                Some(vec![0x04u8]),
+                Some(vec![0x05u8]),
+            ),
+            (keys[43], Some(values[43].clone()), None, Some(vec![0x06u8])),
+            (new_keys[10], None, Some(vec![0x07u8]), Some(vec![0x08u8])),
+        ];
+        expected_conflicts.sort();
+        assert_eq!(conflicts, expected_conflicts);
+
+        let expected = expected.into_iter().collect::<Vec<_>>();
+        let items = bmt
+            .iter(&merged)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected);
+
+        // The merged root is the same as building the merged state directly
+        let mut expected_keys = expected.iter().map(|(key, _)| key).collect::<Vec<_>>();
+        let mut expected_values = expected.iter().map(|(_, value)| value).collect::<Vec<_>>();
+        let direct_root = bmt.insert(None, &mut expected_keys, &mut expected_values)?;
+        assert_eq!(merged, direct_root);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_a_merged_root_after_removing_its_parents() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x55u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x6Fu8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(64, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let base = bmt.insert(None, &mut insert_keys[..32], &mut insert_values[..32])?;
+        let ours = bmt.insert(
+            Some(&base),
+            &mut insert_keys[32..48],
+            &mut insert_values[32..48],
+        )?;
+        let theirs = bmt.insert(
+            Some(&base),
+            &mut insert_keys[48..],
+            &mut insert_values[48..],
+        )?;
+
+        let merged = bmt
+            .merge(&base, &ours, &theirs, |_, _, _, _| {
+                Err(Exception::new("No keys should conflict"))
+            })?
+            .expect("Tree should not be empty");
+
+        // Merging with no changes on their side keeps our root
+        assert_eq!(
+            bmt.merge(&base, &ours, &base, |_, _, _, _| Ok(None))?,
+            Some(ours)
+        );
+
+        let missing_root = [0x00u8; HASH_LEN];
+        assert!(bmt
+            .merge(&base, &missing_root, &theirs, |_, _, _, _| Ok(None))
+            .is_err());
+        assert!(bmt
+            .merge(&missing_root, &ours, &theirs, |_, _, _, _| Ok(None))
+            .is_err());
+
+        assert!(bmt
+            .check(&[base, ours, theirs, merged, ours], false)?
+            .is_clean());
+
+        bmt.remove(&base)?;
+        bmt.remove(&ours)?;
+        bmt.remove(&theirs)?;
+        assert!(bmt.check(&[merged, ours], false)?.is_clean());
+
+        let expected = keys
+            .iter()
+            .cloned()
+            .zip(values.iter().cloned())
+            .collect::<Vec<_>>();
+        let items = bmt
+            .iter(&merged)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_references_the_root_of_a_merge_without_changes() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x57u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let seed = [0x7Eu8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(32, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let a = bmt.insert(None, &mut insert_keys[..16], &mut insert_values[..16])?;
+        let b = bmt.insert(Some(&a), &mut insert_keys[16..], &mut insert_values[16..])?;
+
+        // The merged root is held separately from `a`, even though it is the same root
+        let merged = bmt
+            .merge(&a, &a, &a, |_, _, _, _| {
+                Err(Exception::new("No keys should conflict"))
+            })?
+            .expect("Tree should not be empty");
+        assert_eq!(merged, a);
+        assert!(bmt.check(&[a, b, merged], false)?.is_clean());
+
+        bmt.remove(&merged)?;
+        assert!(bmt.check(&[a, b], false)?.is_clean());
+        let items = bmt.iter(&a).collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items.len(), 16);
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(any(
+        feature = "use_rocksdb",
+        feature = "use_groestl",
+        feature = "use_sha2",
+        feature = "use_rust_sha2",
+        feature = "use_sha3",
+        feature = "use_keccak",
+        feature = "use_seahash",
+    )))]
+    fn it_works_with_other_key_and_hash_lengths() -> BinaryMerkleTreeResult<()> {
+        const SHORT_KEY_LEN: usize = 20;
+        const LONG_HASH_LEN: usize = 64;
+
+        let seed = [0x70u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let mut keys = Vec::with_capacity(64);
+        let mut values = Vec::with_capacity(64);
+        for _ in 0..64 {
+            let mut key = [0u8; SHORT_KEY_LEN];
+            rng.fill(&mut key);
+            keys.push(key);
+            values.push(vec![rng.gen::<u8>()]);
+        }
+        keys.sort();
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = HashTree::<Vec<u8>, SHORT_KEY_LEN, LONG_HASH_LEN>::new(160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+        assert_eq!(root.len(), LONG_HASH_LEN);
+
+        let items = bmt.get(&root, &mut insert_keys)?;
+        for (key, value) in keys.iter().zip(values.iter()) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        let iterated = bmt
+            .iter(&root)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(
+            iterated.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+            keys
+        );
+
+        let proof = bmt
+            .get_proof(&root, &keys[0])?
+            .expect("Failed to get proof");
+        assert!(verify_proof::<TreeHasher, _, SHORT_KEY_LEN, LONG_HASH_LEN>(
+            &root, &keys[0], &values[0], &proof
+        )?);
+        let encoded = proof.encode()?;
+        assert_eq!(encoded[2..4], [SHORT_KEY_LEN as u8, LONG_HASH_LEN as u8]);
+        assert_eq!(InclusionProof::decode(&encoded)?, proof);
+        assert!(<InclusionProof>::decode(&encoded).is_err());
+
+        let new_root = bmt
+            .delete(&root, &mut [&keys[0]])?
+            .expect("Failed to delete");
+        assert_eq!(bmt.len(&new_root)?, 63);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "use_rocksdb")))]
+    fn it_rejects_unsupported_key_lengths() {
+        assert!(HashTree::<Vec<u8>, 33>::new(160).is_err());
+        assert!(HashTree::<Vec<u8>, 0>::new(160).is_err());
+        assert!(HashTree::<Vec<u8>, 1>::new(160).is_ok());
+    }
+
+    #[test]
+    fn it_works_with_keys_of_any_length() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x56u8; KEY_LEN];
+        let path = generate_path(seed);
+
+        let long_key = vec![0xAAu8; 100];
+        let keys: Vec<&[u8]> = vec![b"", b"apple", b"banana", b"cherry", &long_key];
+        let values = (0..keys.len() as u8).map(|i| vec![i]).collect::<Vec<_>>();
+        let insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = KeyedTree::open(&path, 160)?;
+        let root = bmt.insert(None, &keys, &insert_values)?;
+        assert_eq!(bmt.len(&root)?, 5);
+
+        let missing: &[u8] = b"durian";
+        let mut get_keys = keys.clone();
+        get_keys.push(missing);
+        let items = bmt.get(&root, &get_keys)?;
+        for (key, value) in keys.iter().zip(values.iter()) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        assert_eq!(items[missing], None);
+
+        // Iteration gives back the original keys, ordered by their hashes
+        let mut expected = keys
+            .iter()
+            .map(|key| key.to_vec())
+            .zip(values.iter().cloned())
+            .collect::<Vec<_>>();
+        expected.sort_by_key(|(key, _)| KeyedTree::hash_key(key));
+        let items = bmt
+            .iter(&root)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        assert_eq!(items, expected);
+
+        // Differences are reported with the original keys
+        let new_value = vec![0xFFu8];
+        let changed_root = bmt.insert(
+            Some(&root),
+            &[b"apple".as_ref(), missing],
+            &[&new_value, &new_value],
+        )?;
+        let deleted_root = bmt
+            .delete(&changed_root, &[b"banana".as_ref(), b"unknown".as_ref()])?
+            .expect("Tree should not be empty");
+        let diff = bmt.diff(&root, &deleted_root)?;
+        assert_eq!(diff.added, vec![(missing.to_vec(), new_value.clone())]);
+        assert_eq!(diff.removed, vec![(b"banana".to_vec(), vec![2])]);
+        assert_eq!(
+            diff.changed,
+            vec![(b"apple".to_vec(), vec![1], new_value.clone())]
+        );
+
+        // Deleting only keys that are not held leaves the root as it was, with its own reference
+        assert_eq!(
+            bmt.delete(&deleted_root, &[b"unknown".as_ref()])?,
+            Some(deleted_root)
+        );
+        let live_roots = [root, changed_root, deleted_root, deleted_root];
+        assert!(bmt.check(&live_roots, false)?.is_clean());
+        bmt.remove(&deleted_root)?;
+        assert!(bmt.check(&live_roots[..3], false)?.is_clean());
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "use_rayon"))]
+    fn it_hashes_keys_over_a_database_that_is_not_thread_safe() -> BinaryMerkleTreeResult<()> {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        /// A database counting its reads through an `Rc`, so it is neither `Send` nor `Sync`.
+        struct LocalDB {
+            db: HashTreeDB,
+            reads: Rc<Cell<usize>>,
+        }
+
+        impl Database<HASH_LEN> for LocalDB {
+            type NodeType = TreeNode;
+            type EntryType = ([u8; HASH_LEN], Vec<u8>);
+
+            fn open(path: &PathBuf) -> Result<Self, Exception> {
+                Ok(Self {
+                    db: HashTreeDB::open(path)?,
+                    reads: Rc::new(Cell::new(0)),
+                })
+            }
+
+            fn get_node(&self, key: &[u8; HASH_LEN]) -> Result<Option<TreeNode>, Exception> {
+                self.reads.set(self.reads.get() + 1);
+                self.db.get_node(key)
+            }
+
+            fn insert(&mut self, key: [u8; HASH_LEN], node: TreeNode) -> Result<(), Exception> {
+                self.db.insert(key, node)
+            }
+
+            fn remove(&mut self, key: &[u8; HASH_LEN]) -> Result<(), Exception> {
+                self.db.remove(key)
+            }
+
+            fn batch_write(&mut self) -> Result<(), Exception> {
+                self.db.batch_write()
+            }
+
+            fn rollback(&mut self) -> Result<(), Exception> {
+                self.db.rollback()
+            }
+        }
+
+        let db = LocalDB::open(&PathBuf::new())?;
+        let reads = Rc::clone(&db.reads);
+        let mut bmt = HashedKeyTree::<LocalDB, Vec<u8>>::from_db(db, 160)?;
+
+        let keys: Vec<&[u8]> = vec![b"apple", b"banana"];
+        let values = [vec![0x01u8], vec![0x02u8]];
+        let insert_values = values.iter().collect::<Vec<_>>();
+        let root = bmt.insert(None, &keys, &insert_values)?;
+        let items = bmt.get(&root, &keys)?;
+        for (key, value) in keys.iter().zip(values.iter()) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        assert!(reads.get() > 0);
+
+        // Checking needs every location, which this database does not list
+        assert!(bmt.check(&[root], false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "use_rocksdb")))]
+    fn it_rejects_keys_longer_than_hashes_for_hashed_keys() {
+        use starling::tree_hasher::default::DefaultTreeHasher;
+
+        assert!(
+            HashedKeyTree::<HashTreeDB<20, 16>, Vec<u8>, 20, 16, DefaultTreeHasher>::from_db(
+                HashTreeDB::<20, 16>::open(&PathBuf::new()).expect("Failed to open database"),
+                160
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    #[cfg(all(not(any(feature = "use_rocksdb")), feature = "use_blake2b"))]
+    fn it_uses_different_hashers_side_by_side() -> BinaryMerkleTreeResult<()> {
+        use starling::tree_hasher::default::DefaultTreeHasher;
+
+        use starling::tree_hasher::blake2b::Blake2bHasher;
+
+        let seed = [0x71u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(32, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut default_bmt = HashTree::<Vec<u8>, KEY_LEN, HASH_LEN, DefaultTreeHasher>::new(160)?;
+        let mut blake_bmt = HashTree::<Vec<u8>, KEY_LEN, HASH_LEN, Blake2bHasher>::new(160)?;
+        let default_root = default_bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+        let blake_root = blake_bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+        assert_ne!(default_root, blake_root);
+
+        // Each tree only verifies against its own hasher
+        let default_proof = default_bmt
+            .get_proof(&default_root, &keys[0])?
+            .expect("Failed to get proof");
+        let blake_proof = blake_bmt
+            .get_proof(&blake_root, &keys[0])?
+            .expect("Failed to get proof");
+        assert!(verify_proof::<DefaultTreeHasher, _, KEY_LEN, HASH_LEN>(
+            &default_root,
+            &keys[0],
+            &values[0],
+            &default_proof
+        )?);
+        assert!(verify_proof::<Blake2bHasher, _, KEY_LEN, HASH_LEN>(
+            &blake_root,
+            &keys[0],
+            &values[0],
+            &blake_proof
+        )?);
+        assert!(!verify_proof::<DefaultTreeHasher, _, KEY_LEN, HASH_LEN>(
+            &blake_root,
+            &keys[0],
+            &values[0],
+            &blake_proof
+        )?);
+
+        let items = blake_bmt.get(&blake_root, &mut insert_keys)?;
+        for (key, value) in keys.iter().zip(values.iter()) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "use_rocksdb")))]
+    fn it_fills_the_whole_hash_with_the_default_hasher() -> BinaryMerkleTreeResult<()> {
+        use starling::traits::Hasher;
+        use starling::tree_hasher::default::{DefaultTreeHasher, LegacyDefaultHasher};
+
+        let mut hasher = <DefaultTreeHasher as Hasher<HASH_LEN>>::new(HASH_LEN);
+        <DefaultTreeHasher as Hasher<HASH_LEN>>::update(&mut hasher, b"data");
+        let hash = <DefaultTreeHasher as Hasher<HASH_LEN>>::finalize(hasher);
+        let lanes = hash.chunks(8).collect::<Vec<_>>();
+        for (i, lane) in lanes.iter().enumerate() {
+            assert_ne!(*lane, &[0u8; 8][..lane.len()]);
+            assert!(lanes[i + 1..].iter().all(|other| other != lane));
+        }
+
+        let seed = [0x72u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(16, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let mut bmt = HashTree::<Vec<u8>, KEY_LEN, HASH_LEN, DefaultTreeHasher>::new(160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+        assert!(root[8..].iter().any(|&byte| byte != 0));
+
+        // The legacy hasher is still available for trees written with it
+        let mut legacy_bmt = HashTree::<Vec<u8>, KEY_LEN, HASH_LEN, LegacyDefaultHasher>::new(160)?;
+        let legacy_root = legacy_bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+        assert_eq!(legacy_root[8..], [0u8; HASH_LEN - 8]);
+        let items = legacy_bmt.get(&legacy_root, &mut insert_keys)?;
+        for (key, value) in keys.iter().zip(values.iter()) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(any(
+        feature = "use_sha2",
+        feature = "use_rust_sha2",
+        feature = "use_blake3"
+    ))]
+    fn it_matches_the_hasher_vectors() {
+        #[cfg(feature = "use_blake3")]
+        use starling::tree_hasher::blake3::Blake3Hasher;
+        #[cfg(feature = "use_rust_sha2")]
+        use starling::tree_hasher::rust_sha256::RustSha256Hasher;
+        #[cfg(feature = "use_sha2")]
+        use starling::tree_hasher::sha256::Sha256Hasher;
+
+        let vectors = include_str!("vectors/hashers.txt")
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let mut parts = line.split_whitespace();
+                let name = parts.next().expect("Missing vector name");
+                let input = match parts.next().expect("Missing vector input") {
+                    "empty" => vec![],
+                    spec if spec.starts_with("pattern_") => {
+                        let len = spec["pattern_".len()..]
+                            .parse::<usize>()
+                            .expect("Invalid pattern length");
+                        (0..len).map(|i| (i % 251) as u8).collect()
+                    }
+                    hex => decode_hex(hex),
+                };
+                let digest = decode_hex(parts.next().expect("Missing vector digest"));
+                (name, input, digest)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vectors.len(), 9);
+
+        for (name, input, digest) in vectors {
+            match name {
+                "sha256" => {
+                    #[cfg(feature = "use_sha2")]
+                    assert_eq!(hash_with::<Sha256Hasher, 32>(&input)[..], digest[..]);
+                    #[cfg(feature = "use_rust_sha2")]
+                    assert_eq!(hash_with::<RustSha256Hasher, 32>(&input)[..], digest[..]);
+                }
+                "blake3" => {
+                    #[cfg(feature = "use_blake3")]
+                    assert_eq!(hash_with::<Blake3Hasher, 32>(&input)[..], digest[..]);
+                }
+                "blake3_64" => {
+                    #[cfg(feature = "use_blake3")]
+                    assert_eq!(hash_with::<Blake3Hasher, 64>(&input)[..], digest[..]);
+                }
+                _ => panic!("Unknown vector {}", name),
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(all(
+        not(any(feature = "use_rocksdb")),
+        feature = "use_sha2",
+        feature = "use_rust_sha2"
+    ))]
+    fn it_matches_openssl_with_the_pure_rust_sha256() -> BinaryMerkleTreeResult<()> {
+        use starling::tree_hasher::rust_sha256::RustSha256Hasher;
+        use starling::tree_hasher::sha256::Sha256Hasher;
+
+        let seed = [0x73u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        for len in 0..300 {
+            let input = (0..len).map(|_| rng.gen::<u8>()).collect::<Vec<_>>();
+            assert_eq!(
+                hash_with::<RustSha256Hasher, 32>(&input),
+                hash_with::<Sha256Hasher, 32>(&input)
+            );
+        }
+
+        let (keys, values) = prepare_inserts(64, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+        let mut openssl_bmt = HashTree::<Vec<u8>, KEY_LEN, HASH_LEN, Sha256Hasher>::new(160)?;
+        let mut rust_bmt = HashTree::<Vec<u8>, KEY_LEN, HASH_LEN, RustSha256Hasher>::new(160)?;
+        assert_eq!(
+            openssl_bmt.insert(None, &mut insert_keys, &mut insert_values)?,
+            rust_bmt.insert(None, &mut insert_keys, &mut insert_values)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "use_rocksdb")))]
+    fn it_hashes_nodes_with_a_custom_scheme() -> BinaryMerkleTreeResult<()> {
+        use starling::traits::HashingScheme;
+
+        let mut scheme = TaggedScheme::<TreeHasher>::new(b"DATA", b"LEAF", b"NODE");
+        scheme.commit_split_index = true;
+        scheme.commit_key = true;
+        scheme.commit_count = true;
+
+        // A single leaf is the root, so its location can be worked out by hand
+        let key = [0x42u8; KEY_LEN];
+        let value = vec![0x01u8];
+        let mut bmt = HashTree::<Vec<u8>>::with_scheme(160, scheme.clone())?;
+        let root = bmt.insert(None, &mut [&key], &mut [&value])?;
+        let data = scheme.data_location(&key, &value.encode()?);
+        assert_eq!(root, scheme.leaf_location(&key, &data));
+
+        let mut key_values = vec![];
+        let mut values = vec![];
+        for i in 0..16 {
+            key_values.push([(i << 4) as u8; KEY_LEN]);
+            values.push(vec![i as u8]);
+        }
+        let mut keys = key_values.iter().collect::<Vec<_>>();
+        let mut data = values.iter().collect::<Vec<_>>();
+        let root = bmt.insert(None, &mut keys, &mut data)?;
+        let mut default_bmt = HashTree::<Vec<u8>>::new(160)?;
+        assert_ne!(root, default_bmt.insert(None, &mut keys, &mut data)?);
+
+        let items = bmt.get(&root, &mut keys)?;
+        for (key, value) in key_values.iter().zip(values.iter()) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+
+        // Proofs only verify with the scheme of the tree
+        let proof = bmt
+            .get_proof(&root, &key_values[3])?
+            .expect("Failed to get proof");
+        assert!(verify_proof_with_scheme(
+            &scheme,
+            &root,
+            &key_values[3],
+            &values[3],
+            &proof
+        )?);
+        assert!(!verify_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root,
+            &key_values[3],
+            &values[3],
+            &proof
+        )?);
+
+        let absent_key = [0x35u8; KEY_LEN];
+        let absence_proof = bmt
+            .get_absence_proof(&root, &absent_key)?
+            .expect("Failed to get absence proof");
+        assert!(verify_absence_proof(
+            &scheme,
+            &root,
+            &absent_key,
+            &absence_proof
+        )?);
+
+        let mut proof_keys = vec![&key_values[0], &absent_key];
+        let multiproof = bmt
+            .get_multiproof(&root, &mut proof_keys)?
+            .expect("Failed to get multiproof");
+        let claims = vec![(&key_values[0], Some(&values[0])), (&absent_key, None)];
+        assert!(verify_multiproof_with_scheme(
+            &scheme,
+            &root,
+            &claims,
+            &multiproof
+        )?);
+
+        // A branch that claims a different count no longer hashes to the same location
+        let mut changed_proof = proof.clone();
+        changed_proof.branches[0].count += 1;
+        assert!(!verify_proof_with_scheme(
+            &scheme,
+            &root,
+            &key_values[3],
+            &values[3],
+            &changed_proof
+        )?);
+
+        let start = [0x31u8; KEY_LEN];
+        let end = [0x70u8; KEY_LEN];
+        let (entries, range_proof) = bmt
+            .get_range_proof(&root, &start, &end)?
+            .expect("Failed to get range proof");
+        let entry_refs = entries.iter().map(|(k, v)| (k, v)).collect::<Vec<_>>();
+        assert!(verify_range_proof_with_scheme(
+            &scheme,
+            &root,
+            &start,
+            &end,
+            &entry_refs,
+            &range_proof
+        )?);
+        assert!(!verify_range_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &root,
+            &start,
+            &end,
+            &entry_refs,
+            &range_proof
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "use_rocksdb")))]
+    fn it_commits_to_the_position_of_branches() -> BinaryMerkleTreeResult<()> {
+        use starling::traits::HashingScheme;
+
+        let seed = [0x75u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(64, &mut rng);
+        let mut key_refs = keys.iter().collect::<Vec<_>>();
+        let mut value_refs = values.iter().collect::<Vec<_>>();
+
+        // The default scheme is unchanged, so roots of existing trees stay the same
+        let mut legacy_bmt = HashTree::<Vec<u8>>::new(160)?;
+        let legacy_root = legacy_bmt.insert(None, &mut key_refs, &mut value_refs)?;
+        let mut default_bmt =
+            HashTree::<Vec<u8>>::with_scheme(160, TaggedScheme::<TreeHasher>::default())?;
+        assert_eq!(
+            default_bmt.insert(None, &mut key_refs, &mut value_refs)?,
+            legacy_root
+        );
+
+        let scheme = TaggedScheme::<TreeHasher>::committed();
+        let mut bmt = HashTree::<Vec<u8>>::with_scheme(160, scheme.clone())?;
+        let root = bmt.insert(None, &mut key_refs, &mut value_refs)?;
+        assert_ne!(root, legacy_root);
+
+        // Deleting keys gives the same root as inserting only the remaining keys
+        let mut deleted_keys = key_refs.iter().step_by(3).copied().collect::<Vec<_>>();
+        let deleted_root = bmt
+            .delete(&root, &mut deleted_keys)?
+            .expect("Failed to delete keys");
+        let mut remaining_keys = vec![];
+        let mut remaining_values = vec![];
+        for (i, (key, value)) in keys.iter().zip(values.iter()).enumerate() {
+            if i % 3 != 0 {
+                remaining_keys.push(key);
+                remaining_values.push(value);
+            }
+        }
+        let mut rebuilt_bmt = HashTree::<Vec<u8>>::with_scheme(160, scheme.clone())?;
+        assert_eq!(
+            rebuilt_bmt.insert(None, &mut remaining_keys, &mut remaining_values)?,
+            deleted_root
+        );
+
+        let proof = bmt
+            .get_proof(&deleted_root, &keys[1])?
+            .expect("Failed to get proof");
+        assert!(verify_proof_with_scheme(
+            &scheme,
+            &deleted_root,
+            &keys[1],
+            &values[1],
+            &proof
+        )?);
+        assert!(!verify_proof::<TreeHasher, _, KEY_LEN, HASH_LEN>(
+            &deleted_root,
+            &keys[1],
+            &values[1],
+            &proof
+        )?);
+
+        let absence_proof = bmt
+            .get_absence_proof(&deleted_root, &keys[0])?
+            .expect("Failed to get absence proof");
+        assert!(verify_absence_proof(
+            &scheme,
+            &deleted_root,
+            &keys[0],
+            &absence_proof
+        )?);
+
+        // Moving a branch to another split index no longer matches the root
+        let mut moved_proof = proof.clone();
+        let top_branch = moved_proof.branches.len() - 1;
+        moved_proof.branches[top_branch].split_index += 1;
+        assert!(!verify_proof_with_scheme(
+            &scheme,
+            &deleted_root,
+            &keys[1],
+            &values[1],
+            &moved_proof
+        )?);
+
+        // Only the bits of the branch key before the split index are covered
+        let zero = [0x01u8; HASH_LEN];
+        let one = [0x02u8; HASH_LEN];
+        let location = scheme.branch_location(&zero, &one, 12, &[0xFFu8; KEY_LEN], 2);
+        let mut same_prefix = [0x00u8; KEY_LEN];
+        same_prefix[0] = 0xFF;
+        same_prefix[1] = 0xF0;
+        assert_eq!(
+            scheme.branch_location(&zero, &one, 12, &same_prefix, 2),
+            location
+        );
+        same_prefix[1] = 0xE0;
+        assert_ne!(
+            scheme.branch_location(&zero, &one, 12, &same_prefix, 2),
+            location
+        );
+        assert_ne!(
+            scheme.branch_location(&zero, &one, 13, &[0xFFu8; KEY_LEN], 2),
+            location
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_the_store_unchanged_when_a_mutation_fails() -> BinaryMerkleTreeResult<()> {
+        let store: Store = Arc::new(Mutex::new(BTreeMap::new()));
+        let writes_left = Arc::new(AtomicUsize::new(usize::MAX));
+
+        let seed = [0x76u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(64, &mut rng);
+        let (old_keys, new_keys) = keys.split_at(32);
+        let mut insert_keys = old_keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values[..32].iter().collect::<Vec<_>>();
+        let db = FailingDB::with_writes_left(Arc::clone(&store), Arc::clone(&writes_left));
+        let mut bmt = FailingTree::from_db(db, 160)?;
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        // The same tree is used for every failed call and for the call that finally succeeds
+        let mut insert_keys = new_keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values[32..].iter().collect::<Vec<_>>();
+        let new_root = fail_until_done(&mut bmt, &store, &writes_left, |bmt| {
+            bmt.insert(Some(&root), &mut insert_keys, &mut insert_values)
+        })?;
+
+        let mut deleted_keys = old_keys.iter().step_by(2).collect::<Vec<_>>();
+        let deleted_root = fail_until_done(&mut bmt, &store, &writes_left, |bmt| {
+            bmt.delete(&new_root, &mut deleted_keys)
+        })?
+        .expect("Tree should not be empty");
+
+        // Removing every root in turn must leave nothing behind, which only holds if each
+        // mutation committed all of its reference counts
+        for removed_root in &[root, new_root, deleted_root] {
+            fail_until_done(&mut bmt, &store, &writes_left, |bmt| {
+                bmt.remove(removed_root)
+            })?;
+        }
+        assert!(snapshot(&store).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn it_rolls_back_a_failed_mutation() -> BinaryMerkleTreeResult<()> {
+        let store: Store = Arc::new(Mutex::new(BTreeMap::new()));
+        let mut bmt = FailingTree::from_db(FailingDB::new(Arc::clone(&store), usize::MAX), 160)?;
+
+        let seed = [0x77u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(32, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+        let before = snapshot(&store);
+
+        // Duplicate keys are only found after the leaves and references have been queued
+        let duplicate_key = [0x00u8; KEY_LEN];
+        let value = vec![0x01u8];
+        let mut duplicate_keys = vec![&duplicate_key, &duplicate_key];
+        let mut duplicate_values = vec![&value, &value];
+        assert!(bmt
+            .insert(Some(&root), &mut duplicate_keys, &mut duplicate_values)
+            .is_err());
+        assert_eq!(snapshot(&store), before);
+
+        // A changeset that does not reach its recorded root is not written
+        let changeset = Changeset::new(Some(root), [0xFFu8; HASH_LEN], vec![(keys[0], None)]);
+        assert!(bmt.apply_changeset(changeset).is_err());
+        assert_eq!(snapshot(&store), before);
+
+        // Nothing from the failed operations is left to be written with the next one
+        bmt.remove(&root)?;
+        assert!(snapshot(&store).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn it_writes_nothing_after_a_failure_it_can_not_roll_back() -> BinaryMerkleTreeResult<()> {
+        /// A `FailingDB` that keeps the default `rollback`, so its changes can not be discarded.
+        struct NoRollbackDB(FailingDB);
+
+        impl Database<HASH_LEN> for NoRollbackDB {
+            type NodeType = TreeNode;
+            type EntryType = ([u8; HASH_LEN], Vec<u8>);
+
+            fn open(path: &PathBuf) -> Result<Self, Exception> {
+                Ok(Self(FailingDB::open(path)?))
+            }
+
+            fn get_node(&self, key: &[u8; HASH_LEN]) -> Result<Option<TreeNode>, Exception> {
+                self.0.get_node(key)
+            }
+
+            fn locations(&self) -> Result<Vec<[u8; HASH_LEN]>, Exception> {
+                self.0.locations()
+            }
+
+            fn insert(&mut self, key: [u8; HASH_LEN], node: TreeNode) -> Result<(), Exception> {
+                self.0.insert(key, node)
+            }
+
+            fn remove(&mut self, key: &[u8; HASH_LEN]) -> Result<(), Exception> {
+                self.0.remove(key)
+            }
+
+            fn batch_write(&mut self) -> Result<(), Exception> {
+                self.0.batch_write()
+            }
+        }
+
+        let store: Store = Arc::new(Mutex::new(BTreeMap::new()));
+        let writes_left = Arc::new(AtomicUsize::new(usize::MAX));
+        let db = FailingDB::with_writes_left(Arc::clone(&store), Arc::clone(&writes_left));
+        let mut bmt = MerkleBIT::<
+            NoRollbackDB,
+            TreeBranch,
+            TreeLeaf,
+            TreeData,
+            TreeNode,
+            TreeHasher,
+            Vec<u8>,
+        >::from_db(NoRollbackDB(db), 160)?;
+
+        let seed = [0x81u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(32, &mut rng);
+        let mut insert_keys = keys[..16].iter().collect::<Vec<_>>();
+        let mut insert_values = values[..16].iter().collect::<Vec<_>>();
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+        let before = snapshot(&store);
+
+        // The failed insert leaves some of its changes queued
+        let mut insert_keys = keys[16..].iter().collect::<Vec<_>>();
+        let mut insert_values = values[16..].iter().collect::<Vec<_>>();
+        writes_left.store(4, Ordering::SeqCst);
+        assert!(bmt
+            .insert(Some(&root), &mut insert_keys, &mut insert_values)
+            .is_err());
+        assert_eq!(snapshot(&store), before);
+
+        // They must not be written with the next operation, even one that would succeed
+        writes_left.store(usize::MAX, Ordering::SeqCst);
+        assert!(bmt
+            .insert(Some(&root), &mut insert_keys, &mut insert_values)
+            .is_err());
+        assert!(bmt.remove(&root).is_err());
+        assert_eq!(snapshot(&store), before);
+        Ok(())
+    }
+
+    #[test]
+    fn it_shares_branches_recreated_by_another_tree() -> BinaryMerkleTreeResult<()> {
+        use starling::traits::{Branch, Node, NodeVariant};
+
+        let store: Store = Arc::new(Mutex::new(BTreeMap::new()));
+        let mut bmt = FailingTree::from_db(FailingDB::new(Arc::clone(&store), usize::MAX), 160)?;
+        let references = |location: &[u8; HASH_LEN]| snapshot(&store)[location].get_references();
+        let children =
+            |location: &[u8; HASH_LEN]| match snapshot(&store)[location].clone().get_variant() {
+                NodeVariant::Branch(b) => (*b.get_zero(), *b.get_one()),
+                NodeVariant::Leaf(_) | NodeVariant::Data(_) => panic!("Expected a branch"),
+            };
+
+        let key_values = vec![[0x00u8; KEY_LEN], [0x01u8; KEY_LEN], [0x80u8; KEY_LEN]];
+        let mut keys = key_values.iter().collect::<Vec<_>>();
+        let values = vec![vec![0x00u8], vec![0x01u8], vec![0x02u8]];
+        let mut data = values.iter().collect::<Vec<_>>();
+
+        // The second tree holds the whole first tree as the zero child of its root
+        let first_root = bmt.insert(None, &mut keys[..2], &mut data[..2])?;
+        let second_root = bmt.insert(None, &mut keys, &mut data)?;
+        assert_eq!(children(&second_root).0, first_root);
+        let (zero_leaf, one_leaf) = children(&first_root);
+        assert_eq!(references(&first_root), 2);
+        assert_eq!(references(&zero_leaf), 1);
+        assert_eq!(references(&one_leaf), 1);
+        assert!(bmt.check(&[first_root, second_root], false)?.is_clean());
+
+        // Removing either tree leaves the shared branch to the other
+        bmt.remove(&first_root)?;
+        assert_eq!(references(&first_root), 1);
+        assert!(bmt.check(&[second_root], false)?.is_clean());
+        bmt.remove(&second_root)?;
+        assert!(snapshot(&store).is_empty());
+
+        // A stored branch whose child has no references to give back is reported as corrupt
+        let first_root = bmt.insert(None, &mut keys[..2], &mut data[..2])?;
+        let (zero_leaf, _) = children(&first_root);
+        let orphan = [0xEEu8; HASH_LEN];
+        {
+            let mut committed = store.lock().expect("Store is poisoned");
+            let mut orphan_node = committed[&zero_leaf].clone();
+            orphan_node.set_references(0);
+            committed.insert(orphan, orphan_node);
+            let root_node = committed[&first_root].clone();
+            let root_references = root_node.get_references();
+            if let NodeVariant::Branch(mut b) = root_node.get_variant() {
+                b.set_one(orphan);
+                let mut changed_node = TreeNode::new(NodeVariant::Branch(b));
+                changed_node.set_references(root_references);
+                committed.insert(first_root, changed_node);
+            }
+        }
+        let before = snapshot(&store);
+        assert!(bmt.insert(None, &mut keys, &mut data).is_err());
+        assert_eq!(snapshot(&store), before);
+        Ok(())
+    }
+
+    #[test]
+    fn it_counts_references_of_reinserted_leaves_once() -> BinaryMerkleTreeResult<()> {
+        let store: Store = Arc::new(Mutex::new(BTreeMap::new()));
+        let mut bmt = FailingTree::from_db(FailingDB::new(Arc::clone(&store), usize::MAX), 160)?;
+
+        let seed = [0x7Fu8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(32, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+
+        let first_root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+        // The same items again, from scratch and on top of the first root
+        let second_root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+        let third_root = bmt.insert(
+            Some(&first_root),
+            &mut insert_keys[..8],
+            &mut insert_values[..8],
+        )?;
+        assert_eq!(second_root, first_root);
+        assert_eq!(third_root, first_root);
+        let changed_value = vec![0xFFu8];
+        let fourth_root = bmt.insert(Some(&first_root), &mut [&keys[0]], &mut [&changed_value])?;
+
+        let live_roots = [first_root, second_root, third_root, fourth_root];
+        assert!(bmt.check(&live_roots, false)?.is_clean());
+
+        for root in live_roots.iter() {
+            bmt.remove(root)?;
+        }
+        assert!(snapshot(&store).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn it_queues_removals_until_batch_write() -> BinaryMerkleTreeResult<()> {
+        use starling::traits::{Data, NodeVariant};
+
+        let mut db: HashTreeDB = Database::open(&PathBuf::new())?;
+        let location = [0x01u8; HASH_LEN];
+        let mut data = TreeData::new();
+        data.set_value(&[0x02u8]);
+        let node = TreeNode::new(NodeVariant::Data(data));
+        db.insert(location, node.clone())?;
+        db.batch_write()?;
+
+        // Queued removals are seen straight away, but can still be discarded
+        db.remove(&location)?;
+        assert_eq!(db.get_node(&location)?, None);
+        db.rollback()?;
+        assert_eq!(db.get_node(&location)?, Some(node));
+
+        db.remove(&location)?;
+        db.batch_write()?;
+        assert_eq!(db.get_node(&location)?, None);
+
+        let batch_writes = Arc::new(AtomicUsize::new(0));
+        let db = CountingDB::new(Arc::new(AtomicUsize::new(0)), Arc::clone(&batch_writes))?;
+        let mut bmt = CountingTree::from_db(db, 160)?;
+
+        let seed = [0x78u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(256, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        // Every node under the root is removed in a single batch
+        bmt.remove(&root)?;
+        assert_eq!(batch_writes.load(Ordering::SeqCst), 2);
+        let items = bmt.get(&root, &mut insert_keys)?;
+        assert!(items.values().all(Option::is_none));
+        Ok(())
+    }
+
+    #[test]
+    fn it_rolls_back_a_failed_removal_from_a_hash_database() -> BinaryMerkleTreeResult<()> {
+        let fail_removals = Arc::new(AtomicBool::new(false));
+        let db = CountingDB::new(Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)))?
+            .with_failing_removals(Arc::clone(&fail_removals));
+        let mut bmt = CountingTree::from_db(db, 160)?;
+
+        let seed = [0x80u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(64, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+        let root = bmt.insert(None, &mut insert_keys, &mut insert_values)?;
+
+        // The root is queued for removal before the failure, so it must be restored by rollback
+        fail_removals.store(true, Ordering::SeqCst);
+        assert!(bmt.remove(&root).is_err());
+        fail_removals.store(false, Ordering::SeqCst);
+
+        let items = bmt.get(&root, &mut insert_keys)?;
+        for (key, value) in keys.iter().zip(values.iter()) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        assert!(bmt.check(&[root], false)?.is_clean());
+
+        bmt.remove(&root)?;
+        assert!(bmt.check(&[], false)?.is_clean());
+        let items = bmt.get(&root, &mut insert_keys)?;
+        assert!(items.values().all(Option::is_none));
+        Ok(())
+    }
+
+    #[test]
+    fn it_checks_and_repairs_references() -> BinaryMerkleTreeResult<()> {
+        use starling::traits::{Leaf, Node, NodeVariant};
+
+        let store: Store = Arc::new(Mutex::new(BTreeMap::new()));
+        let mut bmt = FailingTree::from_db(FailingDB::new(Arc::clone(&store), usize::MAX), 160)?;
+
+        let seed = [0x79u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(48, &mut rng);
+        let mut insert_keys = keys.iter().collect::<Vec<_>>();
+        let mut insert_values = values.iter().collect::<Vec<_>>();
+        let first_root = bmt.insert(None, &mut insert_keys[..32], &mut insert_values[..32])?;
+        let second_root = bmt.insert(
+            Some(&first_root),
+            &mut insert_keys[32..],
+            &mut insert_values[32..],
+        )?;
+        let mut deleted_keys = insert_keys[..8].to_vec();
+        let third_root = bmt
+            .delete(&second_root, &mut deleted_keys)?
+            .expect("Tree should not be empty");
+        let live_roots = [first_root, second_root, third_root];
+        assert!(bmt.check(&live_roots, false)?.is_clean());
+
+        // Leaving a root out makes the nodes only it holds unreachable, and drops a reference
+        // from the nodes it shares
+        let report = bmt.check(&live_roots[1..], false)?;
+        assert!(report.unreachable.contains(&first_root));
+        assert!(!report.mismatched_references.is_empty());
+
+        let missing_root = [0xDDu8; HASH_LEN];
+        let report = bmt.check(&[missing_root], false)?;
+        assert_eq!(report.dangling, vec![(None, missing_root)]);
+
+        // Damage the store in each of the ways the check looks for
+        let mut leaf = None;
+        let mut data_locations = vec![];
+        for (location, node) in snapshot(&store) {
+            match node.get_variant() {
+                NodeVariant::Leaf(l) if leaf.is_none() => leaf = Some((location, l)),
+                NodeVariant::Data(_) => data_locations.push(location),
+                _ => {}
+            }
+        }
+        let (leaf_location, leaf_node) = leaf.expect("Failed to find a leaf");
+        let missing_data = *leaf_node.get_data();
+        let corrupt_data = data_locations
+            .into_iter()
+            .find(|location| *location != missing_data)
+            .expect("Failed to find another data node");
+        let orphan = [0xEEu8; HASH_LEN];
+        {
+            let mut committed = store.lock().expect("Store is poisoned");
+            let root_node = committed
+                .get_mut(&second_root)
+                .expect("Failed to find root");
+            root_node.set_references(7);
+            committed.remove(&missing_data);
+            let leaf_copy = committed[&leaf_location].clone();
+            committed.insert(corrupt_data, leaf_copy.clone());
+            committed.insert(orphan, leaf_copy);
+        }
+
+        let report = bmt.check(&live_roots, false)?;
+        assert!(!report.is_clean());
+        assert!(!report.repaired);
+        assert_eq!(report.mismatched_references, vec![(second_root, 7, 1)]);
+        assert_eq!(report.unreachable, vec![orphan]);
+        assert_eq!(report.dangling, vec![(Some(leaf_location), missing_data)]);
+        assert_eq!(report.corrupt, vec![corrupt_data]);
+
+        // Repairing only rewrites the references
+        let report = bmt.check(&live_roots, true)?;
+        assert!(report.repaired);
+        let report = bmt.check(&live_roots, false)?;
+        assert!(report.mismatched_references.is_empty());
+        assert_eq!(report.unreachable, vec![orphan]);
+        assert_eq!(report.dangling.len(), 1);
+        assert_eq!(report.corrupt.len(), 1);
+        Ok(())
+    }
+
+    fn generate_path(seed: [u8; KEY_LEN]) -> PathBuf {
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let suffix = rng.gen_range(1000, 10000);
+        let path_string = format!("Test_DB_{}", suffix);
+        PathBuf::from(path_string)
+    }
+
+    #[cfg(not(any(feature = "use_rocksdb")))]
+    fn open_with_scheme(
+        _path: &PathBuf,
+        scheme: TaggedScheme<TreeHasher>,
+    ) -> BinaryMerkleTreeResult<Tree> {
+        Tree::with_scheme(160, scheme)
+    }
+
+    #[cfg(feature = "use_rocksdb")]
+    fn open_with_scheme(
+        path: &PathBuf,
+        scheme: TaggedScheme<TreeHasher>,
+    ) -> BinaryMerkleTreeResult<Tree> {
+        Tree::from_db_with_scheme(RocksDB::open(path)?, 160, scheme)
+    }
+
+    fn tear_down(_path: &PathBuf) {
+        #[cfg(feature = "use_rocksdb")]
+        use std::fs::remove_dir_all;
+
+        #[cfg(feature = "use_rocksdb")]
+        remove_dir_all(&_path).unwrap();
+    }
+
+    fn prepare_inserts(num_entries: usize, rng: &mut StdRng) -> (Vec<[u8; KEY_LEN]>, Vec<Vec<u8>>) {
+        let mut keys = Vec::with_capacity(num_entries);
+        let mut data = Vec::with_capacity(num_entries);
+        for _ in 0..num_entries {
+            let mut key_value = [0u8; KEY_LEN];
+            rng.fill(&mut key_value);
+            keys.push(key_value);
+
+            let mut data_value = [0u8; KEY_LEN];
+            rng.fill(data_value.as_mut());
+            data.push(data_value.to_vec());
+        }
+
+        keys.sort();
+
+        (keys, data)
+    }
+
+    fn decode_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("Invalid hex"))
+            .collect()
+    }
+
+    #[cfg(any(
+        feature = "use_sha2",
+        feature = "use_rust_sha2",
+        feature = "use_blake3"
+    ))]
+    fn hash_with<HasherType, const N: usize>(input: &[u8]) -> [u8; N]
+    where
+        HasherType: starling::traits::Hasher<N, HashType = HasherType>,
+    {
+        let mut hasher = HasherType::new(N);
+        hasher.update(input);
+        hasher.finalize()
     }
 
     fn iterate_inserts(